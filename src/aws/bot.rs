@@ -1,6 +1,8 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+use std::net::IpAddr;
+
 const BOT_NAMES: [&str; 70] = [
     "admedia bot",
     "adsbot-google",
@@ -262,3 +264,148 @@ pub fn user_agent_is_bot(user_agent: &String) -> bool {
             .is_some()
     }
 }
+
+/// Search engine crawlers which are allowed by default.
+const SEARCH_ENGINE_NAMES: [&str; 9] = [
+    "adsbot-google",
+    "applebot",
+    "baiduspider",
+    "bingbot",
+    "duckduckbot",
+    "googlebot",
+    "msnbot",
+    "yahoo! slurp",
+    "yandexbot",
+];
+
+/// The kind of client a user agent claims to be.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BotKind {
+    /// A crawler in the allowlist, e.g. Googlebot, which may be served SSR content.
+    SearchEngine,
+    /// Any other bot or crawler.
+    Scraper,
+    /// Not a known bot (possibly a human).
+    Unknown,
+}
+
+/// Classifies user agents as search engines (per an allowlist), scrapers, or unknown.
+#[derive(Clone, Debug)]
+pub struct BotClassifier {
+    /// Lower case substrings of the user agents of search engines.
+    search_engines: Vec<String>,
+}
+
+impl Default for BotClassifier {
+    fn default() -> Self {
+        Self {
+            search_engines: SEARCH_ENGINE_NAMES.iter().map(|&n| n.to_owned()).collect(),
+        }
+    }
+}
+
+impl BotClassifier {
+    /// Create a classifier which allows Googlebot, Bingbot, and other major search engines.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a classifier which allows only user agents containing the specified names.
+    pub fn with_allowlist<S: Into<String>>(names: impl IntoIterator<Item = S>) -> Self {
+        let classifier = Self {
+            search_engines: Vec::new(),
+        };
+        names
+            .into_iter()
+            .fold(classifier, |classifier, name| classifier.allow(name))
+    }
+
+    /// Also allow user agents containing the specified name, e.g. "slackbot".
+    pub fn allow(mut self, name: impl Into<String>) -> Self {
+        self.search_engines.push(name.into().to_lowercase());
+        self
+    }
+
+    /// Given a user agent, determine what kind of bot, if any, it claims to be.
+    pub fn classify(&self, user_agent: &str) -> BotKind {
+        let lower_case = user_agent.to_lowercase();
+        if self
+            .search_engines
+            .iter()
+            .any(|name| lower_case.contains(name.as_str()))
+        {
+            BotKind::SearchEngine
+        } else if user_agent_is_bot(&user_agent.to_owned()) {
+            BotKind::Scraper
+        } else {
+            BotKind::Unknown
+        }
+    }
+}
+
+/// Search engines which can be verified by DNS, and the domains of their crawlers.
+const VERIFIABLE_BOTS: [(&str, &[&str]); 7] = [
+    ("adsbot-google", &["google.com", "googlebot.com"]),
+    ("applebot", &["applebot.apple.com"]),
+    ("baiduspider", &["baidu.com", "baidu.jp"]),
+    ("bingbot", &["search.msn.com"]),
+    ("googlebot", &["google.com", "googlebot.com"]),
+    ("msnbot", &["search.msn.com"]),
+    ("yandexbot", &["yandex.com", "yandex.net", "yandex.ru"]),
+];
+
+/// The result of verifying the IP address of a user agent which claims to be a bot.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BotVerification {
+    /// The IP address belongs to the search engine the user agent claims to be.
+    Verified,
+    /// The IP address does not belong to (or could not be confirmed to belong to) the
+    /// search engine the user agent claims to be, so the user agent is likely spoofed.
+    Unverified,
+    /// The user agent does not claim to be a search engine which can be verified.
+    NotApplicable,
+}
+
+/// Returns true if `hostname` is `domain` or a subdomain of it.
+pub(crate) fn hostname_in_domain(hostname: &str, domain: &str) -> bool {
+    let hostname = hostname.trim_end_matches('.');
+    hostname.eq_ignore_ascii_case(domain)
+        || hostname
+            .len()
+            .checked_sub(domain.len() + 1)
+            .is_some_and(|dot| {
+                hostname.as_bytes()[dot] == b'.' && hostname[dot + 1..].eq_ignore_ascii_case(domain)
+            })
+}
+
+/// Given a user agent which claims to be a search engine, e.g. Googlebot, verify the
+/// claim by a reverse DNS lookup of the IP address, which must yield a hostname in the
+/// search engine's domain (e.g. `googlebot.com`), followed by a forward DNS lookup of
+/// that hostname, which must yield the same IP address.
+pub async fn verify_bot_ip(user_agent: &str, ip: IpAddr) -> BotVerification {
+    let lower_case = user_agent.to_lowercase();
+    let Some(&(_, domains)) = VERIFIABLE_BOTS
+        .iter()
+        .find(|(name, _)| lower_case.contains(name))
+    else {
+        return BotVerification::NotApplicable;
+    };
+    // The lookups are blocking.
+    let verified = tokio::task::spawn_blocking(move || {
+        let Ok(hostname) = dns_lookup::lookup_addr(&ip) else {
+            return false;
+        };
+        domains
+            .iter()
+            .any(|domain| hostname_in_domain(&hostname, domain))
+            && dns_lookup::lookup_host(hostname.trim_end_matches('.'))
+                .is_ok_and(|ips| ips.contains(&ip))
+    })
+    .await
+    .unwrap_or(false);
+    if verified {
+        BotVerification::Verified
+    } else {
+        BotVerification::Unverified
+    }
+}
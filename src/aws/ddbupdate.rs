@@ -1,10 +1,13 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+use super::dynamo::with_ddb_retries;
 use super::{to_dynamo_av, DynamoDbClient};
 use crate::common::{DynamoError, Error};
 use aws_sdk_dynamodb::operation::update_item::builders::UpdateItemFluentBuilder;
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValue};
 use hyper::StatusCode;
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::collections::HashSet;
 
@@ -25,6 +28,7 @@ pub fn ddb_ranged_update<T: Serialize, U: Serialize>(
         .condition_expression(&format!("attribute_exists(#{hash_name})"))
         .expression_attribute_names(&format!("#{hash_name}"), hash_name);
     Ok(DynamoUpdateBuilder {
+        additions: Default::default(),
         ddb_builder,
         expressions: Default::default(),
         keys: vec![hash_name.to_string(), range_name.to_string()]
@@ -49,6 +53,7 @@ pub fn ddb_update<T: Serialize>(
         .condition_expression(&format!("attribute_exists(#{hash_name})"))
         .expression_attribute_names(&format!("#{hash_name}"), hash_name);
     Ok(DynamoUpdateBuilder {
+        additions: Default::default(),
         ddb_builder,
         expressions: Default::default(),
         keys: vec![hash_name.to_string()].into_iter().collect(),
@@ -57,8 +62,33 @@ pub fn ddb_update<T: Serialize>(
     })
 }
 
+/// Which attributes `DynamoUpdateBuilder::send_returning` should return.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UpdateReturnValues {
+    /// All attributes of the item as they were before the update.
+    AllOld,
+    /// All attributes of the item as they are after the update.
+    AllNew,
+    /// Only the updated attributes, as they were before the update.
+    UpdatedOld,
+    /// Only the updated attributes, as they are after the update.
+    UpdatedNew,
+}
+
+impl UpdateReturnValues {
+    fn to_return_value(self) -> ReturnValue {
+        match self {
+            Self::AllOld => ReturnValue::AllOld,
+            Self::AllNew => ReturnValue::AllNew,
+            Self::UpdatedOld => ReturnValue::UpdatedOld,
+            Self::UpdatedNew => ReturnValue::UpdatedNew,
+        }
+    }
+}
+
 /// Builder for Dynamo DB update.
 pub struct DynamoUpdateBuilder {
+    additions: Vec<(String, String)>,
     ddb_builder: UpdateItemFluentBuilder,
     expressions: Vec<String>,
     keys: HashSet<String>,
@@ -67,6 +97,32 @@ pub struct DynamoUpdateBuilder {
 }
 
 impl DynamoUpdateBuilder {
+    /// Specify a string set attribute to which the values will be added, creating
+    /// the set if it doesn't exist.  Values already in the set are ignored.
+    pub fn add_to_set<S: Into<String>>(
+        mut self,
+        attribute_name: &str,
+        values: impl IntoIterator<Item = S>,
+    ) -> Result<Self, Error> {
+        let values: Vec<String> = values.into_iter().map(Into::into).collect();
+        if values.is_empty() {
+            // Dynamo DB rejects empty sets.
+            return Err(Error::Http(
+                StatusCode::BAD_REQUEST,
+                format!("{attribute_name}: no values to add to set"),
+            ));
+        }
+        self.validate_unique_key(attribute_name)?;
+        let name_key = format!("#{attribute_name}");
+        let value_key = format!(":{attribute_name}");
+        self.ddb_builder = self
+            .ddb_builder
+            .expression_attribute_names(&name_key, attribute_name)
+            .expression_attribute_values(&value_key, AttributeValue::Ss(values));
+        self.additions.push((name_key, value_key));
+        Ok(self)
+    }
+
     /// Specify an attribute for the update that will always be set.
     pub fn attribute<T: Serialize>(
         mut self,
@@ -84,6 +140,34 @@ impl DynamoUpdateBuilder {
         Ok(self)
     }
 
+    /// Specify a list attribute to which the values will be appended, creating
+    /// the list if it doesn't exist.
+    pub fn list_append<T: Serialize>(
+        mut self,
+        attribute_name: &str,
+        values: impl IntoIterator<Item = T>,
+    ) -> Result<Self, Error> {
+        self.validate_unique_key(attribute_name)?;
+        let values = values
+            .into_iter()
+            .map(to_dynamo_av)
+            .collect::<Result<Vec<_>, _>>()?;
+        let name_key = format!("#{attribute_name}");
+        let value_key = format!(":{attribute_name}");
+        // Prefixed so as not to collide with the value of an attribute, e.g. one named
+        // "{attribute_name}_empty_list".
+        let empty_key = format!(":__empty_list_{attribute_name}");
+        self.ddb_builder = self
+            .ddb_builder
+            .expression_attribute_names(&name_key, attribute_name)
+            .expression_attribute_values(&value_key, AttributeValue::L(values))
+            .expression_attribute_values(&empty_key, AttributeValue::L(Vec::new()));
+        self.expressions.push(format!(
+            "{name_key} = list_append(if_not_exists({name_key}, {empty_key}), {value_key})"
+        ));
+        Ok(self)
+    }
+
     /// Specify an optional attribute for the update.
     pub fn optional_attribute<T: Serialize>(
         mut self,
@@ -122,48 +206,92 @@ impl DynamoUpdateBuilder {
         }
     }
 
-    /// Start the Dynamo DB update.
-    pub async fn send(self) -> Result<String, DynamoError> {
-        let updates = if self.updates.is_empty() && self.expressions.is_empty() {
-            Default::default()
-        } else {
-            format!(
+    /// Returns the update expression, e.g. "SET #x = :x ADD #y :y REMOVE #z".
+    pub(super) fn expression(&self) -> String {
+        let mut clauses = Vec::new();
+        if !self.updates.is_empty() || !self.expressions.is_empty() {
+            clauses.push(format!(
                 "SET {}",
                 self.updates
                     .iter()
                     .map(|(name_key, value_key)| format!("{name_key} = {value_key}"))
-                    .chain(self.expressions.into_iter())
+                    .chain(self.expressions.iter().cloned())
                     .collect::<Vec<_>>()
                     .join(", ")
-            )
-        };
-        let removals = if self.removals.is_empty() {
-            Default::default()
-        } else {
-            format!("REMOVE {}", self.removals.join(", "))
-        };
-        let expr = if updates.is_empty() && removals.is_empty() {
-            Default::default()
-        } else if removals.is_empty() {
-            updates
-        } else if updates.is_empty() {
-            removals
-        } else {
-            format!("{updates} {removals}")
-        };
+            ));
+        }
+        if !self.additions.is_empty() {
+            clauses.push(format!(
+                "ADD {}",
+                self.additions
+                    .iter()
+                    .map(|(name_key, value_key)| format!("{name_key} {value_key}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if !self.removals.is_empty() {
+            clauses.push(format!("REMOVE {}", self.removals.join(", ")));
+        }
+        clauses.join(" ")
+    }
+
+    /// Start the Dynamo DB update.
+    pub async fn send(self) -> Result<String, DynamoError> {
+        let expr = self.expression();
         if !expr.is_empty() {
-            self.ddb_builder
-                .update_expression(&expr)
-                .send()
-                .await
-                .map_err(|e| {
-                    let e: DynamoError = e.into();
-                    e
-                })?;
+            let request = self.ddb_builder.update_expression(&expr);
+            with_ddb_retries(|| request.clone().send()).await?;
         }
         Ok(expr)
     }
 
+    /// Start the Dynamo DB update and return the attributes specified by `return_values`,
+    /// for example the old values which the update replaced.  Returns `None` if there
+    /// was nothing to update, or (for `AllOld`) if the item had no attributes.
+    pub async fn send_returning<O: DeserializeOwned>(
+        self,
+        return_values: UpdateReturnValues,
+    ) -> Result<Option<O>, Error> {
+        let expr = self.expression();
+        if expr.is_empty() {
+            return Ok(None);
+        }
+        let request = self
+            .ddb_builder
+            .update_expression(&expr)
+            .return_values(return_values.to_return_value());
+        let output = with_ddb_retries(|| request.clone().send())
+            .await
+            .map_err(|e| Error::Dynamo(e, format!("update_item({expr})")))?;
+        match output.attributes {
+            Some(attributes) if !attributes.is_empty() => serde_dynamo::from_item(attributes)
+                .map(Some)
+                .map_err(Error::Serde),
+            _ => Ok(None),
+        }
+    }
+
+    /// Specify an attribute that will only be set if it doesn't already exist,
+    /// for example to initialize a counter without clobbering it later.
+    pub fn set_if_not_exists<T: Serialize>(
+        mut self,
+        attribute_name: &str,
+        default_value: T,
+    ) -> Result<Self, Error> {
+        self.validate_unique_key(attribute_name)?;
+        let name_key = format!("#{attribute_name}");
+        let value_key = format!(":{attribute_name}");
+        self.ddb_builder = self
+            .ddb_builder
+            .expression_attribute_names(&name_key, attribute_name)
+            .expression_attribute_values(&value_key, to_dynamo_av(default_value)?);
+        self.expressions.push(format!(
+            "{name_key} = if_not_exists({name_key}, {value_key})"
+        ));
+        Ok(self)
+    }
+
     /// Specify an attribute that wont be set if it equals its default value.
     pub fn skippable_attribute<T: Default + PartialEq + Serialize>(
         self,
@@ -1,25 +1,86 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use crate::common::{CubConfig, Error};
+use crate::common::{CubConfig, DynamoError, Error};
 use aws_config::profile::ProfileFileRegionProvider;
-use aws_config::{BehaviorVersion, ConfigLoader, SdkConfig};
-use aws_sdk_dynamodb::types::AttributeValue;
+use aws_config::{BehaviorVersion, ConfigLoader, Region, SdkConfig};
+use aws_sdk_dynamodb::config::Credentials;
+use aws_sdk_dynamodb::types::{AttributeValue, ConsumedCapacity, ReturnConsumedCapacity};
 use aws_sdk_dynamodb::Client;
+use rand::Rng;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_dynamo::Item;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
 use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "ddb_metrics")]
+use std::sync::RwLock;
+use std::time::Duration;
 
 /// A convenient alias for Dynamo DB client so consuming code doesn't need to add it to `Cargo.toml`
 pub type DynamoDbClient = aws_sdk_dynamodb::Client;
 
-/// Create an AWS config loader with profile and region.
+/// Backoff before the first retry of a throttled Dynamo DB operation, which doubles thereafter.
+const DDB_BACKOFF_MILLIS: u64 = 25;
+/// Maximum backoff before retrying a throttled Dynamo DB operation.
+const DDB_MAX_BACKOFF_MILLIS: u64 = 2000;
+
+/// Consistency of Dynamo DB reads.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ReadConsistency {
+    /// Eventually consistent reads cost half as much, but may not reflect recent writes.
+    /// (They are also the only option for global secondary indexes.)
+    Eventual,
+    /// Strongly consistent reads reflect all prior successful writes.
+    #[default]
+    Strong,
+}
+
+impl ReadConsistency {
+    fn is_strong(&self) -> bool {
+        matches!(self, Self::Strong)
+    }
+}
+
+/// See `set_ddb_max_retries`.
+static DDB_MAX_RETRIES: AtomicUsize = AtomicUsize::new(3);
+
+/// Dynamo DB capacity consumed by an operation, as reported to the hook set by
+/// `set_ddb_metrics_hook`.
+#[cfg(feature = "ddb_metrics")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DdbConsumedCapacity {
+    /// Dynamo DB operation, e.g. `get_item`, `put_item`, or `query` (which reports
+    /// each page separately).
+    pub operation: &'static str,
+    /// Table name.
+    pub table: &'static str,
+    /// Total (read and write) capacity units consumed.
+    pub capacity_units: f64,
+}
+
+#[cfg(feature = "ddb_metrics")]
+type DdbMetricsHook = Box<dyn Fn(&DdbConsumedCapacity) + Send + Sync>;
+
+/// See `set_ddb_metrics_hook`.
+#[cfg(feature = "ddb_metrics")]
+static DDB_METRICS_HOOK: RwLock<Option<DdbMetricsHook>> = RwLock::new(None);
+
+/// Create an AWS config loader with profile and region.  The region is that of the
+/// `region` in the `aws` config, if any, otherwise that of the profile, if any.  The
+/// `aws` config may also specify static credentials, `access_key_id` and
+/// `secret_access_key`, e.g. dummy ones for a local Dynamo DB (see `new_ddb_client`).
 pub fn create_aws_config_loader(cub_config: &CubConfig) -> ConfigLoader {
     #[derive(Deserialize)]
     struct AwsConfig {
+        access_key_id: Option<String>,
         profile: Option<String>,
+        region: Option<String>,
+        secret_access_key: Option<String>,
     }
     #[derive(Deserialize)]
     struct ConfigToml {
@@ -27,11 +88,27 @@ pub fn create_aws_config_loader(cub_config: &CubConfig) -> ConfigLoader {
     }
     let mut config_loader = aws_config::defaults(BehaviorVersion::v2024_03_28());
     if let Ok(ConfigToml {
-        aws: AwsConfig {
-            profile: profile_name,
-        },
+        aws:
+            AwsConfig {
+                access_key_id,
+                profile: profile_name,
+                region,
+                secret_access_key,
+            },
     }) = cub_config.get()
     {
+        if let (Some(access_key_id), Some(secret_access_key)) = (access_key_id, secret_access_key) {
+            if cub_config.debug() {
+                println!("AWS using static credentials {access_key_id}");
+            }
+            config_loader = config_loader.credentials_provider(Credentials::new(
+                access_key_id,
+                secret_access_key,
+                None,
+                None,
+                "cub_config",
+            ));
+        }
         if let Some(profile_name) = profile_name {
             if cub_config.debug() {
                 println!("AWS using profile name {profile_name}");
@@ -41,21 +118,71 @@ pub fn create_aws_config_loader(cub_config: &CubConfig) -> ConfigLoader {
                 .build();
             config_loader = config_loader.profile_name(&profile_name).region(region)
         }
+        if let Some(region) = region {
+            if cub_config.debug() {
+                println!("AWS using region {region}");
+            }
+            config_loader = config_loader.region(Region::new(region));
+        }
     };
     // TODO: let options = Options::from_args();
     config_loader
 }
 
+/// Optional overrides of the AWS endpoint, e.g. to test against MinIO or LocalStack.
+#[derive(Default, Deserialize)]
+pub(crate) struct AwsEndpointConfig {
+    /// Replaces the AWS endpoint URL of Dynamo DB and S3 clients.
+    pub endpoint_url: Option<String>,
+    /// Whether S3 clients address buckets by path rather than subdomain.
+    #[serde(default)]
+    pub force_path_style: bool,
+}
+
+impl AwsEndpointConfig {
+    /// Reads the optional `endpoint_url` and `force_path_style` of the `aws` section.
+    pub(crate) fn new(cub_config: &CubConfig) -> Self {
+        #[derive(Deserialize)]
+        struct ConfigToml {
+            aws: AwsEndpointConfig,
+        }
+        let endpoint_config = cub_config
+            .get()
+            .map(|ConfigToml { aws }| aws)
+            .unwrap_or_default();
+        if cub_config.debug() {
+            if let Some(endpoint_url) = &endpoint_config.endpoint_url {
+                println!("AWS using endpoint URL {endpoint_url}");
+            }
+        }
+        endpoint_config
+    }
+}
+
 /// Load AWS configuration with profile and region.
 pub async fn load_aws_config(config: &CubConfig) -> SdkConfig {
     let config_loader = create_aws_config_loader(config);
     config_loader.load().await
 }
 
-/// Creates a Dynamo DB client.
-pub async fn new_ddb_client(config: &CubConfig) -> DynamoDbClient {
-    let config = load_aws_config(config).await;
-    Client::new(&config)
+/// Creates a Dynamo DB client.  The `aws` config may specify an `endpoint_url`, e.g. of
+/// a local Dynamo DB for testing, along with a region and static (dummy) credentials:
+///
+/// ```toml
+/// [aws]
+/// endpoint_url = "http://localhost:8000"
+/// region = "us-east-1"
+/// access_key_id = "local"
+/// secret_access_key = "local"
+/// ```
+pub async fn new_ddb_client(cub_config: &CubConfig) -> DynamoDbClient {
+    let aws_config = load_aws_config(cub_config).await;
+    let AwsEndpointConfig { endpoint_url, .. } = AwsEndpointConfig::new(cub_config);
+    let mut ddb_config = aws_sdk_dynamodb::config::Builder::from(&aws_config);
+    if let Some(endpoint_url) = endpoint_url {
+        ddb_config = ddb_config.endpoint_url(endpoint_url);
+    }
+    Client::from_conf(ddb_config.build())
 }
 
 /// Creates an item in the specified Dynamo DB table only if its hash key (aka partition
@@ -73,20 +200,25 @@ pub async fn create_ddb_item<I: Serialize>(
         Ok(ser) => ser,
         Err(e) => return Err(Error::Serde(e)),
     };
+    let hash_key = redacted_item_key(&ser, hash_name);
 
     let req = client
         .put_item()
         .table_name(table)
         .expression_attribute_names("#hn", hash_name)
         .condition_expression("attribute_not_exists(#hn)")
-        .set_item(Some(ser));
+        .set_item(Some(ser))
+        .set_return_consumed_capacity(ddb_return_consumed_capacity());
 
-    match req.send().await {
+    match with_ddb_retries(|| req.clone().send()).await {
         Err(e) => Err(Error::Dynamo(
-            e.into(),
-            format!("create_item(t={table}, h={hash_name})"),
+            e,
+            format!("create_item(t={table}, h={hash_name}={hash_key})"),
         )),
-        Ok(_) => Ok(()),
+        Ok(output) => {
+            record_ddb_consumed_capacity("create_item", table, output.consumed_capacity());
+            Ok(())
+        }
     }
 }
 
@@ -98,14 +230,21 @@ pub async fn delete_ddb_item<HK: Serialize>(
     hash_value: &HK,
 ) -> Result<(), Error> {
     let hash_ser = to_dynamo_av(hash_value)?;
+    let hash_key = redacted_key(&hash_ser);
 
-    client
+    let req = client
         .delete_item()
         .table_name(table)
         .key(hash_name, hash_ser)
-        .send()
-        .await
-        .map_err(|e| Error::Dynamo(e.into(), format!("delete_item(t={table}, h={hash_name})")))?;
+        .set_return_consumed_capacity(ddb_return_consumed_capacity());
+
+    let output = with_ddb_retries(|| req.clone().send()).await.map_err(|e| {
+        Error::Dynamo(
+            e,
+            format!("delete_item(t={table}, h={hash_name}={hash_key})"),
+        )
+    })?;
+    record_ddb_consumed_capacity("delete_item", table, output.consumed_capacity());
     Ok(())
 }
 
@@ -120,20 +259,27 @@ pub async fn delete_ddb_ranged_item<HK: Serialize, RK: Serialize>(
 ) -> Result<(), Error> {
     let hash_ser = to_dynamo_av(hash_value)?;
     let range_ser = to_dynamo_av(range_value)?;
+    let hash_key = redacted_key(&hash_ser);
+    let range_key = redacted_key(&range_ser);
 
-    client
+    let req = client
         .delete_item()
         .table_name(table)
         .key(hash_name, hash_ser)
         .key(range_name, range_ser)
-        .send()
+        .set_return_consumed_capacity(ddb_return_consumed_capacity());
+
+    let output = with_ddb_retries(|| req.clone().send())
         .await
         .map_err(|e| {
             Error::Dynamo(
-                e.into(),
-                format!("delete_ranged_item(t={table}, h={hash_name}, r={range_name})"),
+                e,
+                format!(
+                    "delete_ranged_item(t={table}, h={hash_name}={hash_key}, r={range_name}={range_key})"
+                ),
             )
         })?;
+    record_ddb_consumed_capacity("delete_item", table, output.consumed_capacity());
     Ok(())
 }
 
@@ -158,25 +304,46 @@ pub async fn get_ddb_item<HK: Serialize, O: DeserializeOwned>(
     table: &'static str,
     hash_name: &'static str,
     hash_value: HK,
+) -> Result<Option<O>, Error> {
+    get_ddb_item_with_consistency(
+        client,
+        table,
+        hash_name,
+        hash_value,
+        ReadConsistency::Strong,
+    )
+    .await
+}
+
+/// Gets an item with the specified hash key, if any, from the specified Dynamo DB table.
+/// Reads are eventually consistent if `consistency` is `ReadConsistency::Eventual`.
+pub async fn get_ddb_item_with_consistency<HK: Serialize, O: DeserializeOwned>(
+    client: &DynamoDbClient,
+    table: &'static str,
+    hash_name: &'static str,
+    hash_value: HK,
+    consistency: ReadConsistency,
 ) -> Result<Option<O>, Error> {
     let hash_ser = to_dynamo_av(hash_value)?;
+    let hash_key = redacted_key(&hash_ser);
 
-    let mut get_item_output = match client
+    let req = client
         .get_item()
-        .consistent_read(true)
+        .consistent_read(consistency.is_strong())
         .table_name(table)
         .key(hash_name, hash_ser)
-        .send()
-        .await
-    {
+        .set_return_consumed_capacity(ddb_return_consumed_capacity());
+
+    let mut get_item_output = match with_ddb_retries(|| req.clone().send()).await {
         Ok(output) => output,
         Err(e) => {
             return Err(Error::Dynamo(
-                e.into(),
-                format!("get_item(t={table}, h={hash_name})"),
+                e,
+                format!("get_item(t={table}, h={hash_name}={hash_key})"),
             ))
         }
     };
+    record_ddb_consumed_capacity("get_item", table, get_item_output.consumed_capacity());
 
     if let Some(item) = mem::take(&mut get_item_output.item) {
         match serde_dynamo::from_item(item) {
@@ -196,27 +363,59 @@ pub async fn get_ddb_ranged_item<HK: Serialize, RK: Serialize, O: DeserializeOwn
     hash_value: HK,
     range_name: &'static str,
     range_value: RK,
+) -> Result<Option<O>, Error> {
+    get_ddb_ranged_item_with_consistency(
+        client,
+        table,
+        hash_name,
+        hash_value,
+        range_name,
+        range_value,
+        ReadConsistency::Strong,
+    )
+    .await
+}
+
+/// Gets an item with specified hash and range keys, if any, from the specified Dynamo DB table.
+/// Reads are eventually consistent if `consistency` is `ReadConsistency::Eventual`.
+pub async fn get_ddb_ranged_item_with_consistency<
+    HK: Serialize,
+    RK: Serialize,
+    O: DeserializeOwned,
+>(
+    client: &DynamoDbClient,
+    table: &'static str,
+    hash_name: &'static str,
+    hash_value: HK,
+    range_name: &'static str,
+    range_value: RK,
+    consistency: ReadConsistency,
 ) -> Result<Option<O>, Error> {
     let hash_ser = to_dynamo_av(hash_value)?;
     let range_ser = to_dynamo_av(range_value)?;
+    let hash_key = redacted_key(&hash_ser);
+    let range_key = redacted_key(&range_ser);
 
-    let mut get_item_output = match client
+    let req = client
         .get_item()
-        .consistent_read(true)
+        .consistent_read(consistency.is_strong())
         .table_name(table)
         .key(hash_name, hash_ser)
         .key(range_name, range_ser)
-        .send()
-        .await
-    {
+        .set_return_consumed_capacity(ddb_return_consumed_capacity());
+
+    let mut get_item_output = match with_ddb_retries(|| req.clone().send()).await {
         Ok(output) => output,
         Err(e) => {
             return Err(Error::Dynamo(
-                e.into(),
-                format!("get_ranged_item(t={table}, h={hash_name}, r={range_name})"),
+                e,
+                format!(
+                "get_ranged_item(t={table}, h={hash_name}={hash_key}, r={range_name}={range_key})"
+            ),
             ))
         }
     };
+    record_ddb_consumed_capacity("get_item", table, get_item_output.consumed_capacity());
 
     if let Some(item) = mem::take(&mut get_item_output.item) {
         match serde_dynamo::from_item(item) {
@@ -228,6 +427,7 @@ pub async fn get_ddb_ranged_item<HK: Serialize, RK: Serialize, O: DeserializeOwn
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn query_inner<O: DeserializeOwned>(
     client: &DynamoDbClient,
     table: &'static str,
@@ -236,14 +436,17 @@ async fn query_inner<O: DeserializeOwned>(
     range_key_bounds: Option<(&'static str, Option<AttributeValue>, Option<AttributeValue>)>,
     last_evaluated_key: Option<HashMap<String, AttributeValue>>,
     ignore_corrupt: bool,
+    consistency: ReadConsistency,
 ) -> Result<(Vec<O>, Option<HashMap<String, AttributeValue>>), Error> {
+    let hash_key = redacted_key(&hash_value);
     let mut scan = client
         .query()
-        .consistent_read(true)
+        .consistent_read(consistency.is_strong())
         .table_name(table)
         .expression_attribute_names("#h", hash_name)
         .expression_attribute_values(":hv", hash_value)
-        .set_exclusive_start_key(last_evaluated_key);
+        .set_exclusive_start_key(last_evaluated_key)
+        .set_return_consumed_capacity(ddb_return_consumed_capacity());
 
     if let Some(key_bounds) = range_key_bounds {
         match (key_bounds.1, key_bounds.2) {
@@ -277,10 +480,11 @@ async fn query_inner<O: DeserializeOwned>(
         Err(e) => {
             return Err(Error::Dynamo(
                 e.into(),
-                format!("query_inner(t={table}, h={hash_name})"),
+                format!("query_inner(t={table}, h={hash_name}={hash_key})"),
             ))
         }
     };
+    record_ddb_consumed_capacity("query", table, scan_output.consumed_capacity());
 
     let mut ret = Vec::new();
     for item in scan_output.items.unwrap_or_default() {
@@ -303,6 +507,27 @@ pub async fn query_ddb<HK: Serialize, O: DeserializeOwned>(
     hash_name: &'static str,
     hash_value: HK,
     ignore_corrupt: bool,
+) -> Result<Vec<O>, Error> {
+    query_ddb_with_consistency(
+        client,
+        table,
+        hash_name,
+        hash_value,
+        ignore_corrupt,
+        ReadConsistency::Strong,
+    )
+    .await
+}
+
+/// Query and return items from the specified Dynamo DB table.
+/// Reads are eventually consistent if `consistency` is `ReadConsistency::Eventual`.
+pub async fn query_ddb_with_consistency<HK: Serialize, O: DeserializeOwned>(
+    client: &DynamoDbClient,
+    table: &'static str,
+    hash_name: &'static str,
+    hash_value: HK,
+    ignore_corrupt: bool,
+    consistency: ReadConsistency,
 ) -> Result<Vec<O>, Error> {
     let hash_ser = to_dynamo_av(hash_value)?;
 
@@ -317,6 +542,7 @@ pub async fn query_ddb<HK: Serialize, O: DeserializeOwned>(
             None,
             last_evaluated_key,
             ignore_corrupt,
+            consistency,
         )
         .await
         {
@@ -342,6 +568,31 @@ pub async fn query_ddb_hash_range<HK: Serialize, RK: Serialize, O: DeserializeOw
     hash_key: (&'static str, HK),
     range_key_bounds: (&'static str, Option<RK>, Option<RK>),
     ignore_corrupt: bool,
+) -> Result<Vec<O>, Error> {
+    query_ddb_hash_range_with_consistency(
+        client,
+        table,
+        hash_key,
+        range_key_bounds,
+        ignore_corrupt,
+        ReadConsistency::Strong,
+    )
+    .await
+}
+
+/// Query and return items from the specified Dynamo DB table.
+/// Reads are eventually consistent if `consistency` is `ReadConsistency::Eventual`.
+pub async fn query_ddb_hash_range_with_consistency<
+    HK: Serialize,
+    RK: Serialize,
+    O: DeserializeOwned,
+>(
+    client: &DynamoDbClient,
+    table: &'static str,
+    hash_key: (&'static str, HK),
+    range_key_bounds: (&'static str, Option<RK>, Option<RK>),
+    ignore_corrupt: bool,
+    consistency: ReadConsistency,
 ) -> Result<Vec<O>, Error> {
     let hash_ser = to_dynamo_av(hash_key.1)?;
 
@@ -370,6 +621,7 @@ pub async fn query_ddb_hash_range<HK: Serialize, RK: Serialize, O: DeserializeOw
             Some(bounds.clone()),
             last_evaluated_key,
             ignore_corrupt,
+            consistency,
         )
         .await
         {
@@ -399,30 +651,62 @@ pub async fn put_ddb_item<I: Serialize>(
         Err(e) => return Err(Error::Serde(e)),
     };
 
-    let req = client.put_item().table_name(table).set_item(Some(ser));
+    let req = client
+        .put_item()
+        .table_name(table)
+        .set_item(Some(ser))
+        .set_return_consumed_capacity(ddb_return_consumed_capacity());
+
+    match with_ddb_retries(|| req.clone().send()).await {
+        Err(e) => Err(Error::Dynamo(e, format!("put_item(t={table})"))),
+        Ok(output) => {
+            record_ddb_consumed_capacity("put_item", table, output.consumed_capacity());
+            Ok(())
+        }
+    }
+}
 
-    match req.send().await {
-        Err(e) => Err(Error::Dynamo(e.into(), format!("put_item(t={table})"))),
-        Ok(_) => Ok(()),
+/// Describes a key for error context without logging it verbatim.  Numbers (which are
+/// typically IDs) are shown in full, but other values are replaced by a fingerprint
+/// which can be compared against that of a suspected key.
+pub(crate) fn redacted_key(value: &AttributeValue) -> String {
+    let fingerprint = |bytes: &[u8]| {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        format!("#{:016x}", hasher.finish())
+    };
+    match value {
+        AttributeValue::N(n) => n.to_string(),
+        AttributeValue::S(s) => fingerprint(s.as_bytes()),
+        _ => fingerprint(format!("{value:?}").as_bytes()),
     }
 }
 
+fn redacted_item_key(item: &HashMap<String, AttributeValue>, key_name: &str) -> String {
+    item.get(key_name)
+        .map(redacted_key)
+        .unwrap_or_else(|| "?".to_string())
+}
+
 async fn scan_inner<O: DeserializeOwned>(
     client: &DynamoDbClient,
     table: &'static str,
     last_evaluated_key: Option<HashMap<String, AttributeValue>>,
+    consistency: ReadConsistency,
 ) -> Result<(Vec<O>, Option<HashMap<String, AttributeValue>>), Error> {
     let scan_output = match client
         .scan()
-        .consistent_read(true)
+        .consistent_read(consistency.is_strong())
         .table_name(table)
         .set_exclusive_start_key(last_evaluated_key)
+        .set_return_consumed_capacity(ddb_return_consumed_capacity())
         .send()
         .await
     {
         Ok(output) => output,
         Err(e) => return Err(Error::Dynamo(e.into(), format!("scan_inner(t={table})"))),
     };
+    record_ddb_consumed_capacity("scan", table, scan_output.consumed_capacity());
 
     let mut ret = Vec::new();
     for item in scan_output.items.unwrap_or_default() {
@@ -438,11 +722,21 @@ async fn scan_inner<O: DeserializeOwned>(
 pub async fn scan_ddb<O: DeserializeOwned>(
     client: &DynamoDbClient,
     table: &'static str,
+) -> Result<Vec<O>, Error> {
+    scan_ddb_with_consistency(client, table, ReadConsistency::Strong).await
+}
+
+/// Scan and return items from the specified Dynamo DB table.
+/// Reads are eventually consistent if `consistency` is `ReadConsistency::Eventual`.
+pub async fn scan_ddb_with_consistency<O: DeserializeOwned>(
+    client: &DynamoDbClient,
+    table: &'static str,
+    consistency: ReadConsistency,
 ) -> Result<Vec<O>, Error> {
     let mut ret = Vec::new();
     let mut last_evaluated_key = None;
     loop {
-        match scan_inner(client, table, last_evaluated_key).await {
+        match scan_inner(client, table, last_evaluated_key, consistency).await {
             Err(e) => return Err(e),
             Ok((mut items, lek)) => {
                 ret.append(&mut items);
@@ -458,6 +752,85 @@ pub async fn scan_ddb<O: DeserializeOwned>(
     Ok(ret)
 }
 
+/// Sets the maximum number of times that single-item operations (`get_ddb_item`,
+/// `put_ddb_item`, `update_ddb_item`, `delete_ddb_item`, etc.) are retried if Dynamo DB
+/// throttles them or reports a transaction conflict.  The default is 3.
+pub fn set_ddb_max_retries(max_retries: usize) {
+    DDB_MAX_RETRIES.store(max_retries, Ordering::Relaxed);
+}
+
+/// Sets a hook which is called with the capacity consumed by each Dynamo DB operation
+/// (`get_ddb_item`, `put_ddb_item`, each page of `query_ddb`, etc.), for example to
+/// surface Dynamo DB cost per endpoint.  Consumed capacity is only requested from
+/// Dynamo DB while a hook is set.
+#[cfg(feature = "ddb_metrics")]
+pub fn set_ddb_metrics_hook(hook: impl Fn(&DdbConsumedCapacity) + Send + Sync + 'static) {
+    *DDB_METRICS_HOOK.write().unwrap() = Some(Box::new(hook));
+}
+
+/// Removes the hook set by `set_ddb_metrics_hook`.
+#[cfg(feature = "ddb_metrics")]
+pub fn clear_ddb_metrics_hook() {
+    *DDB_METRICS_HOOK.write().unwrap() = None;
+}
+
+/// Whether to ask Dynamo DB to return consumed capacity, i.e. if there is a metrics hook.
+fn ddb_return_consumed_capacity() -> Option<ReturnConsumedCapacity> {
+    #[cfg(feature = "ddb_metrics")]
+    if DDB_METRICS_HOOK.read().unwrap().is_some() {
+        return Some(ReturnConsumedCapacity::Total);
+    }
+    None
+}
+
+/// Reports consumed capacity (if Dynamo DB returned it) to the metrics hook, if any.
+#[cfg_attr(not(feature = "ddb_metrics"), allow(unused_variables))]
+pub(crate) fn record_ddb_consumed_capacity(
+    operation: &'static str,
+    table: &'static str,
+    consumed: Option<&ConsumedCapacity>,
+) {
+    #[cfg(feature = "ddb_metrics")]
+    if let Some(capacity_units) = consumed.and_then(|c| c.capacity_units) {
+        if let Some(hook) = DDB_METRICS_HOOK.read().unwrap().as_ref() {
+            hook(&DdbConsumedCapacity {
+                operation,
+                table,
+                capacity_units,
+            });
+        }
+    }
+}
+
+/// Sends a Dynamo DB request, retrying with jittered exponential backoff if it fails
+/// due to throttling or a transaction conflict.
+pub(crate) async fn with_ddb_retries<T, E, F, Fut>(mut send: F) -> Result<T, DynamoError>
+where
+    E: Into<DynamoError>,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let max_retries = DDB_MAX_RETRIES.load(Ordering::Relaxed);
+    let mut retries = 0;
+    loop {
+        match send().await.map_err(Into::into) {
+            Err(
+                DynamoError::ProvisionedThroughputExceededException(_)
+                | DynamoError::RequestLimitExceeded(_)
+                | DynamoError::TransactionConflictException(_),
+            ) if retries < max_retries => {
+                let backoff = DDB_BACKOFF_MILLIS
+                    .saturating_mul(1 << retries.min(16))
+                    .min(DDB_MAX_BACKOFF_MILLIS);
+                let millis = rand::thread_rng().gen_range(backoff / 2..=backoff);
+                tokio::time::sleep(Duration::from_millis(millis)).await;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 /// Packs a Dynamo DB `AttributeValue`.
 pub fn to_dynamo_av<T: Serialize>(value: T) -> Result<AttributeValue, Error> {
     serde_dynamo::to_attribute_value(value).map_err(Error::Serde)
@@ -511,6 +884,7 @@ pub async fn update_ddb_item<I: Serialize>(
         Ok(ser) => ser,
         Err(e) => return Err(Error::Serde(e)),
     };
+    let hash_key = redacted_item_key(&ser, hash_name);
 
     let req = client
         .put_item()
@@ -521,13 +895,17 @@ pub async fn update_ddb_item<I: Serialize>(
             "attribute_exists(#hn) and (attribute_not_exists(#vn) or #vn = :version)",
         )
         .expression_attribute_values(":version", to_dynamo_av(version.saturating_sub(1))?)
-        .set_item(Some(ser));
+        .set_item(Some(ser))
+        .set_return_consumed_capacity(ddb_return_consumed_capacity());
 
-    match req.send().await {
+    match with_ddb_retries(|| req.clone().send()).await {
         Err(e) => Err(Error::Dynamo(
-            e.into(),
-            format!("update_item(t={table}, h={hash_name})"),
+            e,
+            format!("update_item(t={table}, h={hash_name}={hash_key})"),
         )),
-        Ok(_) => Ok(true), // TODO: return false if update failed due to condition.
+        Ok(output) => {
+            record_ddb_consumed_capacity("update_item", table, output.consumed_capacity());
+            Ok(true) // TODO: return false if update failed due to condition.
+        }
     }
 }
@@ -0,0 +1,33 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::Router;
+
+/// The path of the route added by `with_health_route`.
+pub const HEALTH_PATH: &str = "/healthz";
+
+/// Add a `GET /healthz` route which always returns 200, e.g. for a load balancer.
+pub fn with_health_route(router: Router) -> Router {
+    with_readiness_route(router, HEALTH_PATH, || true)
+}
+
+/// Add a `GET` route at the specified path which returns 200 if `ready` returns true,
+/// otherwise 503, e.g. so a load balancer waits until a cache is warm.
+pub fn with_readiness_route(
+    router: Router,
+    path: &str,
+    ready: impl Fn() -> bool + Clone + Send + Sync + 'static,
+) -> Router {
+    router.route(
+        path,
+        get(move || async move {
+            if ready() {
+                (StatusCode::OK, "ok")
+            } else {
+                (StatusCode::SERVICE_UNAVAILABLE, "not ready")
+            }
+        }),
+    )
+}
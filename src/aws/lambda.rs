@@ -1,6 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+use super::X_REQUEST_ID;
 use axum::body::{to_bytes, Body};
 use axum::http::StatusCode;
 use axum::Router;
@@ -11,7 +12,6 @@ use core::task::Context;
 use hyper::header::{HeaderName, HeaderValue};
 use hyper::{Method, Request};
 use lambda_runtime::{Error, LambdaEvent, Service};
-use serde::de::IgnoredAny;
 use serde::Deserialize;
 use serde_json::json;
 use std::collections::HashMap;
@@ -19,22 +19,90 @@ use std::convert::Infallible;
 use std::env::var;
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::Arc;
 use urlencoding::encode;
 
 const DEBUG1: bool = false;
-const DEBUG2: bool = false;
 
 /// Returns true when executable is run in AWS Lambda environment.
 pub fn is_lambda_env() -> bool {
     var("AWS_LAMBDA_RUNTIME_API").is_ok()
 }
 
-/// Run a router on a Lambda Proxy invoked via AWS API Gateway. The
-/// AWS API Gateway binary media type must be set to `*/*` so that binary
-/// data will be encoded using base 64.
+/// Options for running a router on a Lambda Proxy.
+#[derive(Debug, Clone)]
+pub struct LambdaOptions {
+    binary_media_types: Vec<String>,
+    error_bodies: bool,
+}
+
+impl Default for LambdaOptions {
+    fn default() -> Self {
+        Self {
+            binary_media_types: [
+                "application/octet-stream",
+                "image/gif",
+                "image/jpg",
+                "image/jpeg",
+                "image/png",
+                "image/webp",
+            ]
+            .map(String::from)
+            .to_vec(),
+            error_bodies: false,
+        }
+    }
+}
+
+impl LambdaOptions {
+    /// Replace the content types of responses which are base 64 encoded, which should
+    /// match the binary media types in the API Gateway settings.  A type may be a
+    /// wildcard, e.g. `image/*` or `*/*`.  The default is `application/octet-stream`
+    /// and common image types.
+    pub fn binary_media_types<S: Into<String>>(
+        mut self,
+        types: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.binary_media_types = types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Return the body, status, and path of unsuccessful responses as a JSON body with
+    /// status 200, since API Gateway normally hides them.  For debugging only.
+    pub fn error_bodies(mut self) -> Self {
+        self.error_bodies = true;
+        self
+    }
+
+    /// Returns true if responses with the specified content type are base 64 encoded.
+    pub(crate) fn is_binary(&self, content_type: &str) -> bool {
+        let content_type = content_type.split(';').next().unwrap_or_default().trim();
+        self.binary_media_types
+            .iter()
+            .any(|binary_type| match binary_type.strip_suffix('*') {
+                Some("*/") => true,
+                Some(prefix) => content_type
+                    .get(..prefix.len())
+                    .is_some_and(|p| p.eq_ignore_ascii_case(prefix)),
+                None => content_type.eq_ignore_ascii_case(binary_type),
+            })
+    }
+}
+
+/// Run a router on a Lambda Proxy invoked via AWS API Gateway, with the default
+/// `LambdaOptions`.  The AWS API Gateway binary media type must be set to `*/*` so
+/// that binary data will be encoded using base 64.
 pub async fn run_router_on_lambda(router: Router) -> Result<(), Error> {
+    run_router_on_lambda_with(router, LambdaOptions::default()).await
+}
+
+/// Run a router on a Lambda Proxy invoked via AWS API Gateway, with the specified options.
+pub async fn run_router_on_lambda_with(
+    router: Router,
+    options: LambdaOptions,
+) -> Result<(), Error> {
     println!("Begin running router on lambda");
-    lambda_runtime::run(RouterWrapper(router)).await?;
+    lambda_runtime::run(RouterWrapper(router, Arc::new(options))).await?;
     println!("Done running router on lambda");
     Ok(())
 }
@@ -44,7 +112,7 @@ type GwRequest = Request<Body>;
 
 /// The `RouterWrapper` struct layers additional functionality on top of `axum::Router` to parse JSON
 /// requests from AWS API Gateway, and provide JSON responses to AWS API Gateway.
-struct RouterWrapper(Router);
+struct RouterWrapper(Router, Arc<LambdaOptions>);
 
 impl Service<LambdaEvent<ApiGatewayEvent>> for RouterWrapper {
     type Error = Infallible;
@@ -65,6 +133,7 @@ impl Service<LambdaEvent<ApiGatewayEvent>> for RouterWrapper {
         }
         let request = GwRequest::try_from(lambda_event.payload);
         let router_result = request.map(|r| self.0.call(r));
+        let options = Arc::clone(&self.1);
         let fut = async move {
             match router_result {
                 Ok(method_result) => {
@@ -77,21 +146,14 @@ impl Service<LambdaEvent<ApiGatewayEvent>> for RouterWrapper {
                                     headers.insert(k.as_str().to_string(), json!(value_str));
                                 }
                             }
-                            // The following should match the binary media types in API Gateway settings.
-                            let binary =
-                                match headers.get("content-type").map(|v| v.as_str()).flatten() {
-                                    Some("application/octet-stream") => true,
-                                    Some("image/gif") => true,
-                                    Some("image/jpg") => true,
-                                    Some("image/jpeg") => true,
-                                    Some("image/png") => true,
-                                    Some("image/webp") => true,
-                                    _ => false,
-                                };
+                            let binary = headers
+                                .get("content-type")
+                                .and_then(|v| v.as_str())
+                                .is_some_and(|content_type| options.is_binary(content_type));
 
                             match to_bytes(body, usize::MAX).await {
                                 Ok(body) => {
-                                    if DEBUG2
+                                    if options.error_bodies
                                         && !StatusCode::is_success(&parts.status)
                                         && parts.status != StatusCode::SEE_OTHER
                                     {
@@ -175,16 +237,21 @@ impl TryFrom<ApiGatewayEvent> for GwRequest {
     fn try_from(gw_event: ApiGatewayEvent) -> Result<Self, Self::Error> {
         let method = Method::try_from(gw_event.http_method.unwrap_or("GET".to_string()).as_str())?;
 
-        let builder =
+        let (builder, request_id) =
             if let Some(ApiGatewayRequestContext::WebSocket(context)) = gw_event.request_context {
                 let ApiGatewayV2WebsocketContext {
                     connection_id,
                     event_type,
+                    request_id,
                 } = context;
                 let path = format!("/ws/{event_type:?}/{connection_id}");
                 let uri = append_query_string(&path, &gw_event.multi_value_query_string_parameters);
-                Request::builder().method("POST").uri(uri)
+                (Request::builder().method("POST").uri(uri), request_id)
             } else {
+                let request_id = match gw_event.request_context {
+                    Some(ApiGatewayRequestContext::Rest(context)) => context.request_id,
+                    _ => None,
+                };
                 let path = gw_event.path.unwrap_or("/".to_string());
                 let uri = append_query_string(&path, &gw_event.multi_value_query_string_parameters);
                 let mut builder = Request::builder().method(method).uri(uri);
@@ -209,9 +276,20 @@ impl TryFrom<ApiGatewayEvent> for GwRequest {
                         }
                     }
                 }
-                builder
+                (builder, request_id)
             };
 
+        // Unless the client specified one, use the API Gateway request ID.
+        let mut builder = builder;
+        if let (Some(headers_mut), Some(request_id)) = (builder.headers_mut(), request_id) {
+            if let (false, Ok(value)) = (
+                headers_mut.contains_key(X_REQUEST_ID),
+                HeaderValue::from_str(&request_id),
+            ) {
+                headers_mut.insert(X_REQUEST_ID, value);
+            }
+        }
+
         let body = if gw_event.is_base64_encoded {
             let engine =
                 engine::GeneralPurpose::new(&alphabet::STANDARD, engine::general_purpose::PAD);
@@ -261,7 +339,14 @@ fn append_query_string(
 #[serde(untagged)]
 pub(crate) enum ApiGatewayRequestContext {
     WebSocket(ApiGatewayV2WebsocketContext),
-    Rest(IgnoredAny),
+    Rest(ApiGatewayRestContext),
+}
+
+/// The context for an AWS API Gateway REST event, with only the necessary fields.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApiGatewayRestContext {
+    request_id: Option<String>,
 }
 
 /// The context for an AWS API Gateway v2 socket event, with only the necessary fields.
@@ -270,6 +355,7 @@ pub(crate) enum ApiGatewayRequestContext {
 pub struct ApiGatewayV2WebsocketContext {
     connection_id: String,
     event_type: WebsocketEventType,
+    request_id: Option<String>,
 }
 
 /// Web socket event types.
@@ -9,10 +9,14 @@ mod bot;
 mod ddbupdate;
 /// A wrapper around Dynamo DB client.
 mod dynamo;
+/// Health and readiness routes for load balancers.
+mod health;
 /// A wrapper to run a router via AWS API Gateway and Lambda Proxy.
 mod lambda;
 /// A wrapper around large language models.
 mod llm;
+/// Request ID propagation.
+mod request_id;
 /// A wrapper around S3 client.
 mod s3;
 /// Run an `axum::Router` on incoming requests from a socket.
@@ -25,22 +29,39 @@ mod translate;
 mod websocket;
 
 pub use crate::aws::b64::{b64_to_u64, u64_to_b64};
-pub use crate::aws::bot::user_agent_is_bot;
-pub use crate::aws::ddbupdate::{ddb_ranged_update, ddb_update, DynamoUpdateBuilder};
+pub use crate::aws::bot::{
+    user_agent_is_bot, verify_bot_ip, BotClassifier, BotKind, BotVerification,
+};
+pub use crate::aws::ddbupdate::{
+    ddb_ranged_update, ddb_update, DynamoUpdateBuilder, UpdateReturnValues,
+};
+#[cfg(feature = "ddb_metrics")]
+pub use crate::aws::dynamo::{clear_ddb_metrics_hook, set_ddb_metrics_hook, DdbConsumedCapacity};
 pub use crate::aws::dynamo::{
     create_aws_config_loader, create_ddb_item, delete_ddb_item, delete_ddb_ranged_item,
-    describe_ddb_table_length, get_ddb_item, get_ddb_ranged_item, load_aws_config, new_ddb_client,
-    put_ddb_item, query_ddb, query_ddb_hash_range, scan_ddb, to_dynamo_av, to_dynamo_den,
+    describe_ddb_table_length, get_ddb_item, get_ddb_item_with_consistency, get_ddb_ranged_item,
+    get_ddb_ranged_item_with_consistency, load_aws_config, new_ddb_client, put_ddb_item, query_ddb,
+    query_ddb_hash_range, query_ddb_hash_range_with_consistency, query_ddb_with_consistency,
+    scan_ddb, scan_ddb_with_consistency, set_ddb_max_retries, to_dynamo_av, to_dynamo_den,
     to_dynamo_des, to_dynamo_item, to_dynamo_sen, to_dynamo_ses, update_ddb_item, DynamoDbClient,
+    ReadConsistency,
+};
+pub use crate::aws::health::{with_health_route, with_readiness_route, HEALTH_PATH};
+pub use crate::aws::lambda::{
+    is_lambda_env, run_router_on_lambda, run_router_on_lambda_with, LambdaOptions,
 };
-pub use crate::aws::lambda::{is_lambda_env, run_router_on_lambda};
 pub use crate::aws::llm::{new_llm_client, prompt_llm, LlmClient, LlmOptions};
+pub use crate::aws::request_id::{with_request_id, RequestId, X_REQUEST_ID};
 pub use crate::aws::s3::{
-    get_s3_item, list_s3_bucket, new_s3_client, presigned_s3_download_url, presigned_s3_upload_url,
-    put_s3_item, S3Client,
+    get_s3_item, get_s3_item_stream, head_s3_item, list_s3_bucket, new_s3_client,
+    presigned_s3_download_url, presigned_s3_post, presigned_s3_upload_url, put_s3_item, S3Client,
+    S3Item, S3Metadata, S3PresignedPost,
 };
 pub use crate::aws::socket::run_router_on_socket;
 pub use crate::aws::translate::{
-    braces_valid, new_translate_client, translate_text, TranslateClient,
+    braces_valid, new_translate_client, translate_text, validate_braces, BraceError,
+    BraceErrorKind, TranslateClient, TranslationCacheStats,
+};
+pub use crate::aws::websocket::{
+    broadcast_to_table, new_ws_client, send_ws_message, BroadcastSummary, WebsocketClient,
 };
-pub use crate::aws::websocket::{new_ws_client, send_ws_message, WebsocketClient};
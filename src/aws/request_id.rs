@@ -0,0 +1,47 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use crate::time_id::ID64;
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::middleware::{from_fn, Next};
+use axum::response::Response;
+use axum::Router;
+
+/// The header which carries the request ID.  On Lambda, it defaults to the AWS API
+/// Gateway request ID.
+pub const X_REQUEST_ID: &str = "x-request-id";
+
+/// The ID of a request, which `with_request_id` adds to the extensions of each request,
+/// e.g. for handlers to extract with `Extension<RequestId>` and prefix their logs
+/// (see `StringLogger::with_prefix`).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RequestId(pub String);
+crate::impl_wrapper_str!(RequestId);
+
+/// Request IDs from clients longer than this are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Add a layer which takes the ID of each request from its `x-request-id` header, or
+/// generates one (an `ID64`) if absent, makes it available to handlers as an extension,
+/// and echoes it in the `x-request-id` header of the response.
+pub fn with_request_id(router: Router) -> Router {
+    router.layer(from_fn(propagate_request_id))
+}
+
+async fn propagate_request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LEN)
+        .map(String::from)
+        .unwrap_or_else(|| ID64::<0>::generate().to_string());
+    let header_value = HeaderValue::from_str(&request_id).ok();
+    request.extensions_mut().insert(RequestId(request_id));
+    let mut response = next.run(request).await;
+    if let Some(header_value) = header_value {
+        response.headers_mut().insert(X_REQUEST_ID, header_value);
+    }
+    response
+}
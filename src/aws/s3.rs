@@ -1,28 +1,109 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+use super::dynamo::AwsEndpointConfig;
 use super::load_aws_config;
 use crate::common::{CubConfig, Error};
+use aws_sdk_s3::config::interceptors::BeforeTransmitInterceptorContextRef;
+use aws_sdk_s3::config::{
+    ConfigBag, Credentials, Intercept, ResolveCachedIdentity, RuntimeComponents,
+};
 use aws_sdk_s3::presigning::PresigningConfig;
-use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::primitives::{ByteStream, DateTime, DateTimeFormat};
 use aws_sdk_s3::Client;
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::auth::AuthSchemeId;
+use aws_smithy_runtime_api::client::runtime_components::GetIdentityResolver;
+use axum::body::Bytes;
 use axum::http::StatusCode;
-use std::time::Duration;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use futures::Stream;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// A convenient alias for S3 client so consuming code doesn't need to add it to `Cargo.toml`
 pub type S3Client = aws_sdk_s3::Client;
 
-/// Retrieves an object from S3.
-pub async fn get_s3_item(client: &S3Client, bucket: &str, key: &str) -> Result<Vec<u8>, Error> {
+/// Metadata of an object in S3, e.g. to set the response headers of a download or
+/// answer a conditional GET.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct S3Metadata {
+    /// The `Content-Type`, if any.
+    pub content_type: Option<String>,
+    /// The `ETag` (including quotes), if any.
+    pub e_tag: Option<String>,
+    /// The `Last-Modified` time, if any.
+    pub last_modified: Option<SystemTime>,
+    /// The size of the whole object.
+    pub size: u64,
+}
+
+impl S3Metadata {
+    fn new(
+        content_type: Option<&str>,
+        e_tag: Option<&str>,
+        last_modified: Option<&DateTime>,
+        size: u64,
+    ) -> Self {
+        Self {
+            content_type: content_type.map(String::from),
+            e_tag: e_tag.map(String::from),
+            last_modified: last_modified.and_then(|t| SystemTime::try_from(*t).ok()),
+            size,
+        }
+    }
+}
+
+/// An object (or part of one) retrieved from S3.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct S3Item {
+    /// The bytes of the object, or of the requested range.
+    pub data: Vec<u8>,
+    /// The metadata of the object.  Its `size` may exceed that of `data` if a range
+    /// was requested.
+    pub metadata: S3Metadata,
+}
+
+/// Retrieves an object from S3.  If `range` is specified, retrieves only the bytes from
+/// the start offset to the (inclusive) end offset, if any, otherwise to the end of the
+/// object, e.g. to answer an HTTP range request with `206 Partial Content`.
+pub async fn get_s3_item(
+    client: &S3Client,
+    bucket: &str,
+    key: &str,
+    range: Option<(u64, Option<u64>)>,
+) -> Result<S3Item, Error> {
+    let range = range.map(|(start, end)| match end {
+        Some(end) => format!("bytes={start}-{end}"),
+        None => format!("bytes={start}-"),
+    });
     let mut object = client
         .get_object()
         .bucket(bucket)
         .key(key)
+        .set_range(range)
         .send()
         .await
         .map_err(|e| Error::Anyhow(e.into(), format!("get_s3_item({bucket}, {key})")))?;
 
-    let mut buf: Vec<u8> = Vec::with_capacity(10 * 1024 * 1024);
+    // E.g. "bytes 0-99/1234" for a range, otherwise the total size is the content length.
+    let content_length = object.content_length().unwrap_or_default().max(0) as u64;
+    let total_size = object
+        .content_range()
+        .and_then(|content_range| content_range.rsplit_once('/'))
+        .and_then(|(_, total_size)| total_size.parse().ok())
+        .unwrap_or(content_length);
+    let metadata = S3Metadata::new(
+        object.content_type(),
+        object.e_tag(),
+        object.last_modified(),
+        total_size,
+    );
+
+    let mut buf: Vec<u8> = Vec::with_capacity((content_length as usize).min(10 * 1024 * 1024));
     while let Some(bytes) = object
         .body
         .try_next()
@@ -32,7 +113,56 @@ pub async fn get_s3_item(client: &S3Client, bucket: &str, key: &str) -> Result<V
         buf.extend_from_slice(&bytes);
     }
 
-    Ok(buf.into())
+    Ok(S3Item {
+        data: buf,
+        metadata,
+    })
+}
+
+/// Retrieves the metadata of an object from S3, without its body.
+pub async fn head_s3_item(client: &S3Client, bucket: &str, key: &str) -> Result<S3Metadata, Error> {
+    let object = client
+        .head_object()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|e| Error::Anyhow(e.into(), format!("head_s3_item({bucket}, {key})")))?;
+    Ok(S3Metadata::new(
+        object.content_type(),
+        object.e_tag(),
+        object.last_modified(),
+        object.content_length().unwrap_or_default().max(0) as u64,
+    ))
+}
+
+/// Retrieves an object from S3 as a stream of chunks, rather than loading all of it
+/// into memory.  For example, the stream may be piped to `axum::body::Body::from_stream`.
+pub async fn get_s3_item_stream(
+    client: &S3Client,
+    bucket: &str,
+    key: &str,
+) -> Result<impl Stream<Item = Result<Bytes, Error>>, Error> {
+    let object = client
+        .get_object()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|e| Error::Anyhow(e.into(), format!("get_s3_item_stream({bucket}, {key})")))?;
+    let context = format!("s3_try_next({bucket}, {key})");
+    // The stream ends after the first error.
+    Ok(futures::stream::unfold(
+        Some((object.body, context)),
+        |state| async move {
+            let (mut body, context) = state?;
+            match body.try_next().await {
+                Ok(Some(bytes)) => Some((Ok(bytes), Some((body, context)))),
+                Ok(None) => None,
+                Err(e) => Some((Err(Error::Anyhow(e.into(), context)), None)),
+            }
+        },
+    ))
 }
 
 /// Lists objects in the specified S3 bucket.
@@ -57,10 +187,22 @@ pub async fn list_s3_bucket(client: &Client, bucket: &str) -> Result<Vec<String>
     }
 }
 
-/// Creates an S3 client.
+/// Creates an S3 client.  The `aws` config may specify an `endpoint_url` and
+/// `force_path_style`, e.g. to use MinIO or LocalStack for testing.
 pub async fn new_s3_client(cub_config: &CubConfig) -> S3Client {
     let aws_config = load_aws_config(cub_config).await;
-    Client::new(&aws_config)
+    let AwsEndpointConfig {
+        endpoint_url,
+        force_path_style,
+    } = AwsEndpointConfig::new(cub_config);
+    let mut s3_config = aws_sdk_s3::config::Builder::from(&aws_config);
+    if let Some(endpoint_url) = endpoint_url {
+        s3_config = s3_config.endpoint_url(endpoint_url);
+    }
+    if force_path_style {
+        s3_config = s3_config.force_path_style(true);
+    }
+    Client::from_conf(s3_config.build())
 }
 
 /// Retrieves the pre-signed URL for an object from S3.
@@ -106,6 +248,219 @@ pub async fn presigned_s3_upload_url(
     Ok(presigned_request.uri().to_string())
 }
 
+/// A pre-signed POST policy, with which a browser may upload an object directly to S3
+/// via an HTML form.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct S3PresignedPost {
+    /// The URL to which the form is posted.
+    pub url: String,
+    /// The form fields, which must precede the `file` field.  If a wildcard content type
+    /// is allowed, the form must also have a `Content-Type` field.
+    pub fields: Vec<(String, String)>,
+}
+
+/// Returns the POST policy condition on `Content-Type`, if any, and the value of the
+/// `Content-Type` field, if it is fixed.  A policy can only require an exact value or a
+/// prefix, so at most one content type may be allowed, either exact (e.g. `image/png`)
+/// or a wildcard (e.g. `image/*`).  Several exact types are an error, rather than being
+/// widened to a prefix that would also allow e.g. `image/svg+xml`.
+pub(crate) fn content_type_condition(
+    allowed_content_types: &[&str],
+) -> Result<Option<(serde_json::Value, Option<String>)>, Error> {
+    match allowed_content_types {
+        [] | ["*/*"] => Ok(None),
+        [content_type] => match content_type.strip_suffix('*') {
+            Some(prefix) if prefix.ends_with('/') && prefix.len() > 1 => Ok(Some((
+                serde_json::json!(["starts-with", "$Content-Type", prefix]),
+                None,
+            ))),
+            Some(_) => Err(Error::Http(
+                StatusCode::BAD_REQUEST,
+                format!("content type {content_type:?} is not a valid wildcard"),
+            )),
+            None => Ok(Some((
+                serde_json::json!({ "Content-Type": content_type }),
+                Some(content_type.to_string()),
+            ))),
+        },
+        _ => Err(Error::Http(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "content types {allowed_content_types:?} cannot all be allowed by one policy; \
+                 allow one type or a wildcard such as image/*"
+            ),
+        )),
+    }
+}
+
+/// Returns the URL to which a form is posted, given a URL of an object in the bucket.
+/// This respects a custom `endpoint_url` and `force_path_style` (see `new_s3_client`).
+pub(crate) fn post_url(object_url: &str, key: &str) -> String {
+    let path = object_url.split('?').next().unwrap_or_default();
+    let segments = key.split('/').count();
+    let url = path.rsplitn(segments + 1, '/').last().unwrap_or_default();
+    format!("{url}/")
+}
+
+/// Returns the AWS signature version 4 of a string.
+fn sigv4_signature(secret_access_key: &str, date: &str, region: &str, string: &str) -> String {
+    let hmac = |key: &[u8], data: &str| {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("any key length");
+        mac.update(data.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    };
+    let key = format!("AWS4{secret_access_key}");
+    let key = [date, region, "s3", "aws4_request"]
+        .iter()
+        .fold(key.into_bytes(), |key, data| hmac(&key, data));
+    hex::encode(hmac(&key, string))
+}
+
+/// Captures the runtime components of a request, with which the credentials of the client
+/// that made it can be resolved (the client's config doesn't expose them).
+#[derive(Debug, Default)]
+struct CaptureRuntimeComponents(Arc<Mutex<Option<RuntimeComponents>>>);
+
+impl Intercept for CaptureRuntimeComponents {
+    fn name(&self) -> &'static str {
+        "CaptureRuntimeComponents"
+    }
+
+    fn read_before_signing(
+        &self,
+        _context: &BeforeTransmitInterceptorContextRef<'_>,
+        runtime_components: &RuntimeComponents,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        *self.0.lock().unwrap() = Some(runtime_components.clone());
+        Ok(())
+    }
+}
+
+/// Returns the (possibly cached) SigV4 credentials of a client, given the runtime
+/// components of one of its requests.
+async fn resolve_credentials(
+    runtime_components: &RuntimeComponents,
+) -> Result<Credentials, BoxError> {
+    const SIGV4: AuthSchemeId = AuthSchemeId::new("sigv4");
+    let resolver = runtime_components
+        .identity_resolver(SIGV4)
+        .ok_or("no sigv4 identity resolver")?;
+    let identity = runtime_components
+        .identity_cache()
+        .resolve_cached_identity(resolver, runtime_components, &ConfigBag::base())
+        .await?;
+    identity
+        .data::<Credentials>()
+        .cloned()
+        .ok_or_else(|| "identity lacks credentials".into())
+}
+
+/// Returns a pre-signed POST policy, e.g. so that an untrusted browser can upload an
+/// image of at most 5MB directly to S3 without a server round trip.  See
+/// `content_type_condition` regarding `allowed_content_types`; if it is empty, any
+/// content type is allowed.  The policy is signed with the credentials of `client`.
+pub async fn presigned_s3_post(
+    client: &S3Client,
+    bucket: &str,
+    key: &str,
+    max_size: u64,
+    allowed_content_types: &[&str],
+    expiry: Duration,
+) -> Result<S3PresignedPost, Error> {
+    let context = || format!("presigned_s3_post({bucket}, {key})");
+    let region = client
+        .config()
+        .region()
+        .ok_or_else(|| Error::String(format!("{}: no region", context())))?;
+    let presigning_config =
+        PresigningConfig::expires_in(expiry).map_err(|e| Error::Anyhow(e.into(), context()))?;
+    // The client's config exposes neither its endpoint nor its credentials, so presigning
+    // a request for the object (which is done locally) resolves the URL, respecting a
+    // custom `endpoint_url` and `force_path_style`, and captures the means to resolve
+    // the credentials.
+    let capture = CaptureRuntimeComponents::default();
+    let runtime_components = Arc::clone(&capture.0);
+    let object_request = client
+        .get_object()
+        .bucket(bucket)
+        .key(key)
+        .customize()
+        .interceptor(capture)
+        .presigned(presigning_config)
+        .await
+        .map_err(|e| Error::Anyhow(e.into(), context()))?;
+    let runtime_components = runtime_components
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| Error::String(format!("{}: no credentials", context())))?;
+    let credentials = resolve_credentials(&runtime_components)
+        .await
+        .map_err(|e| Error::String(format!("{}: no credentials: {e}", context())))?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    // Whole seconds, e.g. "2024-01-02T03:04:05Z".
+    let format = |time: Duration| {
+        DateTime::from_secs(time.as_secs() as i64)
+            .fmt(DateTimeFormat::DateTime)
+            .ok()
+    };
+    let (Some(now), Some(expiration)) = (format(now), format(now + expiry)) else {
+        return Err(Error::String(format!("{}: cannot format date", context())));
+    };
+    // E.g. "20240102T030405Z".
+    let amz_date = now.replace(['-', ':'], "");
+    let date = &amz_date[..8];
+    let credential = format!(
+        "{}/{date}/{region}/s3/aws4_request",
+        credentials.access_key_id()
+    );
+
+    let mut fields = vec![("key".to_string(), key.to_string())];
+    let mut conditions = vec![
+        serde_json::json!({ "bucket": bucket }),
+        serde_json::json!({ "key": key }),
+        serde_json::json!(["content-length-range", 0, max_size]),
+    ];
+    if let Some((condition, content_type)) = content_type_condition(allowed_content_types)? {
+        conditions.push(condition);
+        fields.extend(content_type.map(|content_type| ("Content-Type".to_string(), content_type)));
+    }
+    let mut amz_fields = vec![
+        ("x-amz-algorithm", "AWS4-HMAC-SHA256".to_string()),
+        ("x-amz-credential", credential),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(session_token) = credentials.session_token() {
+        amz_fields.push(("x-amz-security-token", session_token.to_string()));
+    }
+    for (name, value) in amz_fields {
+        conditions.push(serde_json::json!({ name: value }));
+        fields.push((name.to_string(), value));
+    }
+    let policy = serde_json::json!({
+        "expiration": expiration,
+        "conditions": conditions,
+    });
+    let policy = STANDARD.encode(policy.to_string());
+    let signature = sigv4_signature(
+        credentials.secret_access_key(),
+        date,
+        region.as_ref(),
+        &policy,
+    );
+    fields.push(("policy".to_string(), policy));
+    fields.push(("x-amz-signature".to_string(), signature));
+
+    Ok(S3PresignedPost {
+        url: post_url(object_request.uri(), key),
+        fields,
+    })
+}
+
 /// Put an object into the specified S3 bucket.
 pub async fn put_s3_item(
     client: &S3Client,
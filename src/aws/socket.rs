@@ -6,6 +6,7 @@ use axum::Router;
 use pnet::datalink::interfaces;
 use std::net::SocketAddr;
 use structopt::StructOpt;
+use tower_http::compression::CompressionLayer;
 use tower_http::services::ServeDir;
 
 #[derive(StructOpt)]
@@ -18,9 +19,15 @@ struct Options {
 
     #[structopt(long, default_value = "8080")]
     port: u16,
+
+    /// Compress responses (with gzip or deflate) if the client accepts it.
+    #[structopt(long)]
+    compression: bool,
 }
 
-/// Run an `axum::Router` on incoming requests from a socket.
+/// Run an `axum::Router` on incoming requests from a socket.  Command line options
+/// specify the `--host`, `--port`, a directory of `--html` files to serve, and whether
+/// to enable `--compression`.
 pub async fn run_router_on_socket(router: Router) -> Result<(), String> {
     let options = Options::from_args();
     let port = options.port;
@@ -43,6 +50,12 @@ pub async fn run_router_on_socket(router: Router) -> Result<(), String> {
         router
     };
 
+    let router = if options.compression {
+        router.layer(CompressionLayer::new())
+    } else {
+        router
+    };
+
     if let Some(addr) = addr {
         println!("Begin running router on socket {}", addr);
         let listener = tokio::net::TcpListener::bind(&addr)
@@ -3,12 +3,233 @@
 
 #[cfg(test)]
 mod aws_tests {
+    use crate::aws::bot::hostname_in_domain;
+    use crate::aws::dynamo::{redacted_key, with_ddb_retries, AwsEndpointConfig};
+    use crate::aws::s3::{content_type_condition, post_url, presigned_s3_post};
     use crate::aws::translate::{
-        braced_names, new_translate_client, to_names, to_numbers, translate_text,
+        braced_names, chunk_text, new_translate_client, to_names, to_numbers, translate_text,
+        validate_braces, BraceErrorKind, TranslationCache,
+    };
+    use crate::aws::{
+        b64_to_u64, ddb_update, load_aws_config, new_ddb_client, to_dynamo_av, u64_to_b64,
+        verify_bot_ip, with_health_route, with_readiness_route, with_request_id, BotClassifier,
+        BotKind, BotVerification, LambdaOptions,
     };
-    use crate::aws::{b64_to_u64, ddb_update, new_ddb_client, u64_to_b64};
     use crate::common::CubConfig;
 
+    #[test]
+    fn bot_kind_tests() {
+        let googlebot = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+        let ahrefs = "Mozilla/5.0 (compatible; AhrefsBot/7.0; +http://ahrefs.com/robot/)";
+        let human = "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/115.0";
+        let classifier = BotClassifier::new();
+        assert_eq!(classifier.classify(googlebot), BotKind::SearchEngine);
+        assert_eq!(classifier.classify(ahrefs), BotKind::Scraper);
+        assert_eq!(classifier.classify("Wget/1.21"), BotKind::Scraper);
+        assert_eq!(classifier.classify(human), BotKind::Unknown);
+
+        let classifier = BotClassifier::with_allowlist(["AhrefsBot"]);
+        assert_eq!(classifier.classify(googlebot), BotKind::Scraper);
+        assert_eq!(classifier.classify(ahrefs), BotKind::SearchEngine);
+    }
+
+    #[tokio::test]
+    async fn verify_bot_ip_tests() {
+        assert!(hostname_in_domain(
+            "crawl-66-249-66-1.googlebot.com",
+            "googlebot.com"
+        ));
+        assert!(hostname_in_domain(
+            "rate-limited-proxy.google.com.",
+            "google.com"
+        ));
+        assert!(hostname_in_domain("GoogleBot.com", "googlebot.com"));
+        assert!(!hostname_in_domain("fakegooglebot.com", "googlebot.com"));
+        assert!(!hostname_in_domain(
+            "googlebot.com.example.com",
+            "googlebot.com"
+        ));
+        assert!(!hostname_in_domain("com", "googlebot.com"));
+
+        let localhost = "127.0.0.1".parse().unwrap();
+        let human = "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/115.0";
+        assert_eq!(
+            verify_bot_ip(human, localhost).await,
+            BotVerification::NotApplicable
+        );
+        let googlebot = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+        assert_eq!(
+            verify_bot_ip(googlebot, localhost).await,
+            BotVerification::Unverified
+        );
+    }
+
+    #[tokio::test]
+    async fn health_route_tests() {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use axum::Router;
+        use lambda_runtime::Service;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        async fn status(router: &mut Router, path: &str) -> StatusCode {
+            let request = Request::get(path).body(Body::empty()).unwrap();
+            router.call(request).await.unwrap().status()
+        }
+
+        let mut router = with_health_route(Router::new());
+        assert_eq!(status(&mut router, "/healthz").await, StatusCode::OK);
+        assert_eq!(status(&mut router, "/readyz").await, StatusCode::NOT_FOUND);
+
+        let ready = Arc::new(AtomicBool::new(false));
+        let is_ready = Arc::clone(&ready);
+        let mut router = with_readiness_route(Router::new(), "/readyz", move || {
+            is_ready.load(Ordering::Relaxed)
+        });
+        assert_eq!(
+            status(&mut router, "/readyz").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        ready.store(true, Ordering::Relaxed);
+        assert_eq!(status(&mut router, "/readyz").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn request_id_tests() {
+        use crate::aws::lambda::ApiGatewayEvent;
+        use crate::aws::{RequestId, X_REQUEST_ID};
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use axum::routing::get;
+        use axum::{Extension, Router};
+        use lambda_runtime::Service;
+
+        let mut router = with_request_id(Router::new().route(
+            "/",
+            get(
+                |Extension(request_id): Extension<RequestId>| async move { request_id.to_string() },
+            ),
+        ));
+        let request = Request::get("/")
+            .header(X_REQUEST_ID, "abc")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.call(request).await.unwrap();
+        assert_eq!(response.headers()[X_REQUEST_ID], "abc");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "abc");
+
+        let request = Request::get("/").body(Body::empty()).unwrap();
+        let response = router.call(request).await.unwrap();
+        let generated = response.headers()[X_REQUEST_ID]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert!(generated.parse::<u64>().is_ok());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, generated);
+
+        let event: ApiGatewayEvent = serde_json::from_str(
+            r#"{"httpMethod": "GET", "path": "/", "requestContext": {"requestId": "gw-1"}}"#,
+        )
+        .unwrap();
+        let request = Request::<Body>::try_from(event).unwrap();
+        assert_eq!(request.headers()[X_REQUEST_ID], "gw-1");
+    }
+
+    #[test]
+    fn lambda_options_tests() {
+        let options = LambdaOptions::default();
+        assert!(options.is_binary("image/png"));
+        assert!(options.is_binary("Application/Octet-Stream; charset=binary"));
+        assert!(!options.is_binary("application/json"));
+        let options = options.binary_media_types(["image/*", "font/woff2"]);
+        assert!(options.is_binary("image/avif"));
+        assert!(options.is_binary("font/woff2"));
+        assert!(!options.is_binary("application/octet-stream"));
+        let options = options.binary_media_types(["*/*"]);
+        assert!(options.is_binary("text/html"));
+    }
+
+    #[test]
+    fn presigned_post_tests() {
+        assert_eq!(
+            post_url(
+                "https://bucket.s3.us-east-1.amazonaws.com/a/b.png?X-Amz-Signature=1",
+                "a/b.png"
+            ),
+            "https://bucket.s3.us-east-1.amazonaws.com/"
+        );
+        assert_eq!(
+            post_url(
+                "http://localhost:9000/bucket/b.png?X-Amz-Signature=1",
+                "b.png"
+            ),
+            "http://localhost:9000/bucket/"
+        );
+
+        let condition = |types: &[&str]| {
+            content_type_condition(types)
+                .map(|condition| condition.map(|(value, field)| (value.to_string(), field)))
+                .map_err(|_| ())
+        };
+        assert_eq!(condition(&[]).unwrap(), None);
+        assert_eq!(
+            condition(&["image/png"]).unwrap(),
+            Some((
+                r#"{"Content-Type":"image/png"}"#.to_string(),
+                Some("image/png".to_string())
+            ))
+        );
+        let image = Some((
+            r#"["starts-with","$Content-Type","image/"]"#.to_string(),
+            None,
+        ));
+        assert_eq!(condition(&["image/*"]).unwrap(), image);
+        assert_eq!(condition(&["*/*"]).unwrap(), None);
+        // Several exact types must not be widened to their common prefix.
+        assert!(condition(&["image/png", "image/jpeg"]).is_err());
+        assert!(condition(&["image/*", "image/png"]).is_err());
+        assert!(condition(&["image/png", "video/mp4"]).is_err());
+        assert!(condition(&["image*"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn presigned_post_credentials_tests() {
+        use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+        use std::time::Duration;
+
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("AKIDTEST", "secret", None, None, "test"))
+            .endpoint_url("http://localhost:9000")
+            .force_path_style(true)
+            .build();
+        let client = aws_sdk_s3::Client::from_conf(config);
+        let post = presigned_s3_post(
+            &client,
+            "bucket",
+            "a/b.png",
+            1024,
+            &["image/png"],
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+        assert_eq!(post.url, "http://localhost:9000/bucket/");
+        let field = |name: &str| {
+            post.fields
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(field("Content-Type"), Some("image/png"));
+        assert!(field("x-amz-credential").unwrap().starts_with("AKIDTEST/"));
+        assert!(field("x-amz-security-token").is_none());
+    }
+
     #[test]
     fn b64_tests() {
         println!("Testing b64");
@@ -22,6 +243,43 @@ mod aws_tests {
         println!("n1 = {} => {} (len {}) => {}", n2, s2, s2.len(), t2);
     }
 
+    #[test]
+    fn endpoint_config_tests() {
+        let cub_config = CubConfig::builder()
+            .toml_str(
+                r#"
+                [aws]
+                endpoint_url = "http://localhost:9000"
+                force_path_style = true
+            "#,
+            )
+            .build()
+            .expect("endpoint_config_tests.toml");
+        let endpoint_config = AwsEndpointConfig::new(&cub_config);
+        assert_eq!(
+            endpoint_config.endpoint_url.as_deref(),
+            Some("http://localhost:9000")
+        );
+        assert!(endpoint_config.force_path_style);
+        let cub_config = CubConfig::builder()
+            .toml_str("[aws]\nprofile = \"test_profile\"\n")
+            .build()
+            .expect("endpoint_config_tests.toml");
+        let endpoint_config = AwsEndpointConfig::new(&cub_config);
+        assert!(endpoint_config.endpoint_url.is_none());
+        assert!(!endpoint_config.force_path_style);
+    }
+
+    #[tokio::test]
+    async fn region_config_tests() {
+        let cub_config = CubConfig::builder()
+            .toml_str("[aws]\nregion = \"eu-west-1\"\n")
+            .build()
+            .expect("region_config_tests.toml");
+        let aws_config = load_aws_config(&cub_config).await;
+        assert_eq!(aws_config.region().map(|r| r.as_ref()), Some("eu-west-1"));
+    }
+
     #[tokio::test]
     async fn ddb_update_tests() {
         let cub_config = CubConfig::builder()
@@ -40,7 +298,7 @@ mod aws_tests {
         let x: u32 = 1;
         let y: u32 = 2;
         let z: Option<u32> = None;
-        match ddb_update(&ddb_client, "NoSuchTable", "NoSuchHash", &h)
+        match ddb_update(&ddb_client, "NoSuchTable", "NoSuchHash", h)
             .expect("ddb_update failed")
             .volatile_attribute("j", j)
             .expect("volatile attribute failed")
@@ -62,6 +320,151 @@ mod aws_tests {
         }
     }
 
+    #[tokio::test]
+    async fn ddb_update_collection_tests() {
+        let cub_config = CubConfig::builder()
+            .toml_str(
+                r#"
+                [aws]
+                profile = "test_profile"
+                "#,
+            )
+            .build()
+            .expect("update_collection_tests.toml");
+        let ddb_client = new_ddb_client(&cub_config).await;
+        let builder = ddb_update(&ddb_client, "NoSuchTable", "h", 0u32)
+            .expect("ddb_update failed")
+            .attribute("x", 1u32)
+            .expect("x attribute failed")
+            .list_append("history", [1u32, 2])
+            .expect("list_append failed")
+            .attribute("history_empty_list", 3u32)
+            .expect("history_empty_list attribute failed")
+            .add_to_set("tags", ["a", "b"])
+            .expect("add_to_set failed")
+            .set_if_not_exists("count", 0u32)
+            .expect("set_if_not_exists failed")
+            .optional_attribute("z", None::<u32>)
+            .expect("z optional_attribute failed");
+        assert_eq!(
+            builder.expression(),
+            "SET #x = :x, #history_empty_list = :history_empty_list, #history = list_append(if_not_exists(#history, :__empty_list_history), :history), #count = if_not_exists(#count, :count) ADD #tags :tags REMOVE #z"
+        );
+        assert!(builder.add_to_set("tags", ["c"]).is_err());
+        let builder = ddb_update(&ddb_client, "NoSuchTable", "h", 0u32).expect("ddb_update failed");
+        assert!(builder.add_to_set("empty", Vec::<String>::new()).is_err());
+    }
+
+    #[test]
+    fn redacted_key_tests() {
+        let id = to_dynamo_av(12345u64).unwrap();
+        assert_eq!(redacted_key(&id), "12345");
+        let email = to_dynamo_av("someone@example.com").unwrap();
+        let redacted = redacted_key(&email);
+        println!("{redacted}");
+        assert!(!redacted.contains("someone"));
+        assert_eq!(redacted, redacted_key(&email));
+    }
+
+    #[tokio::test]
+    async fn ddb_retry_tests() {
+        use aws_sdk_dynamodb::types::error::ProvisionedThroughputExceededException;
+        use aws_sdk_dynamodb::Error as DynamoError;
+
+        let mut attempts = 0;
+        let result: Result<(), DynamoError> = with_ddb_retries(|| {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                if attempt < 3 {
+                    Err(DynamoError::ProvisionedThroughputExceededException(
+                        ProvisionedThroughputExceededException::builder().build(),
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn chunk_text_tests() {
+        assert_eq!(chunk_text("", 10), vec![""]);
+        assert_eq!(chunk_text("Short.", 10), vec!["Short."]);
+
+        let text = "One two. Three four.\n\nFive six seven.";
+        let chunks = chunk_text(text, 24);
+        assert_eq!(chunks, vec!["One two. Three four.\n\n", "Five six seven."]);
+        assert_eq!(chunks.concat(), text);
+
+        // Prefer sentences to words, and never split a braced name.
+        assert_eq!(
+            chunk_text("Hi there. The {long name} here", 18),
+            vec!["Hi there. ", "The {long name} ", "here"]
+        );
+        assert_eq!(chunk_text("ab {cdef} gh", 7), vec!["ab ", "{cdef} ", "gh"]);
+
+        // Multi-byte characters are never split.
+        let text = "\u{e9}".repeat(10);
+        let chunks = chunk_text(&text, 5);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 5));
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn translation_cache_tests() {
+        let key = |text: &str| ("en".to_string(), "es".to_string(), text.to_string());
+        let mut cache = TranslationCache::new(2);
+        assert_eq!(cache.get(&key("cat")), None);
+        cache.insert(key("cat"), "gato".to_string());
+        cache.insert(key("dog"), "perro".to_string());
+        assert_eq!(cache.get(&key("cat")).as_deref(), Some("gato"));
+        // "dog" is now the least recently used.
+        cache.insert(key("hat"), "sombrero".to_string());
+        assert_eq!(cache.get(&key("dog")), None);
+        assert_eq!(cache.get(&key("hat")).as_deref(), Some("sombrero"));
+        assert_eq!(cache.get(&key("cat")).as_deref(), Some("gato"));
+        let stats = cache.stats();
+        assert_eq!(
+            (stats.hits, stats.misses, stats.len, stats.capacity),
+            (3, 2, 2, 2)
+        );
+        cache.clear();
+        assert_eq!(cache.stats().len, 0);
+        assert_eq!(cache.get(&key("cat")), None);
+    }
+
+    #[test]
+    fn validate_braces_tests() {
+        let source = "You have {count} new {kind} messages";
+        let error = |target| validate_braces(source, target).map_err(|e| (e.kind, e.offset));
+        assert_eq!(error("Tienes {count} mensajes {kind} nuevos"), Ok(()));
+        assert_eq!(
+            error("Tienes { count} mensajes {kind}"),
+            Err((BraceErrorKind::AlteredName(" count".to_string()), 7))
+        );
+        assert_eq!(
+            error("Tienes {count mensajes {kind}"),
+            Err((BraceErrorKind::UnmatchedOpen, 7))
+        );
+        assert_eq!(
+            error("Tienes count} mensajes {kind}"),
+            Err((BraceErrorKind::UnmatchedClose, 12))
+        );
+        assert_eq!(
+            error("Tienes {count} mensajes {kind"),
+            Err((BraceErrorKind::UnmatchedOpen, 24))
+        );
+        assert_eq!(
+            error("Tienes {count} mensajes"),
+            Err((BraceErrorKind::MissingName("kind".to_string()), 23))
+        );
+        assert_eq!(error("{{literal}} {count} {kind}"), Ok(()));
+    }
+
     #[tokio::test]
     async fn translate_tests() {
         println!("Testing translate");
@@ -73,6 +476,21 @@ mod aws_tests {
         let b = to_names(&a, &vars);
         println!("to_name: {b}");
 
+        // A malformed setting means no cache, rather than a panic.
+        let cub_config = CubConfig::builder()
+            .toml_str(
+                r#"
+                [aws]
+                translate_cache_size = "many"
+                "#,
+            )
+            .build()
+            .expect("translate_tests.toml");
+        assert!(new_translate_client(&cub_config)
+            .await
+            .cache_stats()
+            .is_none());
+
         let cub_config = CubConfig::builder()
             .toml_str(
                 r#"
@@ -83,6 +501,7 @@ mod aws_tests {
             .build()
             .expect("translate_tests.toml");
         let client = new_translate_client(&cub_config).await;
+        assert!(client.cache_stats().is_none());
         let source_language_code = "en";
         let target_language_code = "es";
         let english_text = "The cat {name} and the hat";
@@ -98,4 +517,155 @@ mod aws_tests {
             _ => println!("cannot translate"),
         }
     }
+
+    #[cfg(feature = "ddb_metrics")]
+    #[test]
+    fn ddb_metrics_hook_tests() {
+        use crate::aws::dynamo::record_ddb_consumed_capacity;
+        use crate::aws::{clear_ddb_metrics_hook, set_ddb_metrics_hook, DdbConsumedCapacity};
+        use aws_sdk_dynamodb::types::ConsumedCapacity;
+        use std::sync::{Arc, Mutex};
+
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let hook_recorded = Arc::clone(&recorded);
+        set_ddb_metrics_hook(move |c: &DdbConsumedCapacity| {
+            hook_recorded.lock().unwrap().push(*c);
+        });
+        let consumed = ConsumedCapacity::builder().capacity_units(1.5).build();
+        record_ddb_consumed_capacity("get_item", "users", Some(&consumed));
+        record_ddb_consumed_capacity("get_item", "users", None);
+        clear_ddb_metrics_hook();
+        record_ddb_consumed_capacity("put_item", "users", Some(&consumed));
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            vec![DdbConsumedCapacity {
+                operation: "get_item",
+                table: "users",
+                capacity_units: 1.5,
+            }]
+        );
+    }
+
+    /// Requires a local Dynamo DB, e.g. `docker run -p 8000:8000 amazon/dynamodb-local`,
+    /// at `DDB_LOCAL_ENDPOINT` (default `http://localhost:8000`).
+    #[cfg(feature = "ddb_local")]
+    #[tokio::test]
+    async fn ddb_local_tests() {
+        use crate::aws::{
+            create_ddb_item, ddb_ranged_update, delete_ddb_ranged_item, get_ddb_ranged_item,
+            put_ddb_item, query_ddb, scan_ddb, update_ddb_item,
+        };
+        use aws_sdk_dynamodb::types::{
+            AttributeDefinition, BillingMode, KeySchemaElement, KeyType, ScalarAttributeType,
+        };
+        use serde::{Deserialize, Serialize};
+
+        const TABLE: &str = "CubLocalTests";
+
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Item {
+            h: u32,
+            r: u32,
+            name: String,
+            version: usize,
+        }
+
+        let endpoint_url = std::env::var("DDB_LOCAL_ENDPOINT")
+            .unwrap_or_else(|_| "http://localhost:8000".to_string());
+        let cub_config = CubConfig::builder()
+            .toml_str(&format!(
+                r#"
+                [aws]
+                endpoint_url = "{endpoint_url}"
+                region = "us-east-1"
+                access_key_id = "local"
+                secret_access_key = "local"
+                "#
+            ))
+            .build()
+            .expect("ddb_local_tests.toml");
+        let client = new_ddb_client(&cub_config).await;
+
+        // Start from scratch, in case a previous run failed.
+        let _ = client.delete_table().table_name(TABLE).send().await;
+        let key = |name: &str, key_type| {
+            KeySchemaElement::builder()
+                .attribute_name(name)
+                .key_type(key_type)
+                .build()
+                .unwrap()
+        };
+        let attribute = |name: &str| {
+            AttributeDefinition::builder()
+                .attribute_name(name)
+                .attribute_type(ScalarAttributeType::N)
+                .build()
+                .unwrap()
+        };
+        client
+            .create_table()
+            .table_name(TABLE)
+            .key_schema(key("h", KeyType::Hash))
+            .key_schema(key("r", KeyType::Range))
+            .attribute_definitions(attribute("h"))
+            .attribute_definitions(attribute("r"))
+            .billing_mode(BillingMode::PayPerRequest)
+            .send()
+            .await
+            .expect("create table");
+
+        let item = |r: u32, name: &str, version| Item {
+            h: 1,
+            r,
+            name: name.to_string(),
+            version,
+        };
+        create_ddb_item(&client, item(1, "one", 0), TABLE, "h")
+            .await
+            .expect("create");
+        assert!(create_ddb_item(&client, item(1, "again", 0), TABLE, "h")
+            .await
+            .is_err());
+        put_ddb_item(&client, item(2, "two", 0), TABLE)
+            .await
+            .expect("put");
+
+        let got: Option<Item> = get_ddb_ranged_item(&client, TABLE, "h", 1u32, "r", 2u32)
+            .await
+            .expect("get");
+        assert_eq!(got, Some(item(2, "two", 0)));
+        let queried: Vec<Item> = query_ddb(&client, TABLE, "h", 1u32, false)
+            .await
+            .expect("query");
+        assert_eq!(queried.len(), 2);
+
+        update_ddb_item(&client, item(2, "deux", 1), TABLE, "h", "version", 1)
+            .await
+            .expect("update");
+        ddb_ranged_update(&client, TABLE, "h", 1u32, "r", 1u32)
+            .expect("ddb_ranged_update")
+            .attribute("name", "uno")
+            .expect("name attribute")
+            .send()
+            .await
+            .expect("ranged update");
+        let mut scanned: Vec<Item> = scan_ddb(&client, TABLE).await.expect("scan");
+        scanned.sort_by_key(|item| item.r);
+        assert_eq!(scanned, vec![item(1, "uno", 0), item(2, "deux", 1)]);
+
+        delete_ddb_ranged_item(&client, TABLE, "h", &1u32, "r", &1u32)
+            .await
+            .expect("delete");
+        let got: Option<Item> = get_ddb_ranged_item(&client, TABLE, "h", 1u32, "r", 1u32)
+            .await
+            .expect("get deleted");
+        assert!(got.is_none());
+
+        client
+            .delete_table()
+            .table_name(TABLE)
+            .send()
+            .await
+            .expect("delete table");
+    }
 }
@@ -4,15 +4,157 @@
 use super::load_aws_config;
 use crate::common::{CubConfig, Error};
 use aws_sdk_translate::Client;
-use std::collections::HashSet;
+use hyper::StatusCode;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::sync::Mutex;
 
-/// A convenient alias for translate client so consuming code doesn't need to add it to `Cargo.toml`
-pub type TranslateClient = aws_sdk_translate::Client;
+/// AWS Translate rejects text longer than this many (UTF-8) bytes.
+const MAX_TRANSLATE_BYTES: usize = 10_000;
 
-/// Creates a Translate client.
+/// A Translate client, optionally with an in-memory cache of translations.  It
+/// dereferences to `aws_sdk_translate::Client`, so consuming code doesn't need to add
+/// it to `Cargo.toml`.
+pub struct TranslateClient {
+    cache: Option<Mutex<TranslationCache>>,
+    client: Client,
+}
+
+impl TranslateClient {
+    /// Caches up to `capacity` translations, evicting the least recently used.  A
+    /// `capacity` of zero disables the cache.
+    pub fn with_cache(mut self, capacity: usize) -> Self {
+        self.cache = (capacity != 0).then(|| Mutex::new(TranslationCache::new(capacity)));
+        self
+    }
+
+    /// Removes all cached translations (but not the statistics).
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.lock().unwrap().clear();
+        }
+    }
+
+    /// Returns cache statistics, or `None` if there is no cache.
+    pub fn cache_stats(&self) -> Option<TranslationCacheStats> {
+        self.cache
+            .as_ref()
+            .map(|cache| cache.lock().unwrap().stats())
+    }
+}
+
+impl std::ops::Deref for TranslateClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+/// Statistics of the translation cache of a `TranslateClient`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TranslationCacheStats {
+    /// The maximum number of cached translations.
+    pub capacity: usize,
+    /// The number of translations served from the cache.
+    pub hits: u64,
+    /// The number of cached translations.
+    pub len: usize,
+    /// The number of translations not found in the cache.
+    pub misses: u64,
+}
+
+/// (source language code, target language code, source text).
+type TranslationKey = (String, String, String);
+
+/// A least recently used cache of translations.
+pub(crate) struct TranslationCache {
+    /// Translations and when each was last used.
+    entries: HashMap<TranslationKey, (String, u64)>,
+    /// Keys by when each was last used, oldest first.
+    recency: BTreeMap<u64, TranslationKey>,
+    stats: TranslationCacheStats,
+    tick: u64,
+}
+
+impl TranslationCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            stats: TranslationCacheStats {
+                capacity,
+                ..Default::default()
+            },
+            tick: 0,
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    pub(crate) fn get(&mut self, key: &TranslationKey) -> Option<String> {
+        self.tick += 1;
+        let Some((target_text, last_used)) = self.entries.get_mut(key) else {
+            self.stats.misses += 1;
+            return None;
+        };
+        self.stats.hits += 1;
+        let key = self.recency.remove(last_used).unwrap();
+        *last_used = self.tick;
+        let target_text = target_text.clone();
+        self.recency.insert(self.tick, key);
+        Some(target_text)
+    }
+
+    pub(crate) fn insert(&mut self, key: TranslationKey, target_text: String) {
+        self.tick += 1;
+        if let Some((_, last_used)) = self.entries.remove(&key) {
+            self.recency.remove(&last_used);
+        } else if self.entries.len() >= self.stats.capacity {
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(key, (target_text, self.tick));
+    }
+
+    pub(crate) fn stats(&self) -> TranslationCacheStats {
+        TranslationCacheStats {
+            len: self.entries.len(),
+            ..self.stats
+        }
+    }
+}
+
+/// Creates a Translate client.  If the `aws` config specifies a `translate_cache_size`,
+/// up to that many translations are cached (see `TranslateClient::with_cache`).
 pub async fn new_translate_client(cub_config: &CubConfig) -> TranslateClient {
+    #[derive(Default, Deserialize)]
+    struct AwsTranslateConfig {
+        translate_cache_size: Option<usize>,
+    }
+    #[derive(Deserialize)]
+    struct ConfigToml {
+        #[serde(default)]
+        aws: AwsTranslateConfig,
+    }
+    // A missing or malformed `aws` section means no cache.
+    let translate_cache_size = cub_config
+        .get()
+        .map(|ConfigToml { aws }| aws)
+        .unwrap_or_default()
+        .translate_cache_size;
     let aws_config = load_aws_config(cub_config).await;
-    Client::new(&aws_config)
+    TranslateClient {
+        cache: None,
+        client: Client::new(&aws_config),
+    }
+    .with_cache(translate_cache_size.unwrap_or_default())
 }
 
 /// Returns braced names which appear in a string.  For example, `{me}`.
@@ -50,6 +192,85 @@ pub fn braces_valid(source_text: &str, target_text: &str) -> bool {
         .any(|name| !target_names.contains(&name))
 }
 
+/// Why `validate_braces` rejected a translation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BraceErrorKind {
+    /// A `{` is never closed.
+    UnmatchedOpen,
+    /// A `}` was never opened.
+    UnmatchedClose,
+    /// A braced name is not in the source, e.g. `{ count}` instead of `{count}`.
+    AlteredName(String),
+    /// A braced name of the source is not in the target.
+    MissingName(String),
+}
+
+/// The location and kind of a brace error in a translation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BraceError {
+    /// The kind of error.
+    pub kind: BraceErrorKind,
+    /// The byte offset in the target text of the offending brace, or the length of
+    /// the target text for a `MissingName`.
+    pub offset: usize,
+}
+
+impl Display for BraceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let offset = self.offset;
+        match &self.kind {
+            BraceErrorKind::UnmatchedOpen => write!(f, "unmatched {{ at {offset}"),
+            BraceErrorKind::UnmatchedClose => write!(f, "unmatched }} at {offset}"),
+            BraceErrorKind::AlteredName(name) => write!(f, "altered {{{name}}} at {offset}"),
+            BraceErrorKind::MissingName(name) => write!(f, "missing {{{name}}}"),
+        }
+    }
+}
+
+/// Like `braces_valid`, but also checks that the braces of the target are balanced and
+/// that it has no braced names absent from the source, and reports the first error.
+/// Doubled braces (`{{` and `}}`) are literals, as in `braced_names`.
+pub fn validate_braces(source_text: &str, target_text: &str) -> Result<(), BraceError> {
+    let source_names: HashSet<_> = braced_names(source_text).into_iter().collect();
+    let mut target_names = HashSet::new();
+    let error = |kind, offset| Err(BraceError { kind, offset });
+    let mut open: Option<usize> = None;
+    let mut chars = target_text.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        match (ch, open) {
+            ('{', None) => open = Some(i),
+            ('{', Some(o)) if o + 1 == i => open = None,
+            ('{', Some(o)) => return error(BraceErrorKind::UnmatchedOpen, o),
+            ('}', Some(o)) => {
+                let name = &target_text[o + 1..i];
+                if !source_names.contains(name) {
+                    return error(BraceErrorKind::AlteredName(name.to_owned()), o);
+                }
+                target_names.insert(name);
+                open = None;
+            }
+            ('}', None) if chars.next_if(|&(_, next)| next == '}').is_some() => {}
+            ('}', None) => return error(BraceErrorKind::UnmatchedClose, i),
+            _ => {}
+        }
+    }
+    if let Some(o) = open {
+        return error(BraceErrorKind::UnmatchedOpen, o);
+    }
+    let mut missing: Vec<_> = source_names
+        .iter()
+        .filter(|name| !target_names.contains(name.as_str()))
+        .collect();
+    missing.sort();
+    match missing.first() {
+        Some(name) => error(
+            BraceErrorKind::MissingName(name.to_string()),
+            target_text.len(),
+        ),
+        None => Ok(()),
+    }
+}
+
 /// Replaces braced numbers with braced names in a string.
 pub(crate) fn to_names(source_text: &str, vars: &Vec<String>) -> String {
     let mut result = source_text.to_owned();
@@ -70,24 +291,102 @@ pub(crate) fn to_numbers(source_text: &str, vars: &Vec<String>) -> String {
     result
 }
 
-/// Translates text from one language to another.
+/// Splits text into chunks of at most `max_bytes`, preferring paragraph, line, sentence,
+/// and then word boundaries, and never splitting a braced name.  Each chunk retains its
+/// trailing whitespace, so concatenating the chunks reproduces the text.
+pub(crate) fn chunk_text(text: &str, max_bytes: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut remaining = text;
+    while remaining.len() > max_bytes {
+        let (chunk, rest) = remaining.split_at(split_point(remaining, max_bytes));
+        chunks.push(chunk);
+        remaining = rest;
+    }
+    if !remaining.is_empty() || chunks.is_empty() {
+        chunks.push(remaining);
+    }
+    chunks
+}
+
+/// Returns the best (non-zero) index at which to split `text` so the first part is at
+/// most `max_bytes`, if possible.
+fn split_point(text: &str, max_bytes: usize) -> usize {
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    if end == 0 {
+        // Not even one character fits.
+        return text.chars().next().map_or(0, char::len_utf8);
+    }
+    let window = &text[..end];
+    let outside_braces = |i: usize| match window[..i].rfind('{') {
+        Some(open) => window[..i].rfind('}').is_some_and(|close| close > open),
+        None => true,
+    };
+    for boundary in ["\n\n", "\n", ". ", "! ", "? ", "\u{3002}", " "] {
+        if let Some(i) = window
+            .rmatch_indices(boundary)
+            .map(|(i, _)| i + boundary.len())
+            .find(|&i| outside_braces(i))
+        {
+            return i;
+        }
+    }
+    (1..end)
+        .rev()
+        .find(|&i| window.is_char_boundary(i) && outside_braces(i))
+        .unwrap_or(end)
+}
+
+/// Translates text from one language to another.  Text longer than AWS Translate
+/// accepts is translated in chunks (see `chunk_text`), which are then reassembled.
+/// Fails if the translation lost any of the braced names, e.g. `{name}`, in the source.
+/// Translations are served from the client's cache, if any.
 pub async fn translate_text(
     client: &TranslateClient,
     source_text: &str,
     source_language_code: &str,
     target_language_code: &str,
 ) -> Result<String, Error> {
+    let key = (
+        source_language_code.to_owned(),
+        target_language_code.to_owned(),
+        source_text.to_owned(),
+    );
+    if let Some(cache) = &client.cache {
+        if let Some(target_text) = cache.lock().unwrap().get(&key) {
+            return Ok(target_text);
+        }
+    }
     let vars = braced_names(source_text);
-    let source_text = to_numbers(source_text, &vars);
-    let output = client
-        .translate_text()
-        .source_language_code(source_language_code.to_owned())
-        .target_language_code(target_language_code.to_owned())
-        .text(&source_text)
-        .send()
-        .await
-        .map_err(|e| Error::Anyhow(e.into(), format!("translate_text({source_text})")))?;
-    let target_text = output.translated_text();
-    let target_text = to_names(target_text, &vars);
+    let numbered_text = to_numbers(source_text, &vars);
+    let mut target_text = String::with_capacity(numbered_text.len());
+    for chunk in chunk_text(&numbered_text, MAX_TRANSLATE_BYTES) {
+        // Translation may not preserve trailing whitespace, such as paragraph breaks.
+        let trimmed = chunk.trim_end();
+        if !trimmed.is_empty() {
+            let output = client
+                .translate_text()
+                .source_language_code(source_language_code.to_owned())
+                .target_language_code(target_language_code.to_owned())
+                .text(trimmed)
+                .send()
+                .await
+                .map_err(|e| Error::Anyhow(e.into(), format!("translate_text({trimmed})")))?;
+            target_text.push_str(output.translated_text());
+        }
+        target_text.push_str(&chunk[trimmed.len()..]);
+    }
+    let target_text = to_names(&target_text, &vars);
+    if !braces_valid(source_text, &target_text) {
+        return Err(Error::Http(
+            StatusCode::FAILED_DEPENDENCY,
+            format!("translate_text: braced names altered in {target_text}"),
+        ));
+    }
+    if let Some(cache) = &client.cache {
+        cache.lock().unwrap().insert(key, target_text.clone());
+    }
     Ok(target_text)
 }
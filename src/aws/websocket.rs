@@ -1,11 +1,13 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::create_aws_config_loader;
+use super::{create_aws_config_loader, delete_ddb_item, scan_ddb, DynamoDbClient};
 use crate::common::{CubConfig, Error};
 use aws_sdk_apigatewaymanagement::primitives::Blob;
 use aws_sdk_apigatewaymanagement::Client;
+use futures::StreamExt;
 use serde::Deserialize;
+use std::collections::HashMap;
 
 /// A convenient alias for websocket client so consuming code doesn't need to add it to `Cargo.toml`
 pub type WebsocketClient = aws_sdk_apigatewaymanagement::Client;
@@ -46,3 +48,73 @@ pub async fn send_ws_message(
         .map_err(|e| Error::Anyhow(e.into(), format!("send_ws_message({connection_id})")))?;
     Ok(())
 }
+
+/// The outcome of `broadcast_to_table`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BroadcastSummary {
+    /// The number of connections to which the message was sent.
+    pub sent: usize,
+    /// The number of connections which were gone, and were deleted from the table.
+    pub removed: usize,
+    /// The number of connections to which the message could not be sent, or which
+    /// could not be deleted.
+    pub failed: usize,
+}
+
+/// Send a message to every connection in a Dynamo DB table, whose hash key is the
+/// (string) connection ID, deleting the rows of connections which are gone (410).
+pub async fn broadcast_to_table(
+    ws_client: &WebsocketClient,
+    ddb_client: &DynamoDbClient,
+    table: &'static str,
+    hash_name: &'static str,
+    message: &[u8],
+) -> Result<BroadcastSummary, Error> {
+    /// Maximum number of concurrent requests.
+    const CONCURRENCY: usize = 16;
+    let rows: Vec<HashMap<String, serde_json::Value>> = scan_ddb(ddb_client, table).await?;
+    let connection_ids = rows
+        .into_iter()
+        .filter_map(|mut row| match row.remove(hash_name) {
+            Some(serde_json::Value::String(connection_id)) => Some(connection_id),
+            _ => None,
+        });
+    enum Outcome {
+        Sent,
+        Removed,
+        Failed,
+    }
+    let summary = futures::stream::iter(connection_ids)
+        .map(|connection_id| async move {
+            let result = ws_client
+                .post_to_connection()
+                .connection_id(&connection_id)
+                .data(Blob::new(message))
+                .send()
+                .await;
+            match result {
+                Ok(_) => Outcome::Sent,
+                Err(e) if e.as_service_error().is_some_and(|e| e.is_gone_exception()) => {
+                    match delete_ddb_item(ddb_client, table, hash_name, &connection_id).await {
+                        Ok(()) => Outcome::Removed,
+                        Err(_) => Outcome::Failed,
+                    }
+                }
+                Err(_) => Outcome::Failed,
+            }
+        })
+        .buffer_unordered(CONCURRENCY)
+        .fold(
+            BroadcastSummary::default(),
+            |mut summary, outcome| async move {
+                match outcome {
+                    Outcome::Sent => summary.sent += 1,
+                    Outcome::Removed => summary.removed += 1,
+                    Outcome::Failed => summary.failed += 1,
+                }
+                summary
+            },
+        )
+        .await;
+    Ok(summary)
+}
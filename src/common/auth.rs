@@ -2,14 +2,46 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::str::FromStr;
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
-/// This is used, for example, with Oauth2 and JWT authentication.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+/// This is used, for example, with Oauth2 and JWT authentication.  It is serialized as
+/// a plain string, e.g. `"google/123"`.
 pub struct AuthenticatedId(pub String);
 crate::impl_wrapper_str!(AuthenticatedId);
 
-/// The `Identity` struct is returned upon successful `OAuth2` authentication.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+impl FromStr for AuthenticatedId {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl AuthenticatedId {
+    /// Returns the provider prefix, e.g. "google" for "google/123".
+    pub fn provider(&self) -> Option<&str> {
+        self.split().map(|(provider, _)| provider)
+    }
+
+    /// Returns the provider-specific ID, e.g. "123" for "google/123".
+    pub fn subject(&self) -> Option<&str> {
+        self.split().map(|(_, subject)| subject)
+    }
+
+    fn split(&self) -> Option<(&str, &str)> {
+        self.0
+            .split_once('/')
+            .filter(|(provider, subject)| !provider.is_empty() && !subject.is_empty())
+    }
+}
+
+/// The `Identity` struct is returned upon successful `OAuth2` authentication.  It is
+/// serialized as `{"login_id": "google/123", "user_name": "Alice"}`, where `user_name`
+/// may be `null`, so it may be persisted (e.g. in a cookie or JWT) and restored.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 pub struct Identity {
     /// The login ID of the authenticated user.
     pub login_id: AuthenticatedId,
@@ -17,7 +49,16 @@ pub struct Identity {
     pub user_name: Option<UserName>,
 }
 
-#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
-/// A user name.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+/// A user name.  It is serialized as a plain string.
 pub struct UserName(pub String);
 crate::impl_wrapper_str!(UserName);
+
+impl FromStr for UserName {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_string()))
+    }
+}
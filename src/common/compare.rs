@@ -0,0 +1,14 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+/// Returns `true` if `a` and `b` are equal, taking the same time regardless of where
+/// (or whether) they differ, so that e.g. signature checks don't leak timing information.
+/// Only the lengths (which are usually public) may affect the time taken.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    // Prevent the compiler from short-circuiting the fold.
+    std::hint::black_box(difference) == 0
+}
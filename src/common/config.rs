@@ -29,6 +29,14 @@ impl CubConfig {
         self.debug_enabled
     }
 
+    /// Returns `true` if the configuration has the specified top-level section (table),
+    /// e.g. `[linode]`.
+    pub fn has_section(&self, name: &str) -> bool {
+        toml::from_str::<toml::Table>(&self.toml)
+            .map(|table| table.get(name).is_some_and(toml::Value::is_table))
+            .unwrap_or(false)
+    }
+
     /// Returns configuration parameters.
     pub fn get<T: DeserializeOwned>(&self) -> Result<T, Error> {
         toml::from_str(&self.toml).map_err(|e: toml::de::Error| Error::String(format!("toml: {e}")))
@@ -41,7 +41,7 @@ pub enum Error {
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         match self {
-            #[cfg(feature = "aws")]
+            #[cfg(feature = "hyper")]
             Error::Http(status_code, mesg) => Display::fmt(&format!("{status_code}: {mesg}"), f),
             #[cfg(feature = "aws")]
             Error::Dynamo(DynamoError::ConditionalCheckFailedException(_), source) => {
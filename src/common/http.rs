@@ -2,10 +2,13 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 use super::Error;
+#[cfg(feature = "axum")]
 use axum::body::Body;
+#[cfg(feature = "axum")]
 use axum::response::{IntoResponse, Response};
 use hyper::StatusCode;
 
+#[cfg(feature = "axum")]
 /// Create a `Response` suitable for `axum::Router`.
 pub fn create_error_response(status: StatusCode, message: String) -> Response {
     Response::builder()
@@ -16,6 +19,7 @@ pub fn create_error_response(status: StatusCode, message: String) -> Response {
         .into_response()
 }
 
+#[cfg(feature = "axum")]
 /// Convert `Error` into a `Response` suitable for `axum::Router`.
 impl Into<Response> for Error {
     fn into(self) -> Response {
@@ -29,6 +33,7 @@ impl Into<Response> for Error {
                 create_error_response(StatusCode::FAILED_DEPENDENCY, format!("{s}: {e:?}"))
             }
             Error::Http(code, mesg) => create_error_response(code, mesg),
+            #[cfg(feature = "aws")]
             Error::Serde(e) => {
                 create_error_response(StatusCode::UNPROCESSABLE_ENTITY, format!("{e:?}"))
             }
@@ -38,6 +43,17 @@ impl Into<Response> for Error {
 }
 
 impl Error {
+    /// Map the status of a failed request to a cloud provider (e.g. Linode or Youtube)
+    /// to `Error`.  Not found and authorization failures keep their status, so they
+    /// can be passed on, whereas other failures are `FAILED_DEPENDENCY`.
+    pub fn from_provider_status(status: StatusCode, message: String) -> Self {
+        let status = match status {
+            StatusCode::NOT_FOUND | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => status,
+            _ => StatusCode::FAILED_DEPENDENCY,
+        };
+        Error::Http(status, message)
+    }
+
     /// Map `String` to `Error`.
     pub fn from_string(s: String) -> Self {
         Error::Http(StatusCode::INTERNAL_SERVER_ERROR, s)
@@ -1,20 +1,33 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+/// Authenticated identities.
 mod auth;
+/// Timing-safe comparison.
+mod compare;
 #[cfg(feature = "toml")]
 mod config;
 /// An enum that encapsulates a variety of error types.
 mod error;
 #[cfg(feature = "hyper")]
 mod http;
+/// Cloud providers supported by this build.
+mod providers;
+/// Provider-prefixed resource IDs.
+#[cfg(feature = "hyper")]
+mod resource_id;
+/// Unit tests.
+mod tests;
 
-#[cfg(feature = "aws")]
 pub use self::auth::{AuthenticatedId, Identity, UserName};
+pub use self::compare::constant_time_eq;
 #[cfg(feature = "toml")]
 pub use self::config::CubConfig;
 pub use self::error::Error;
 #[cfg(feature = "aws")]
 pub use self::error::{AnyhowError, DynamoError, SerdeError};
-#[cfg(feature = "hyper")]
+#[cfg(all(feature = "hyper", feature = "axum"))]
 pub use self::http::create_error_response;
+pub use self::providers::{supported_providers, ProviderInfo};
+#[cfg(feature = "hyper")]
+pub use self::resource_id::strip_provider_prefix;
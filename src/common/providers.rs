@@ -0,0 +1,43 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+/// A cloud provider and what this build of the crate can do with it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ProviderInfo {
+    /// The name of the provider, e.g. `"linode"`, as used in `CubConfig` sections.
+    pub name: &'static str,
+    /// Supports DNS (see `CloudDns`).
+    pub dns: bool,
+    /// Supports virtual hosts (see `CloudHosts`).
+    pub hosts: bool,
+    /// Supports videos (see `CloudVideos`).
+    pub videos: bool,
+}
+
+/// Returns the providers supported by the features this crate was compiled with,
+/// omitting any provider without capabilities, sorted by name.
+pub fn supported_providers() -> Vec<ProviderInfo> {
+    [
+        ProviderInfo {
+            name: "aws",
+            dns: cfg!(feature = "dns"),
+            hosts: false,
+            videos: false,
+        },
+        ProviderInfo {
+            name: "linode",
+            dns: cfg!(feature = "dns"),
+            hosts: cfg!(feature = "hosts"),
+            videos: false,
+        },
+        ProviderInfo {
+            name: "youtube",
+            dns: false,
+            hosts: false,
+            videos: cfg!(feature = "videos"),
+        },
+    ]
+    .into_iter()
+    .filter(|info| info.dns || info.hosts || info.videos)
+    .collect()
+}
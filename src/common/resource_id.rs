@@ -0,0 +1,20 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::Error;
+use hyper::StatusCode;
+
+/// Strips the expected provider prefix from a resource ID, e.g. "linode/123" becomes "123".
+pub fn strip_provider_prefix<'a>(resource_id: &'a str, provider: &str) -> Result<&'a str, Error> {
+    match resource_id.split_once('/') {
+        Some((prefix, payload)) if prefix == provider && !payload.is_empty() => Ok(payload),
+        Some((prefix, _)) if prefix == provider => Err(Error::Http(
+            StatusCode::NOT_ACCEPTABLE,
+            format!("{resource_id}: invalid {provider} resource ID"),
+        )),
+        _ => Err(Error::Http(
+            StatusCode::NOT_ACCEPTABLE,
+            format!("{resource_id}: expected '{provider}' prefix in resource ID"),
+        )),
+    }
+}
@@ -0,0 +1,82 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+#[cfg(test)]
+mod common_tests {
+    use crate::common::constant_time_eq;
+
+    #[test]
+    fn constant_time_eq_tests() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"signature", b"signature"));
+        assert!(!constant_time_eq(b"signature", b"signaturf"));
+        assert!(!constant_time_eq(b"signature", b"Signature"));
+        assert!(!constant_time_eq(b"signature", b"signatur"));
+        assert!(!constant_time_eq(b"", b"x"));
+    }
+
+    #[test]
+    fn supported_providers_tests() {
+        use crate::common::supported_providers;
+
+        let providers = supported_providers();
+        let linode = providers.iter().find(|info| info.name == "linode");
+        assert_eq!(
+            linode.is_some_and(|info| info.hosts),
+            cfg!(feature = "hosts")
+        );
+        assert_eq!(
+            providers.iter().any(|info| info.name == "youtube"),
+            cfg!(feature = "videos")
+        );
+        assert!(providers
+            .iter()
+            .all(|info| info.dns || info.hosts || info.videos));
+    }
+
+    #[cfg(feature = "hyper")]
+    #[test]
+    fn provider_status_tests() {
+        use crate::common::Error;
+        use hyper::StatusCode;
+
+        let status = |status| match Error::from_provider_status(status, String::new()) {
+            Error::Http(status, _) => status,
+            e => panic!("unexpected {e:?}"),
+        };
+        assert_eq!(status(StatusCode::NOT_FOUND), StatusCode::NOT_FOUND);
+        assert_eq!(status(StatusCode::UNAUTHORIZED), StatusCode::UNAUTHORIZED);
+        assert_eq!(status(StatusCode::FORBIDDEN), StatusCode::FORBIDDEN);
+        assert_eq!(
+            status(StatusCode::BAD_REQUEST),
+            StatusCode::FAILED_DEPENDENCY
+        );
+        assert_eq!(
+            status(StatusCode::INTERNAL_SERVER_ERROR),
+            StatusCode::FAILED_DEPENDENCY
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn identity_round_trip_tests() {
+        use crate::common::{AuthenticatedId, Identity, UserName};
+
+        let identity = Identity {
+            login_id: "google/123".parse().unwrap(),
+            user_name: Some(UserName("Alice".to_string())),
+        };
+        let json = serde_json::to_string(&identity).unwrap();
+        assert_eq!(json, r#"{"login_id":"google/123","user_name":"Alice"}"#);
+        assert_eq!(serde_json::from_str::<Identity>(&json).unwrap(), identity);
+
+        let anonymous: Identity =
+            serde_json::from_str(r#"{"login_id":"discord/456","user_name":null}"#).unwrap();
+        assert_eq!(
+            anonymous.login_id,
+            AuthenticatedId("discord/456".to_string())
+        );
+        assert_eq!(anonymous.login_id.provider(), Some("discord"));
+        assert_eq!(anonymous.user_name, None);
+    }
+}
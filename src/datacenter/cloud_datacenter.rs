@@ -5,6 +5,7 @@ use crate::common::Error;
 use axum::http::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
+use std::future::Future;
 use std::str::FromStr;
 
 // Datacenter city.
@@ -42,6 +43,36 @@ impl DcCity {
         }
     }
 
+    // Returns the approximate latitude and longitude of the city, in degrees.
+    fn coordinates(&self) -> (f64, f64) {
+        match self {
+            Self::Boardman => (45.84, -119.70),
+            Self::Frankfurt => (50.11, 8.68),
+            Self::London => (51.51, -0.13),
+            Self::Mumbai => (19.08, 72.88),
+            Self::Newark => (40.74, -74.17),
+            Self::Nuremberg => (49.45, 11.08),
+            Self::Washington => (38.91, -77.04),
+            Self::Singapore => (1.35, 103.82),
+            Self::SaoPaulo => (-23.55, -46.63),
+            Self::Seattle => (47.61, -122.33),
+            Self::Sydney => (-33.87, 151.21),
+            Self::Tokyo => (35.68, 139.69),
+        }
+    }
+
+    // Returns the great-circle distance to another city, in kilometers.
+    fn distance_km(&self, other: &Self) -> f64 {
+        const EARTH_RADIUS_KM: f64 = 6371.0;
+        let (lat1, lon1) = self.coordinates();
+        let (lat2, lon2) = other.coordinates();
+        let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (lon2 - lon1).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+    }
+
     // Returns city corresponding to the specified AWS region.
     #[cfg(feature = "aws")]
     fn from_aws_region(label: &str) -> Option<Self> {
@@ -234,6 +265,7 @@ impl FromStr for DcProvider {
     }
 }
 
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 /// Cloud datacenter provider and location. For example, "Linode/eu-central".
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub struct CloudDatacenter(String);
@@ -323,6 +355,39 @@ impl CloudDatacenter {
             .unwrap_or("us-east-1")
     }
 
+    /// Returns the nearest of `candidates` for which `probe` reports healthy, if any.
+    /// Candidates are probed one at a time, nearest first, until one passes.  Those
+    /// whose location is unknown (as is that of `self`) are probed last, in order.
+    pub async fn nearest_healthy<F, Fut>(
+        &self,
+        candidates: &[CloudDatacenter],
+        probe: F,
+    ) -> Option<CloudDatacenter>
+    where
+        F: Fn(&CloudDatacenter) -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        let origin = self.city().map(|(_, c)| c);
+        let mut candidates: Vec<_> = candidates
+            .iter()
+            .map(|candidate| {
+                let distance = origin
+                    .zip(candidate.city())
+                    .map(|(o, (_, c))| o.distance_km(&c))
+                    .unwrap_or(f64::INFINITY);
+                (distance, candidate)
+            })
+            .collect();
+        // Stable, so equidistant candidates keep their order.
+        candidates.sort_by(|(d1, _), (d2, _)| d1.total_cmp(d2));
+        for (_, candidate) in candidates {
+            if probe(candidate).await {
+                return Some(candidate.clone());
+            }
+        }
+        None
+    }
+
     /// If `CloudDatacenter` is in AWS then return it otherwise error.
     pub fn to_aws_region(&self) -> Result<String, Error> {
         self.to_region(DcProvider::Aws)
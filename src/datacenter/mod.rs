@@ -3,5 +3,7 @@
 
 /// Cloud datacenter
 mod cloud_datacenter;
+/// Unit tests
+mod tests;
 
 pub use self::cloud_datacenter::CloudDatacenter;
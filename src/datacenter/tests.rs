@@ -0,0 +1,48 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+#[cfg(test)]
+#[cfg(all(feature = "aws", feature = "linode"))]
+mod datacenter_tests {
+    use crate::datacenter::CloudDatacenter;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn nearest_healthy_tests() {
+        let frankfurt = CloudDatacenter::from_aws_region("eu-central-1");
+        let london = CloudDatacenter::from_aws_region("eu-west-2");
+        let tokyo = CloudDatacenter::from_aws_region("ap-northeast-1");
+        let virginia = CloudDatacenter::from_aws_region("us-east-1");
+        let candidates = [tokyo.clone(), virginia.clone(), london.clone()];
+        let newark = CloudDatacenter::from_linode_region("us-east");
+
+        let nearest = newark.nearest_healthy(&candidates, |_| async { true });
+        assert_eq!(nearest.await, Some(virginia.clone()));
+
+        let probed = Mutex::new(Vec::new());
+        let nearest = newark.nearest_healthy(&candidates, |dc| {
+            probed.lock().unwrap().push(dc.clone());
+            let healthy = *dc != virginia;
+            async move { healthy }
+        });
+        assert_eq!(nearest.await, Some(london.clone()));
+        assert_eq!(
+            *probed.lock().unwrap(),
+            vec![virginia.clone(), london.clone()]
+        );
+
+        let nearest = frankfurt.nearest_healthy(&candidates, |dc| {
+            let healthy = *dc == tokyo;
+            async move { healthy }
+        });
+        assert_eq!(nearest.await, Some(tokyo.clone()));
+
+        let nearest = newark.nearest_healthy(&candidates, |_| async { false });
+        assert_eq!(nearest.await, None);
+
+        // Unknown locations are probed last, in order.
+        let unknown = CloudDatacenter::default();
+        let nearest = unknown.nearest_healthy(&candidates, |_| async { true });
+        assert_eq!(nearest.await, Some(tokyo));
+    }
+}
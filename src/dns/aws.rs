@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::{CloudDns, DnsRecord, DnsRecordSet};
+use super::{CloudDns, DnsRecord, DnsRecordSet, DnsUpdateOutcome, ZoneInfo};
 use crate::aws::load_aws_config;
 use crate::common::{CubConfig, Error};
 use crate::datacenter::CloudDatacenter;
@@ -10,11 +10,12 @@ use crate::log::StringLogger;
 use async_trait::async_trait;
 use aws_sdk_dynamodb::error::BuildError;
 use aws_sdk_route53::types::{
-    Change, ChangeAction, ChangeBatch, GeoProximityLocation, ResourceRecord, ResourceRecordSet,
-    RrType,
+    AliasTarget, Change, ChangeAction, ChangeBatch, GeoProximityLocation, ResourceRecord,
+    ResourceRecordSet, RrType,
 };
 use aws_sdk_route53::Client;
 use hyper::StatusCode;
+use serde::Deserialize;
 use std::{
     collections::{HashMap, HashSet},
     net::IpAddr,
@@ -26,25 +27,88 @@ const DEBUG: bool = false;
 /// This struct implements `CloudDNS` for Aws.
 pub struct AwsDns {
     client: Client,
+    /// The TTL of records for which none is specified.
+    ttl_secs: usize,
 }
 
 impl AwsDns {
     const TTL_SECS: usize = 30;
 
-    /// Create a `CloudDNS` for AWS.
+    /// Create a `CloudDNS` for AWS.  The default TTL is that of the `dns_ttl_secs`
+    /// in the `aws` config, if any, otherwise 30 seconds.
     pub async fn new(cub_config: &CubConfig) -> Self {
+        #[derive(Default, Deserialize)]
+        struct AwsDnsConfig {
+            dns_ttl_secs: Option<usize>,
+        }
+        #[derive(Deserialize)]
+        struct ConfigToml {
+            #[serde(default)]
+            aws: AwsDnsConfig,
+        }
+        // A missing or malformed `aws` section means the default TTL.
+        let dns_ttl_secs = cub_config
+            .get()
+            .map(|ConfigToml { aws }| aws)
+            .unwrap_or_default()
+            .dns_ttl_secs;
         let aws_config = load_aws_config(cub_config).await;
         let client = Client::new(&aws_config);
-        Self { client }
+        Self {
+            client,
+            ttl_secs: dns_ttl_secs
+                .filter(|ttl| *ttl != 0)
+                .unwrap_or(Self::TTL_SECS),
+        }
     }
 
-    async fn create_domain_record(
+    /// Apply changes to the records of the specified domain (zone) in a single batch.
+    async fn change_domain_records(
         &self,
         domain_id: &AwsDomainId,
-        record: ExtendedDnsRecord,
-        logger: &StringLogger,
-    ) -> Result<(), Error> {
+        changes: Vec<Change>,
+    ) -> Result<bool, Error> {
+        // A record set that is upserted needn't (and mustn't) also be deleted.
+        let upserts: HashSet<_> = changes
+            .iter()
+            .filter(|change| *change.action() == ChangeAction::Upsert)
+            .filter_map(|change| change.resource_record_set().map(Self::record_set_key))
+            .collect();
+        let changes: Vec<_> = changes
+            .into_iter()
+            .filter(|change| {
+                *change.action() != ChangeAction::Delete
+                    || !change
+                        .resource_record_set()
+                        .is_some_and(|rrs| upserts.contains(&Self::record_set_key(rrs)))
+            })
+            .collect();
+        if changes.is_empty() {
+            return Ok(false);
+        }
+        let batch = ChangeBatch::builder()
+            .set_changes(Some(changes))
+            .build()
+            .map_err(Self::map_build_err)?;
+        let _ = self
+            .client
+            .change_resource_record_sets()
+            .hosted_zone_id(domain_id.to_string())
+            .change_batch(batch)
+            .send()
+            .await
+            .map_err(|e| {
+                Error::Anyhow(
+                    e.into(),
+                    format!("change_domain_records(domain={domain_id}): cannot change records"),
+                )
+            })?;
+        Ok(true)
+    }
+
+    fn create_change(record: ExtendedDnsRecord, logger: &StringLogger) -> Result<Change, Error> {
         let ExtendedDnsRecord {
+            alias,
             datacenter,
             name,
             record_type,
@@ -53,8 +117,30 @@ impl AwsDns {
             ..
         } = record;
         logger.trace(format!(
-            "domain {domain_id} hostname {name} create {record_type:?} record {targets:?}",
+            "hostname {name} create {record_type:?} record {targets:?}",
         ));
+        if let Some((dns_name, hosted_zone_id)) = alias {
+            // Alias records have neither a TTL nor resource records of their own.
+            logger.trace(format!("alias to {dns_name} in {hosted_zone_id}"));
+            let alias_target = AliasTarget::builder()
+                .dns_name(dns_name)
+                .hosted_zone_id(hosted_zone_id)
+                .evaluate_target_health(false)
+                .build()
+                .map_err(Self::map_build_err)?;
+            return Change::builder()
+                .action(ChangeAction::Upsert)
+                .resource_record_set(
+                    ResourceRecordSet::builder()
+                        .name(name)
+                        .r#type(record_type)
+                        .alias_target(alias_target)
+                        .build()
+                        .map_err(Self::map_build_err)?,
+                )
+                .build()
+                .map_err(Self::map_build_err);
+        }
         let geo_proximity_location = datacenter.as_ref().map(|dc| {
             GeoProximityLocation::builder()
                 .aws_region(dc.nearest_aws_region().to_owned())
@@ -70,79 +156,37 @@ impl AwsDns {
         } else {
             ResourceRecordSet::builder()
         };
-        let batch = ChangeBatch::builder()
-            .changes(
-                Change::builder()
-                    .action(ChangeAction::Upsert)
-                    .resource_record_set(
-                        rrs_builder
-                            .name(name)
-                            .r#type(record_type)
-                            .ttl(ttl_sec as i64)
-                            .set_resource_records(Some(
-                                targets
-                                    .into_iter()
-                                    .map(|target| {
-                                        ResourceRecord::builder()
-                                            .value(target)
-                                            .build()
-                                            .map_err(Self::map_build_err)
-                                    })
-                                    .collect::<Result<Vec<_>, _>>()?,
-                            ))
-                            .build()
-                            .map_err(Self::map_build_err)?,
-                    )
+        Change::builder()
+            .action(ChangeAction::Upsert)
+            .resource_record_set(
+                rrs_builder
+                    .name(name)
+                    .r#type(record_type)
+                    .ttl(ttl_sec as i64)
+                    .set_resource_records(Some(
+                        targets
+                            .into_iter()
+                            .map(|target| {
+                                ResourceRecord::builder()
+                                    .value(target)
+                                    .build()
+                                    .map_err(Self::map_build_err)
+                            })
+                            .collect::<Result<Vec<_>, _>>()?,
+                    ))
                     .build()
                     .map_err(Self::map_build_err)?,
             )
             .build()
-            .map_err(Self::map_build_err)?;
-        let _ = self
-            .client
-            .change_resource_record_sets()
-            .hosted_zone_id(domain_id.to_string())
-            .change_batch(batch)
-            .send()
-            .await
-            .map_err(|e| {
-                Error::Anyhow(
-                    e.into(),
-                    format!("create_domain_record: cannot change records"),
-                )
-            })?;
-        Ok(())
+            .map_err(Self::map_build_err)
     }
 
-    async fn delete_domain_record(
-        &self,
-        domain_id: &AwsDomainId,
-        record_id: &AwsRecordId,
-    ) -> Result<(), Error> {
-        let batch = ChangeBatch::builder()
-            .changes(
-                Change::builder()
-                    .action(ChangeAction::Delete)
-                    .resource_record_set(record_id.0.clone())
-                    .build()
-                    .map_err(Self::map_build_err)?,
-            )
+    fn delete_change(record_id: &AwsRecordId) -> Result<Change, Error> {
+        Change::builder()
+            .action(ChangeAction::Delete)
+            .resource_record_set(record_id.0.clone())
             .build()
-            .map_err(Self::map_build_err)?;
-        let _ = self
-            .client
-            .change_resource_record_sets()
-            .hosted_zone_id(domain_id.to_string())
-            .change_batch(batch)
-            .send()
-            .await
-            .map_err(|e| {
-                Error::Anyhow(
-                    e.into(),
-                    format!("delete_domain_record(domain={domain_id}, record={record_id:?})"),
-                )
-            })?;
-        Ok(())
+            .map_err(Self::map_build_err)
     }
 
     fn double_quoted(text: &str) -> String {
@@ -153,11 +197,14 @@ impl AwsDns {
         }
     }
 
+    /// Returns the fully qualified, lowercase, hostname, since DNS is case-insensitive.
     fn fully_qualified(hostname: &str, domain: &str) -> String {
+        let hostname = hostname.to_ascii_lowercase();
+        let domain = domain.to_ascii_lowercase();
         if hostname.len() == 0 {
-            domain.to_string()
-        } else if hostname.ends_with(domain) {
-            hostname.to_string()
+            domain
+        } else if hostname == domain || hostname.ends_with(&format!(".{domain}")) {
+            hostname
         } else {
             format!("{hostname}.{domain}")
         }
@@ -228,12 +275,16 @@ impl AwsDns {
                 }
                 rrs
             })
-            // For now, A records must have an IP address or they will be ignored.
-            .filter(|rrs| rrs.alias_target().is_none())
             .map(|rrs| {
                 (
                     AwsRecordId(rrs.clone()),
                     ExtendedDnsRecord {
+                        alias: rrs.alias_target().map(|at| {
+                            (
+                                Self::sans_trailing_dot(at.dns_name()),
+                                at.hosted_zone_id().to_owned(),
+                            )
+                        }),
                         name: Self::parse_name(rrs.name()),
                         datacenter: rrs.geo_proximity_location().and_then(|gpl| {
                             gpl.aws_region()
@@ -265,11 +316,346 @@ impl AwsDns {
         })
     }
 
+    /// Returns the lowercase name, with the wildcard (if any) unescaped.
     fn parse_name(target: &str) -> String {
-        Self::sans_trailing_dot(&target.replace("\\052", "*"))
+        Self::sans_trailing_dot(&target.replace("\\052", "*")).to_ascii_lowercase()
+    }
+
+    /// Returns the changes needed to update (or remove) the metadata of a host.
+    fn metadata_changes(
+        domain: &str,
+        hostname: &str,
+        value: DnsRecord,
+        ttl_sec: usize,
+        records: &[(AwsRecordId, ExtendedDnsRecord)],
+        logger: &StringLogger,
+    ) -> Result<Vec<Change>, Error> {
+        let fq_hostname = Self::fully_qualified(hostname, domain);
+        let txt_records: Vec<_> = records
+            .iter()
+            .filter(
+                |(
+                    _,
+                    ExtendedDnsRecord {
+                        name, record_type, ..
+                    },
+                )| {
+                    name.eq_ignore_ascii_case(&fq_hostname) && *record_type == RrType::Txt
+                },
+            )
+            .collect();
+        let mut changes = Vec::new();
+        match value {
+            DnsRecord::Txt(text) => {
+                let target = Self::double_quoted(&text);
+                if let [(_, ExtendedDnsRecord { targets, .. })] = txt_records.as_slice() {
+                    if *targets == [target.clone()] {
+                        // Already up to date.
+                        return Ok(changes);
+                    }
+                }
+                for (record_id, _) in txt_records {
+                    changes.push(Self::delete_change(record_id)?);
+                }
+                changes.push(Self::create_change(
+                    ExtendedDnsRecord {
+                        alias: None,
+                        datacenter: None,
+                        name: fq_hostname,
+                        record_type: RrType::Txt,
+                        targets: vec![target],
+                        ttl_sec,
+                    },
+                    logger,
+                )?);
+            }
+            DnsRecord::Caa { .. } => {
+                // Add to, rather than replace, the existing values.
+                let mut values: Vec<_> = records
+                    .iter()
+                    .filter(|(_, record)| {
+                        record.name.eq_ignore_ascii_case(&fq_hostname)
+                            && record.record_type == RrType::Caa
+                    })
+                    .flat_map(|(_, record)| &record.targets)
+                    .filter_map(|target| Self::parse_caa(target))
+                    .collect();
+                values.push(value);
+                return Self::caa_changes(domain, hostname, &values, ttl_sec, records, logger);
+            }
+            DnsRecord::Srv {
+                priority,
+                weight,
+                port,
+                target,
+            } => {
+                let target = format!("{priority} {weight} {port} {target}");
+                let srv_records: Vec<_> = records
+                    .iter()
+                    .filter(|(_, record)| {
+                        record.name.eq_ignore_ascii_case(&fq_hostname)
+                            && record.record_type == RrType::Srv
+                    })
+                    .collect();
+                if let [(_, ExtendedDnsRecord { targets, .. })] = srv_records.as_slice() {
+                    if targets.len() == 1
+                        && Self::parse_srv(&targets[0]) == Self::parse_srv(&target)
+                    {
+                        // Already up to date.
+                        return Ok(changes);
+                    }
+                }
+                for (record_id, _) in srv_records {
+                    changes.push(Self::delete_change(record_id)?);
+                }
+                changes.push(Self::create_change(
+                    ExtendedDnsRecord {
+                        alias: None,
+                        datacenter: None,
+                        name: fq_hostname,
+                        record_type: RrType::Srv,
+                        targets: vec![target],
+                        ttl_sec,
+                    },
+                    logger,
+                )?);
+            }
+            DnsRecord::None => {
+                for (record_id, _) in txt_records {
+                    changes.push(Self::delete_change(record_id)?);
+                }
+                for (record_id, _) in records.iter().filter(|(_, record)| {
+                    record.name.eq_ignore_ascii_case(&fq_hostname)
+                        && record.record_type == RrType::Srv
+                }) {
+                    changes.push(Self::delete_change(record_id)?);
+                }
+            }
+            _ => logger.trace("non-metadata record ignored".to_string()),
+        }
+        Ok(changes)
+    }
+
+    /// Returns the changes needed to replace the `Caa` records of a host with `values`.
+    /// Route 53 has one record set per host and type, so it is replaced as a whole.
+    fn caa_changes(
+        domain: &str,
+        hostname: &str,
+        values: &[DnsRecord],
+        ttl_sec: usize,
+        records: &[(AwsRecordId, ExtendedDnsRecord)],
+        logger: &StringLogger,
+    ) -> Result<Vec<Change>, Error> {
+        let fq_hostname = Self::fully_qualified(hostname, domain);
+        let existing = records.iter().find(|(_, record)| {
+            record.name.eq_ignore_ascii_case(&fq_hostname) && record.record_type == RrType::Caa
+        });
+        let mut targets = Vec::new();
+        let mut changes = Vec::new();
+        for value in values {
+            if let DnsRecord::Caa { flags, tag, value } = value {
+                let target = format!("{flags} {tag} {}", Self::double_quoted(value));
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+        }
+        if let Some((record_id, record)) = existing {
+            if record.targets.len() == targets.len()
+                && targets.iter().all(|target| record.targets.contains(target))
+            {
+                // Already up to date.
+                return Ok(changes);
+            }
+            changes.push(Self::delete_change(record_id)?);
+        }
+        if !targets.is_empty() {
+            changes.push(Self::create_change(
+                ExtendedDnsRecord {
+                    alias: None,
+                    datacenter: None,
+                    name: fq_hostname,
+                    record_type: RrType::Caa,
+                    targets,
+                    ttl_sec,
+                },
+                logger,
+            )?);
+        }
+        Ok(changes)
+    }
+
+    /// Parses the value of a CAA record, e.g. `0 issue "letsencrypt.org"`.
+    fn parse_caa(target: &str) -> Option<DnsRecord> {
+        let mut parts = target.splitn(3, ' ');
+        let flags = parts.next()?.parse().ok()?;
+        let tag = parts.next()?.to_owned();
+        let value = parts.next()?.trim_matches('"').to_owned();
+        Some(DnsRecord::Caa { flags, tag, value })
+    }
+
+    /// Parses the serial and refresh interval of the value of a SOA record, e.g.
+    /// `ns-2048.awsdns-64.net. hostmaster.example.com. 1 7200 900 1209600 86400`.
+    fn parse_soa(target: &str) -> Option<(u32, u32)> {
+        let mut parts = target.split_whitespace().skip(2);
+        let serial = parts.next()?.parse().ok()?;
+        let refresh = parts.next()?.parse().ok()?;
+        Some((serial, refresh))
+    }
+
+    /// Parses the value of a SRV record, e.g. `10 5 25565 mc.example.com.`.
+    fn parse_srv(target: &str) -> Option<DnsRecord> {
+        let mut parts = target.split_whitespace();
+        let priority = parts.next()?.parse().ok()?;
+        let weight = parts.next()?.parse().ok()?;
+        let port = parts.next()?.parse().ok()?;
+        let target = Self::sans_trailing_dot(parts.next()?).to_ascii_lowercase();
+        Some(DnsRecord::Srv {
+            priority,
+            weight,
+            port,
+            target,
+        })
+    }
+
+    /// Identifies a record set, for the purpose of matching changes to it.
+    fn record_set_key(rrs: &ResourceRecordSet) -> (String, RrType, Option<String>) {
+        (
+            Self::parse_name(rrs.name()),
+            rrs.r#type().clone(),
+            rrs.set_identifier().map(|s| s.to_owned()),
+        )
+    }
+
+    /// Returns the changes needed to update (or remove) the route(s) to a host.
+    fn route_changes(
+        domain: &str,
+        hostname: &str,
+        value: DnsRecord,
+        ttl_sec: usize,
+        records: &[(AwsRecordId, ExtendedDnsRecord)],
+        logger: &StringLogger,
+    ) -> Result<Vec<Change>, Error> {
+        let fq_hostname = Self::fully_qualified(hostname, domain);
+        let id_records: Vec<_> = records
+            .iter()
+            .filter(|(_, ExtendedDnsRecord { name, .. })| name.eq_ignore_ascii_case(&fq_hostname))
+            .collect();
+        let mut changes = Vec::new();
+        match value {
+            DnsRecord::A(ipgeos) => {
+                changes = Self::a_record_changes(
+                    domain,
+                    &fq_hostname,
+                    ttl_sec,
+                    &id_records,
+                    ipgeos,
+                    logger,
+                )?;
+            }
+            DnsRecord::Cname(link) => {
+                let mut found: bool = false;
+                for (
+                    id,
+                    ExtendedDnsRecord {
+                        record_type,
+                        targets,
+                        ..
+                    },
+                ) in id_records.iter().copied()
+                {
+                    match record_type {
+                        RrType::A => {
+                            changes.push(Self::delete_change(id)?);
+                        }
+                        RrType::Cname => {
+                            if !found && targets.len() == 1 && targets[0] == link {
+                                found = true;
+                            } else {
+                                changes.push(Self::delete_change(id)?);
+                            }
+                        }
+                        _ => {
+                            // Ignore TXT records, etc.
+                        }
+                    }
+                }
+
+                if !found {
+                    changes.push(Self::create_change(
+                        ExtendedDnsRecord {
+                            alias: None,
+                            datacenter: None,
+                            name: fq_hostname,
+                            record_type: RrType::Cname,
+                            targets: vec![link],
+                            ttl_sec,
+                        },
+                        logger,
+                    )?);
+                }
+            }
+            DnsRecord::Alias {
+                target,
+                hosted_zone_id,
+            } => {
+                let alias = Some((target, hosted_zone_id));
+                let mut found: bool = false;
+                for (
+                    id,
+                    ExtendedDnsRecord {
+                        alias: existing,
+                        record_type,
+                        ..
+                    },
+                ) in id_records.iter().copied()
+                {
+                    match record_type {
+                        RrType::A if !found && *existing == alias => {
+                            found = true;
+                        }
+                        RrType::A | RrType::Cname => {
+                            changes.push(Self::delete_change(id)?);
+                        }
+                        _ => {
+                            // Ignore TXT records, etc.
+                        }
+                    }
+                }
+
+                if !found {
+                    changes.push(Self::create_change(
+                        ExtendedDnsRecord {
+                            alias,
+                            datacenter: None,
+                            name: fq_hostname,
+                            record_type: RrType::A,
+                            targets: Vec::new(),
+                            ttl_sec,
+                        },
+                        logger,
+                    )?);
+                }
+            }
+            DnsRecord::None => {
+                for (record_id, ExtendedDnsRecord { record_type, .. }) in id_records {
+                    match record_type {
+                        RrType::A | RrType::Cname => {
+                            changes.push(Self::delete_change(record_id)?);
+                        }
+                        _ => {
+                            // Ignore TXT records, etc.
+                        }
+                    }
+                }
+            }
+            _ => logger.trace("non route record ignored".to_string()),
+        }
+        Ok(changes)
     }
 
     fn sans_domain(domain: &str, hostname: &str) -> String {
+        let domain = domain.to_ascii_lowercase();
         if domain == hostname {
             "".to_string()
         } else {
@@ -291,29 +677,38 @@ impl AwsDns {
         }
     }
 
-    async fn upsert_a_record(
-        &self,
+    fn ttl_sec(&self, ttl: Option<usize>) -> usize {
+        match ttl {
+            Some(ttl) if ttl != 0 => ttl,
+            _ => self.ttl_secs,
+        }
+    }
+
+    fn a_record_changes(
         domain: &str,
-        domain_id: AwsDomainId,
         fq_hostname: &str,
         ttl_sec: usize,
-        id_records: &Vec<(AwsRecordId, ExtendedDnsRecord)>,
+        id_records: &[&(AwsRecordId, ExtendedDnsRecord)],
         ipgeos: HashMap<IpAddr, Option<CloudDatacenter>>,
         logger: &StringLogger,
-    ) -> Result<(), Error> {
+    ) -> Result<Vec<Change>, Error> {
         let mut removals: Vec<AwsRecordId> = Vec::new();
         let mut found: HashSet<IpAddr> = HashSet::new();
         for (
             id,
             ExtendedDnsRecord {
+                alias,
                 record_type,
                 targets,
                 datacenter,
                 ..
             },
-        ) in id_records.iter()
+        ) in id_records.iter().copied()
         {
             match record_type {
+                RrType::A if alias.is_some() => {
+                    removals.push(id.clone());
+                }
                 RrType::A => {
                     let ips = targets
                         .iter()
@@ -352,6 +747,7 @@ impl AwsDns {
             let record = adds
                 .entry(datacenter.clone())
                 .or_insert_with(|| ExtendedDnsRecord {
+                    alias: None,
                     datacenter,
                     name: fq_hostname.to_owned(),
                     record_type: RrType::A,
@@ -361,17 +757,14 @@ impl AwsDns {
             record.targets.push(ip.to_string());
         }
 
-        for record_id in removals {
-            self.delete_domain_record(&domain_id, &record_id).await?;
-        }
-
-        // TODO: can set these in a single command.
+        let mut changes = removals
+            .iter()
+            .map(Self::delete_change)
+            .collect::<Result<Vec<_>, _>>()?;
         for record in adds.into_values() {
-            self.create_domain_record(&domain_id, record, &logger)
-                .await?;
+            changes.push(Self::create_change(record, logger)?);
         }
-
-        Ok(())
+        Ok(changes)
     }
 }
 
@@ -391,10 +784,14 @@ impl CloudDns for AwsDns {
         let mut a_ipgeos: HashMap<String, HashMap<IpAddr, Option<CloudDatacenter>>> =
             HashMap::new();
         let mut other: HashMap<String, DnsRecord> = HashMap::new();
+        let mut aliases: Vec<(String, DnsRecord)> = Vec::new();
+        let mut caa: Vec<(String, DnsRecord)> = Vec::new();
+        let mut srv: Vec<(String, DnsRecord)> = Vec::new();
 
         for (
             _,
             ExtendedDnsRecord {
+                alias,
                 datacenter,
                 name: hostname,
                 targets,
@@ -403,6 +800,16 @@ impl CloudDns for AwsDns {
             },
         ) in list.into_iter()
         {
+            if let (RrType::A, Some((target, hosted_zone_id))) = (&record_type, alias) {
+                aliases.push((
+                    hostname,
+                    DnsRecord::Alias {
+                        target,
+                        hosted_zone_id,
+                    },
+                ));
+                continue;
+            }
             match record_type {
                 RrType::A => {
                     let ips = targets
@@ -422,6 +829,18 @@ impl CloudDns for AwsDns {
                         );
                     }
                 }
+                RrType::Caa => {
+                    for target in targets {
+                        if let Some(record) = Self::parse_caa(&target) {
+                            caa.push((hostname.clone(), record));
+                        }
+                    }
+                }
+                RrType::Srv if targets.len() == 1 => {
+                    if let Some(record) = Self::parse_srv(&targets[0]) {
+                        srv.push((hostname, record));
+                    }
+                }
                 RrType::Txt => {
                     // TODO: Support multiple TXT
                     if targets.len() == 1 {
@@ -438,7 +857,7 @@ impl CloudDns for AwsDns {
         // May be more capacity than required, but always enough.
         let mut dns_records = HashSet::with_capacity(list_len);
 
-        for (hostname, record) in other.into_iter() {
+        for (hostname, record) in other.into_iter().chain(aliases).chain(caa).chain(srv) {
             dns_records.insert((Self::sans_domain(domain, &hostname), record));
         }
 
@@ -452,98 +871,121 @@ impl CloudDns for AwsDns {
         Ok(DnsRecordSet(dns_records))
     }
 
+    async fn read_zone_info(&self, domain: &str) -> Result<ZoneInfo, Error> {
+        let domain_id = self.get_domain_id(domain).await?;
+        let output = self
+            .client
+            .get_hosted_zone()
+            .id(domain_id.to_string())
+            .send()
+            .await
+            .map_err(|e| Error::Anyhow(e.into(), format!("read_zone_info(domain={domain_id})")))?;
+        let nameservers = output
+            .delegation_set()
+            .map(|ds| {
+                ds.name_servers()
+                    .iter()
+                    .map(|ns| Self::sans_trailing_dot(ns))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let fq_domain = Self::fully_qualified("", domain);
+        let (soa_serial, soa_refresh_secs) = self
+            .list_route53_records(&domain_id)
+            .await?
+            .into_iter()
+            .find(|(_, record)| {
+                record.record_type == RrType::Soa && record.name.eq_ignore_ascii_case(&fq_domain)
+            })
+            .and_then(|(_, record)| record.targets.first().and_then(|t| Self::parse_soa(t)))
+            .ok_or_else(|| {
+                Error::Http(
+                    StatusCode::FAILED_DEPENDENCY,
+                    format!("{domain}: could not find SOA record"),
+                )
+            })?;
+        Ok(ZoneInfo {
+            nameservers,
+            soa_serial: Some(soa_serial),
+            soa_refresh_secs,
+        })
+    }
+
+    async fn update_dns_caa(
+        &self,
+        domain: &str,
+        hostname: &str,
+        values: Vec<DnsRecord>,
+        ttl: Option<usize>,
+    ) -> Result<DnsUpdateOutcome, Error> {
+        let logger = StringLogger::default();
+        let domain_id = self.get_domain_id(domain).await?;
+        let ttl_sec = self.ttl_sec(ttl);
+        let records = self.list_route53_records(&domain_id).await?;
+        let changes = Self::caa_changes(domain, hostname, &values, ttl_sec, &records, &logger)?;
+        let changed = self.change_domain_records(&domain_id, changes).await?;
+        Ok(DnsUpdateOutcome::new(changed, &logger))
+    }
+
     async fn update_dns_metadata(
         &self,
         domain: &str,
         hostname: &str,
         value: DnsRecord,
         ttl: Option<usize>,
-    ) -> Result<String, Error> {
+    ) -> Result<DnsUpdateOutcome, Error> {
         let logger = StringLogger::default();
         let domain_id = self.get_domain_id(domain).await?;
-        let fq_hostname = Self::fully_qualified(hostname, domain);
-
-        let ttl_sec = if let Some(ttl) = ttl {
-            if ttl == 0 {
-                Self::TTL_SECS
-            } else {
-                ttl
-            }
-        } else {
-            Self::TTL_SECS
-        };
-
-        let response = self.list_route53_records(&domain_id).await?;
-        let id_records: Vec<_> = response
-            .into_iter()
-            .filter(
-                |(
-                    _,
-                    ExtendedDnsRecord {
-                        name, record_type, ..
-                    },
-                )| {
-                    *name == fq_hostname
-                        && match record_type {
-                            RrType::Txt | RrType::Mx | RrType::Srv => true,
-                            _ => false,
-                        }
-                },
-            )
-            .collect();
+        let ttl_sec = self.ttl_sec(ttl);
+        let records = self.list_route53_records(&domain_id).await?;
+        let changes = Self::metadata_changes(domain, hostname, value, ttl_sec, &records, &logger)?;
+        let changed = self.change_domain_records(&domain_id, changes).await?;
+        Ok(DnsUpdateOutcome::new(changed, &logger))
+    }
 
-        match value {
-            DnsRecord::Txt(text) => {
-                for (record_id, _, _) in id_records
-                    .iter()
-                    .filter(|(_, ExtendedDnsRecord { record_type, .. })| {
-                        *record_type == RrType::Txt
-                    })
-                    .map(
-                        |(
-                            record_id,
-                            ExtendedDnsRecord {
-                                name, record_type, ..
-                            },
-                        )| (record_id, name, record_type),
-                    )
-                {
-                    self.delete_domain_record(&domain_id, record_id).await?;
-                }
-                self.create_domain_record(
-                    &domain_id,
-                    ExtendedDnsRecord {
-                        datacenter: None,
-                        name: fq_hostname,
-                        record_type: RrType::Txt,
-                        targets: vec![Self::double_quoted(&text)],
-                        ttl_sec,
-                    },
-                    &logger,
-                )
-                .await?;
-            }
-            DnsRecord::None => {
-                for (record_id, _, _) in id_records
-                    .iter()
-                    .filter(|(_, ExtendedDnsRecord { record_type, .. })| {
-                        *record_type == RrType::Txt
-                    })
-                    .map(
-                        |(
-                            record_id,
-                            ExtendedDnsRecord {
-                                name, record_type, ..
-                            },
-                        )| (record_id, name, record_type),
-                    )
-                {
-                    self.delete_domain_record(&domain_id, record_id).await?;
-                }
-            }
-            _ => logger.trace("non-metadata record ignored".to_string()),
+    /// Update multiple DNS records, reading the domain (zone) once and then applying
+    /// only the necessary changes in a single batch.
+    async fn update_dns_records(
+        &self,
+        domain: &str,
+        record_set: DnsRecordSet,
+    ) -> Result<DnsUpdateOutcome, Error> {
+        let logger = StringLogger::default();
+        let domain_id = self.get_domain_id(domain).await?;
+        let records = self.list_route53_records(&domain_id).await?;
+        let mut changes = Vec::new();
+        for (hostname, record) in record_set.metadata().into_iter().chain(record_set.srv()) {
+            changes.extend(Self::metadata_changes(
+                domain,
+                &hostname,
+                record,
+                self.ttl_secs,
+                &records,
+                &logger,
+            )?);
+        }
+        for (hostname, values) in record_set.caa_by_hostname() {
+            changes.extend(Self::caa_changes(
+                domain,
+                &hostname,
+                &values,
+                self.ttl_secs,
+                &records,
+                &logger,
+            )?);
         }
-        Ok(logger.to_string())
+        for (hostname, record) in record_set.routes() {
+            changes.extend(Self::route_changes(
+                domain,
+                &hostname,
+                record,
+                self.ttl_secs,
+                &records,
+                &logger,
+            )?);
+        }
+        let changed = self.change_domain_records(&domain_id, changes).await?;
+        Ok(DnsUpdateOutcome::new(changed, &logger))
     }
 
     async fn update_dns_route(
@@ -552,103 +994,14 @@ impl CloudDns for AwsDns {
         hostname: &str,
         value: DnsRecord,
         ttl: Option<usize>,
-    ) -> Result<String, Error> {
+    ) -> Result<DnsUpdateOutcome, Error> {
         let logger = StringLogger::default();
         let domain_id = self.get_domain_id(domain).await?;
-        let fq_hostname = Self::fully_qualified(hostname, domain);
-
-        let ttl_sec = if let Some(ttl) = ttl {
-            if ttl == 0 {
-                Self::TTL_SECS
-            } else {
-                ttl
-            }
-        } else {
-            Self::TTL_SECS
-        };
-        let response = self.list_route53_records(&domain_id).await?;
-        let id_records: Vec<_> = response
-            .into_iter()
-            .filter(|(_, ExtendedDnsRecord { name, .. })| *name == fq_hostname)
-            .collect();
-
-        match value {
-            DnsRecord::A(ipgeos) => {
-                self.upsert_a_record(
-                    domain,
-                    domain_id,
-                    &fq_hostname,
-                    ttl_sec,
-                    &id_records,
-                    ipgeos,
-                    &logger,
-                )
-                .await?
-            }
-            DnsRecord::Cname(link) => {
-                let mut removals: Vec<AwsRecordId> = Vec::new();
-                let mut found: bool = false;
-                for (
-                    id,
-                    ExtendedDnsRecord {
-                        record_type,
-                        targets,
-                        ..
-                    },
-                ) in id_records.iter()
-                {
-                    match record_type {
-                        RrType::A => {
-                            removals.push(id.clone());
-                        }
-                        RrType::Cname => {
-                            if !found && targets.len() == 1 && targets[0] == link {
-                                found = true;
-                            } else {
-                                removals.push(id.clone());
-                            }
-                        }
-                        _ => {
-                            // Ignore TXT records, etc.
-                        }
-                    }
-                }
-
-                for record_id in removals {
-                    self.delete_domain_record(&domain_id, &record_id).await?;
-                }
-
-                if !found {
-                    self.create_domain_record(
-                        &domain_id,
-                        ExtendedDnsRecord {
-                            datacenter: None,
-                            name: fq_hostname,
-                            record_type: RrType::Cname,
-                            targets: vec![link],
-                            ttl_sec,
-                        },
-                        &logger,
-                    )
-                    .await?;
-                }
-            }
-            DnsRecord::None => {
-                for (record_id, ExtendedDnsRecord { record_type, .. }) in id_records.iter() {
-                    match record_type {
-                        RrType::A | RrType::Cname => {
-                            self.delete_domain_record(&domain_id, record_id).await?;
-                        }
-                        _ => {
-                            // Ignore TXT records, etc.
-                        }
-                    }
-                }
-            }
-            _ => logger.trace("non route record ignored".to_string()),
-        }
-
-        Ok(logger.to_string())
+        let ttl_sec = self.ttl_sec(ttl);
+        let records = self.list_route53_records(&domain_id).await?;
+        let changes = Self::route_changes(domain, hostname, value, ttl_sec, &records, &logger)?;
+        let changed = self.change_domain_records(&domain_id, changes).await?;
+        Ok(DnsUpdateOutcome::new(changed, &logger))
     }
 }
 
@@ -663,6 +1016,8 @@ pub struct AwsRecordId(ResourceRecordSet);
 
 #[derive(Debug, Eq, PartialEq)]
 struct ExtendedDnsRecord {
+    /// The DNS name and hosted zone ID of the target of an alias record, if any.
+    alias: Option<(String, String)>,
     datacenter: Option<CloudDatacenter>,
     name: String,
     targets: Vec<String>,
@@ -6,6 +6,8 @@ use crate::common::{CubConfig, Error};
 use crate::datacenter::CloudDatacenter;
 use crate::log::StringLogger;
 use async_trait::async_trait;
+use futures::future::join_all;
+use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 use std::{
     collections::{HashMap, HashSet},
@@ -19,36 +21,81 @@ pub trait CloudDns {
     /// Read the DNS records of the specified domain (zone).
     async fn read_dns_records(&self, domain: &str) -> Result<DnsRecordSet, Error>;
 
+    /// Read the authoritative nameservers and SOA of the specified domain (zone), e.g.
+    /// to delegate it at the registrar.
+    async fn read_zone_info(&self, domain: &str) -> Result<ZoneInfo, Error>;
+
+    /// Replace the `Caa` records of a particular host in the specified domain (zone) with
+    /// `values`, removing any others.  If `values` is empty, all are removed.
+    async fn update_dns_caa(
+        &self,
+        domain: &str,
+        hostname: &str,
+        values: Vec<DnsRecord>,
+        ttl: Option<usize>,
+    ) -> Result<DnsUpdateOutcome, Error>;
+
     /// Update (or remove) the metadata of a particular host in the specified domain (zone).
+    /// A `Caa` value is added to those of the host (see `update_dns_caa` to replace them).
     async fn update_dns_metadata(
         &self,
         domain: &str,
         hostname: &str,
         value: DnsRecord,
         ttl: Option<usize>,
-    ) -> Result<String, Error>;
+    ) -> Result<DnsUpdateOutcome, Error>;
 
-    /// Update multiple DNS records in the specified domain (zone).
+    /// Update multiple DNS records in the specified domain (zone).  The `Caa` records of
+    /// each host that has any in `record_set` are replaced (see `update_dns_caa`).
+    /// Implementations may override this to read the domain once and apply changes in
+    /// a batch.
     async fn update_dns_records(
         &self,
         domain: &str,
         record_set: DnsRecordSet,
-    ) -> Result<String, Error> {
+    ) -> Result<DnsUpdateOutcome, Error> {
         let logger = StringLogger::default();
-        // TODO: this could be optimized to avoid reading the domain multiple times.
-        for (hostname, record) in record_set.metadata() {
-            logger.trace(
-                self.update_dns_metadata(domain, &hostname, record, None)
-                    .await?,
-            );
+        // Read the domain once to skip records that are already up to date.
+        let current = self.read_dns_records(domain).await?;
+        let DnsChanges {
+            adds,
+            updates,
+            deletes,
+        } = record_set.diff(&current);
+        let mut caa = record_set.caa_by_hostname();
+        let mut caa_hostnames: Vec<_> = adds
+            .iter()
+            .chain(&deletes)
+            .filter(|(hostname, record)| {
+                matches!(record, DnsRecord::Caa { .. }) && caa.contains_key(hostname)
+            })
+            .map(|(hostname, _)| hostname.clone())
+            .collect();
+        caa_hostnames.sort();
+        caa_hostnames.dedup();
+        let mut changed = false;
+        for hostname in caa_hostnames {
+            let values = caa.remove(&hostname).unwrap_or_default();
+            let outcome = self.update_dns_caa(domain, &hostname, values, None).await?;
+            changed |= outcome.changed;
+            logger.trace(outcome.log);
         }
-        for (hostname, record) in record_set.routes() {
-            logger.trace(
-                self.update_dns_route(domain, &hostname, record, None)
-                    .await?,
-            );
+        for (hostname, record) in adds.into_iter().chain(updates) {
+            let outcome = match record {
+                DnsRecord::Srv { .. } | DnsRecord::Txt(_) => {
+                    self.update_dns_metadata(domain, &hostname, record, None)
+                        .await?
+                }
+                DnsRecord::A(_) | DnsRecord::Alias { .. } | DnsRecord::Cname(_) => {
+                    self.update_dns_route(domain, &hostname, record, None)
+                        .await?
+                }
+                DnsRecord::Caa { .. } | DnsRecord::None => continue,
+            };
+            changed |= outcome.changed;
+            logger.trace(outcome.log);
         }
-        Ok(logger.to_string())
+        Ok(DnsUpdateOutcome::new(changed, &logger))
     }
 
     /// Update (or remove) the route(s) to a particular host in the specified domain (zone).
@@ -58,16 +105,83 @@ pub trait CloudDns {
         hostname: &str,
         value: DnsRecord,
         ttl: Option<usize>,
-    ) -> Result<String, Error>;
+    ) -> Result<DnsUpdateOutcome, Error>;
 }
 
+/// Zone-level information about a domain (zone).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ZoneInfo {
+    /// The authoritative nameservers, e.g. `ns1.linode.com`, without trailing dots.
+    pub nameservers: Vec<String>,
+    /// The serial number of the SOA record, if the provider exposes it.
+    pub soa_serial: Option<u32>,
+    /// How often secondary nameservers should refresh the zone, in seconds.
+    pub soa_refresh_secs: u32,
+}
+
+/// The outcome of a successful DNS update.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DnsUpdateOutcome {
+    /// Whether any records were actually created, changed, or deleted.  If not, the
+    /// records were already up to date.
+    pub changed: bool,
+    /// Human-readable log of the update.
+    pub log: String,
+}
+
+impl DnsUpdateOutcome {
+    pub(crate) fn new(changed: bool, logger: &StringLogger) -> Self {
+        Self {
+            changed,
+            log: logger.to_string(),
+        }
+    }
+}
+
+impl Display for DnsUpdateOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.write_str(&self.log)
+    }
+}
+
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 /// The types of DNS metadata and routes that are supported.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DnsRecord {
     /// The `A` record is for IP addresses with optional geographic region.
     A(HashMap<IpAddr, Option<CloudDatacenter>>),
+    /// The `Alias` record (AWS only) is for routing to an AWS resource, such as a
+    /// CloudFront distribution or load balancer, even at the apex of a domain.
+    Alias {
+        /// The DNS name of the AWS resource, e.g. `d111111abcdef8.cloudfront.net`.
+        target: String,
+        /// The ID of the hosted zone of the AWS resource, e.g. `Z2FDTNDATAQYW2` for CloudFront.
+        hosted_zone_id: String,
+    },
+    /// The `Caa` record restricts which certificate authorities may issue certificates.
+    /// A host may have several, e.g. one per authority.
+    Caa {
+        /// Flags, e.g. `128` for critical (Linode only supports `0`).
+        flags: u8,
+        /// The property, e.g. `issue`, `issuewild`, or `iodef`.
+        tag: String,
+        /// The value of the property, e.g. `letsencrypt.org`.
+        value: String,
+    },
     /// The `Cname` record is for aliases.
     Cname(String),
+    /// The `Srv` record locates a service, e.g. at `_minecraft._tcp`.  For now, a host
+    /// may have at most one.
+    Srv {
+        /// Lower values are tried first.
+        priority: u16,
+        /// Relative weight among targets of the same priority.
+        weight: u16,
+        /// The port of the service.
+        port: u16,
+        /// The hostname providing the service, e.g. `mc.example.com`.
+        target: String,
+    },
     /// The `Txt` record is for text.
     Txt(String),
     /// `None` is for clearing an existing route or metadata.
@@ -75,6 +189,19 @@ pub enum DnsRecord {
 }
 
 impl DnsRecord {
+    /// Returns the name of the record type, e.g. `"CNAME"`.
+    pub fn record_type(&self) -> &'static str {
+        match self {
+            DnsRecord::A(_) => "A",
+            DnsRecord::Alias { .. } => "ALIAS",
+            DnsRecord::Caa { .. } => "CAA",
+            DnsRecord::Cname(_) => "CNAME",
+            DnsRecord::Srv { .. } => "SRV",
+            DnsRecord::Txt(_) => "TXT",
+            DnsRecord::None => "NONE",
+        }
+    }
+
     /// Create a DNS record for a single IP address
     pub fn new_a(ip_addr: IpAddr) -> Self {
         let mut m = HashMap::new();
@@ -98,13 +225,17 @@ impl Hash for DnsRecord {
             DnsRecord::Cname(_) => 2,
             DnsRecord::Txt(_) => 3,
             DnsRecord::None => 4,
+            DnsRecord::Alias { .. } => 5,
+            DnsRecord::Caa { .. } => 6,
+            DnsRecord::Srv { .. } => 7,
         };
         n.hash(state);
     }
 }
 
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 /// DNS record set for a domain.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct DnsRecordSet(pub(crate) HashSet<(String, DnsRecord)>);
 
 impl DnsRecordSet {
@@ -113,7 +244,110 @@ impl DnsRecordSet {
         Default::default()
     }
 
-    /// Returns the metadata records but not the route records.
+    /// Returns the changes needed to go from the `current` record set to this one.
+    /// Records are matched by hostname and record type, then compared by value.
+    /// Since a host may have several `Caa` records, they are also matched by value,
+    /// so they are only ever added or deleted.
+    pub fn diff(&self, current: &DnsRecordSet) -> DnsChanges {
+        type Key<'a> = (&'a str, &'static str, Option<&'a DnsRecord>);
+        fn by_key(set: &DnsRecordSet) -> HashMap<Key<'_>, &DnsRecord> {
+            set.0
+                .iter()
+                .filter(|(_, record)| *record != DnsRecord::None)
+                .map(|(hostname, record)| {
+                    let value = matches!(record, DnsRecord::Caa { .. }).then_some(record);
+                    ((hostname.as_str(), record.record_type(), value), record)
+                })
+                .collect()
+        }
+        let desired = by_key(self);
+        let current = by_key(current);
+        let mut changes = DnsChanges::default();
+        for (key @ (hostname, _, _), record) in &desired {
+            match current.get(key) {
+                None => changes.adds.push((hostname.to_string(), (*record).clone())),
+                Some(existing) if existing != record => changes
+                    .updates
+                    .push((hostname.to_string(), (*record).clone())),
+                _ => {}
+            }
+        }
+        for (key @ (hostname, _, _), record) in &current {
+            if !desired.contains_key(key) {
+                changes
+                    .deletes
+                    .push((hostname.to_string(), (*record).clone()));
+            }
+        }
+        for records in [
+            &mut changes.adds,
+            &mut changes.updates,
+            &mut changes.deletes,
+        ] {
+            records
+                .sort_by(|(h1, r1), (h2, r2)| (h1, r1.record_type()).cmp(&(h2, r2.record_type())));
+        }
+        changes
+    }
+
+    /// Returns the wildcard hostname that matches any subdomain of `hostname`, such
+    /// as `*.api`, or `*` for any subdomain of the domain if `hostname` is empty.
+    pub fn wildcard(hostname: &str) -> String {
+        if hostname.is_empty() {
+            "*".to_string()
+        } else {
+            format!("*.{}", hostname.to_ascii_lowercase())
+        }
+    }
+
+    /// Returns the `Caa` records, of which a host may have several, sorted by hostname.
+    pub fn caa(&self) -> Vec<(String, DnsRecord)> {
+        let mut caa: Vec<_> = self
+            .0
+            .iter()
+            .filter(|(_, record)| matches!(record, DnsRecord::Caa { .. }))
+            .cloned()
+            .collect();
+        caa.sort_by(|(h1, r1), (h2, r2)| match (r1, r2) {
+            (
+                DnsRecord::Caa {
+                    flags: f1,
+                    tag: t1,
+                    value: v1,
+                },
+                DnsRecord::Caa {
+                    flags: f2,
+                    tag: t2,
+                    value: v2,
+                },
+            ) => (h1, t1, v1, f1).cmp(&(h2, t2, v2, f2)),
+            _ => h1.cmp(h2),
+        });
+        caa
+    }
+
+    /// Returns the `Caa` records of each host.
+    pub(crate) fn caa_by_hostname(&self) -> HashMap<String, Vec<DnsRecord>> {
+        let mut caa: HashMap<String, Vec<DnsRecord>> = HashMap::new();
+        for (hostname, record) in self.caa() {
+            caa.entry(hostname).or_default().push(record);
+        }
+        caa
+    }
+
+    /// Returns the `Srv` records, which are named after their service (e.g.
+    /// `_minecraft._tcp`) rather than a host.
+    pub fn srv(&self) -> HashMap<String, DnsRecord> {
+        self.0
+            .iter()
+            .filter(|(_, record)| matches!(record, DnsRecord::Srv { .. }))
+            .map(|(hostname, record)| (hostname.clone(), record.clone()))
+            .collect()
+    }
+
+    /// Returns the metadata records but not the route records.  Since a host may
+    /// have several `Caa` records, they are excluded (see `caa`), as are `Srv`
+    /// records (see `srv`).
     pub fn metadata(&self) -> HashMap<String, DnsRecord> {
         self.0
             .iter()
@@ -130,7 +364,7 @@ impl DnsRecordSet {
         self.0
             .iter()
             .filter(|(_, record)| match record {
-                DnsRecord::A(_) | DnsRecord::Cname(_) => true,
+                DnsRecord::A(_) | DnsRecord::Alias { .. } | DnsRecord::Cname(_) => true,
                 _ => false,
             })
             .map(|(hostname, record)| (hostname.clone(), record.clone()))
@@ -138,7 +372,27 @@ impl DnsRecordSet {
     }
 }
 
-/// DNS record set builder.
+/// The changes needed to go from one DNS record set to another, sorted by hostname.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DnsChanges {
+    /// Records whose hostname and type are new.
+    pub adds: Vec<(String, DnsRecord)>,
+    /// Records whose hostname and type exist, but whose value has changed.
+    pub updates: Vec<(String, DnsRecord)>,
+    /// Records whose hostname and type no longer exist.
+    pub deletes: Vec<(String, DnsRecord)>,
+}
+
+impl DnsChanges {
+    /// Returns true if no changes are needed.
+    pub fn is_empty(&self) -> bool {
+        self.adds.is_empty() && self.updates.is_empty() && self.deletes.is_empty()
+    }
+}
+
+/// DNS record set builder.  Hostnames are relative to the domain (zone) and are
+/// converted to lowercase, since DNS is case-insensitive.  A hostname may be a
+/// wildcard (see `DnsRecordSet::wildcard`).
 #[derive(Default)]
 pub struct DnsRecordSetBuilder {
     record_set: DnsRecordSet,
@@ -155,7 +409,19 @@ impl DnsRecordSetBuilder {
     pub fn ag(mut self, hostname: &str, ipgeos: HashMap<IpAddr, Option<CloudDatacenter>>) -> Self {
         self.record_set
             .0
-            .insert((hostname.to_owned(), DnsRecord::A(ipgeos)));
+            .insert((hostname.to_ascii_lowercase(), DnsRecord::A(ipgeos)));
+        self
+    }
+
+    /// Add an `Alias` record (AWS only) for an AWS resource.
+    pub fn alias(mut self, hostname: &str, target: &str, hosted_zone_id: &str) -> Self {
+        self.record_set.0.insert((
+            hostname.to_ascii_lowercase(),
+            DnsRecord::Alias {
+                target: target.to_owned(),
+                hosted_zone_id: hosted_zone_id.to_owned(),
+            },
+        ));
         self
     }
 
@@ -164,47 +430,152 @@ impl DnsRecordSetBuilder {
         DnsRecordSet(self.record_set.0.drain().collect())
     }
 
+    /// The `Caa` record restricts which certificate authorities may issue certificates.
+    /// May be called more than once per hostname.
+    pub fn caa(mut self, hostname: &str, flags: u8, tag: &str, value: &str) -> Self {
+        self.record_set.0.insert((
+            hostname.to_ascii_lowercase(),
+            DnsRecord::Caa {
+                flags,
+                tag: tag.to_owned(),
+                value: value.to_owned(),
+            },
+        ));
+        self
+    }
+
     /// The `Cname` record is for aliases.
     pub fn cname(mut self, hostname: &str, name: &str) -> Self {
-        self.record_set
-            .0
-            .insert((hostname.to_owned(), DnsRecord::Cname(name.to_owned())));
+        self.record_set.0.insert((
+            hostname.to_ascii_lowercase(),
+            DnsRecord::Cname(name.to_owned()),
+        ));
+        self
+    }
+
+    /// The `Srv` record locates a service, e.g. `_minecraft._tcp`.
+    pub fn srv(
+        mut self,
+        hostname: &str,
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: &str,
+    ) -> Self {
+        self.record_set.0.insert((
+            hostname.to_ascii_lowercase(),
+            DnsRecord::Srv {
+                priority,
+                weight,
+                port,
+                target: target.to_owned(),
+            },
+        ));
         self
     }
 
     /// The `Txt` record is for text.
     pub fn txt(mut self, hostname: &str, text: &str) -> Self {
-        self.record_set
-            .0
-            .insert((hostname.to_owned(), DnsRecord::Txt(text.to_owned())));
+        self.record_set.0.insert((
+            hostname.to_ascii_lowercase(),
+            DnsRecord::Txt(text.to_owned()),
+        ));
         self
     }
 }
 
 /// Cloud DNS client.
 pub struct CloudDnsClient {
-    aws: Arc<dyn CloudDns + Sync + Send>,
-    linode: Arc<dyn CloudDns + Sync + Send>,
+    aws: Option<Arc<dyn CloudDns + Sync + Send>>,
+    linode: Option<Arc<dyn CloudDns + Sync + Send>>,
 }
 
 /// Multiple DNS APIs.
 impl CloudDnsClient {
-    /// Create a new cloud DNS client.
+    /// Create a new cloud DNS client.  Only the providers with a config section (`aws`
+    /// and/or `linode`) are created, so credentials are only needed for those.
     pub async fn new(cub_config: &CubConfig) -> CloudDnsClient {
-        Self {
-            aws: Arc::new(AwsDns::new(cub_config).await),
-            linode: Arc::new(LinodeDns::new(cub_config)),
-        }
+        let aws: Option<Arc<dyn CloudDns + Sync + Send>> = if cub_config.has_section("aws") {
+            Some(Arc::new(AwsDns::new(cub_config).await))
+        } else {
+            None
+        };
+        let linode: Option<Arc<dyn CloudDns + Sync + Send>> = if cub_config.has_section("linode") {
+            Some(Arc::new(LinodeDns::new(cub_config)))
+        } else {
+            None
+        };
+        Self::with_providers(aws, linode)
+    }
+
+    /// Create a cloud DNS client with the specified provider APIs, e.g. `MockDns`.
+    pub fn with_providers(
+        aws: Option<Arc<dyn CloudDns + Sync + Send>>,
+        linode: Option<Arc<dyn CloudDns + Sync + Send>>,
+    ) -> CloudDnsClient {
+        Self { aws, linode }
+    }
+
+    /// Every configured provider API, with its name.
+    fn providers(&self) -> Vec<(&'static str, Arc<dyn CloudDns + Sync + Send>)> {
+        [("aws", &self.aws), ("linode", &self.linode)]
+            .into_iter()
+            .filter_map(|(name, api)| api.as_ref().map(|api| (name, Arc::clone(api))))
+            .collect()
     }
 
-    /// Choose which nameserver to use.
+    /// Choose which nameserver to use, `aws` or `linode`.  If unspecified, Linode is
+    /// used if it is configured, otherwise AWS.  Returns an error if the nameserver isn't
+    /// configured.
     pub async fn nameserver_api(
         &self,
         nameserver_api: Option<&str>,
-    ) -> Arc<dyn CloudDns + Sync + Send> {
-        match nameserver_api {
-            Some("aws") => Arc::clone(&self.aws),
-            _ => Arc::clone(&self.linode),
+    ) -> Result<Arc<dyn CloudDns + Sync + Send>, Error> {
+        let api = match nameserver_api {
+            Some("aws") => self.aws.as_ref(),
+            Some("linode") => self.linode.as_ref(),
+            None => self.linode.as_ref().or(self.aws.as_ref()),
+            Some(other) => {
+                return Err(Error::String(format!("{other}: not a nameserver API")));
+            }
+        };
+        api.map(Arc::clone).ok_or_else(|| {
+            Error::String(format!(
+                "{}: nameserver API is not configured",
+                nameserver_api.unwrap_or("default")
+            ))
+        })
+    }
+
+    /// Update the DNS records of the specified domain (zone) with every configured
+    /// provider in parallel, e.g. to keep dual-homed DNS in sync during a migration.
+    /// Returns the combined outcome of all providers, or (if any provider failed) an
+    /// error containing the logs and errors of all of them.
+    pub async fn update_all(
+        &self,
+        domain: &str,
+        record_set: DnsRecordSet,
+    ) -> Result<DnsUpdateOutcome, Error> {
+        let providers = self.providers();
+        if providers.is_empty() {
+            return Err(Error::String("no nameserver API is configured".to_string()));
+        }
+        let results = join_all(providers.into_iter().map(|(name, api)| {
+            let record_set = record_set.clone();
+            async move { (name, api.update_dns_records(domain, record_set).await) }
+        }))
+        .await;
+        let logger = StringLogger::default();
+        let mut changed = false;
+        for (name, result) in results {
+            changed |= result.as_ref().is_ok_and(|outcome| outcome.changed);
+            let result = result.map(|outcome| outcome.log);
+            let _ = logger.conclude(format!("{name} update of {domain}"), result);
+        }
+        if logger.contains_warnings() {
+            Err(Error::String(logger.to_string()))
+        } else {
+            Ok(DnsUpdateOutcome::new(changed, &logger))
         }
     }
 }
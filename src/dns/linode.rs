@@ -1,12 +1,13 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::{CloudDns, DnsRecord, DnsRecordSet};
+use super::{CloudDns, DnsRecord, DnsRecordSet, DnsUpdateOutcome, ZoneInfo};
 use crate::common::{CubConfig, Error};
 use crate::log::StringLogger;
 use async_trait::async_trait;
 use hyper::{http::HeaderValue, HeaderMap, StatusCode};
-use reqwest::Client;
+use reqwest::{Client, Response};
+use serde::de::{DeserializeOwned, IgnoredAny};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
@@ -18,16 +19,22 @@ use std::{
 /// This struct implements `CloudDNS` for Linode.
 pub struct LinodeDns {
     client: Client,
+    /// The TTL of records for which none is specified.
+    ttl_secs: usize,
 }
 
 impl LinodeDns {
     const TIMEOUT_SECS: u64 = 5;
     const TTL_SECS: usize = 30;
+    /// The SOA refresh interval of domains whose `refresh_sec` is zero.
+    const DEFAULT_REFRESH_SECS: u32 = 14400;
 
-    /// Create a `CloudDNS` for Linode.
+    /// Create a `CloudDNS` for Linode.  The default TTL is that of the `dns_ttl_secs`
+    /// in the `linode` config, if any, otherwise 30 seconds.
     pub fn new(cub_config: &CubConfig) -> Self {
         #[derive(Deserialize)]
         struct LinodeConfig {
+            dns_ttl_secs: Option<usize>,
             personal_access_token: String,
         }
         #[derive(Deserialize)]
@@ -35,17 +42,19 @@ impl LinodeDns {
             linode: LinodeConfig,
         }
         let ConfigToml {
-            linode: LinodeConfig {
-                personal_access_token,
-            },
+            linode:
+                LinodeConfig {
+                    dns_ttl_secs,
+                    personal_access_token,
+                },
         } = cub_config.get().expect("linode.toml");
 
         let mut default_headers = HeaderMap::new();
 
-        default_headers.insert(
-            reqwest::header::AUTHORIZATION,
-            HeaderValue::from_str(&format!("Bearer {}", personal_access_token)).unwrap(),
-        );
+        let mut auth_header =
+            HeaderValue::from_str(&format!("Bearer {}", personal_access_token)).unwrap();
+        auth_header.set_sensitive(true);
+        default_headers.insert(reqwest::header::AUTHORIZATION, auth_header);
         default_headers.insert(
             reqwest::header::USER_AGENT,
             HeaderValue::from_str("softbear cloud control").unwrap(),
@@ -58,7 +67,328 @@ impl LinodeDns {
                 .http1_only()
                 .build()
                 .unwrap(),
+            ttl_secs: dns_ttl_secs
+                .filter(|ttl| *ttl != 0)
+                .unwrap_or(Self::TTL_SECS),
+        }
+    }
+
+    /// Replace the `Caa` records of a host, given the records of its domain.
+    async fn apply_caa(
+        &self,
+        zone: &LinodeZone<'_>,
+        hostname: &str,
+        values: Vec<DnsRecord>,
+        ttl_sec: usize,
+    ) -> Result<bool, Error> {
+        let LinodeZone {
+            domain_id,
+            records,
+            logger,
+            ..
+        } = *zone;
+        let mut desired: Vec<(String, String)> = Vec::new();
+        for value in values {
+            if let DnsRecord::Caa { flags, tag, value } = value {
+                if flags != 0 {
+                    return Err(Error::Http(
+                        StatusCode::BAD_REQUEST,
+                        format!("Linode does not support CAA flags ({flags})"),
+                    ));
+                }
+                if !desired.contains(&(tag.clone(), value.clone())) {
+                    desired.push((tag, value));
+                }
+            }
+        }
+
+        let mut changed = false;
+        let mut found: Vec<(String, String)> = Vec::new();
+        for record in records.iter().filter(|r| {
+            r.record.name.eq_ignore_ascii_case(hostname)
+                && r.record.record_type == LinodeRecordType::Caa
+        }) {
+            let existing = (
+                record.record.tag.clone().unwrap_or_default(),
+                record.record.target.clone(),
+            );
+            if desired.contains(&existing) && !found.contains(&existing) {
+                found.push(existing);
+            } else {
+                self.delete_domain_record(domain_id, record.id).await?;
+                changed = true;
+            }
         }
+        for (tag, value) in desired {
+            if found.contains(&(tag.clone(), value.clone())) {
+                continue;
+            }
+            self.create_domain_record(
+                domain_id,
+                LinodeDomainRecord {
+                    tag: Some(tag),
+                    ..LinodeDomainRecord::new(hostname, LinodeRecordType::Caa, value, ttl_sec)
+                },
+                logger,
+            )
+            .await?;
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    /// Update (or remove) the metadata of a host, given the records of its domain.
+    async fn apply_metadata(
+        &self,
+        zone: &LinodeZone<'_>,
+        hostname: &str,
+        value: DnsRecord,
+        ttl_sec: usize,
+    ) -> Result<bool, Error> {
+        let LinodeZone {
+            domain_id,
+            records,
+            logger,
+            ..
+        } = *zone;
+        let id_records: Vec<_> = records
+            .iter()
+            .filter(|r| {
+                r.record.name.eq_ignore_ascii_case(hostname)
+                    && match r.record.record_type {
+                        LinodeRecordType::Txt | LinodeRecordType::Mx | LinodeRecordType::Srv => {
+                            true
+                        }
+                        _ => false,
+                    }
+            })
+            .collect();
+
+        let changed = match value {
+            DnsRecord::Txt(text) => {
+                if let [record] = id_records.as_slice() {
+                    if record.record.record_type == LinodeRecordType::Txt
+                        && record.record.target == text
+                    {
+                        // Already up to date.
+                        return Ok(false);
+                    }
+                }
+                for record_id in id_records
+                    .iter()
+                    .filter(|r| r.record.record_type == LinodeRecordType::Txt)
+                    .map(|r| r.id)
+                {
+                    self.delete_domain_record(domain_id, record_id).await?;
+                }
+                self.create_domain_record(
+                    domain_id,
+                    LinodeDomainRecord::new(hostname, LinodeRecordType::Txt, text, ttl_sec),
+                    logger,
+                )
+                .await?;
+                true
+            }
+            DnsRecord::Caa { flags, tag, value } => {
+                if flags != 0 {
+                    return Err(Error::Http(
+                        StatusCode::BAD_REQUEST,
+                        format!("Linode does not support CAA flags ({flags})"),
+                    ));
+                }
+                if records.iter().any(|r| {
+                    r.record.name.eq_ignore_ascii_case(hostname)
+                        && r.record.record_type == LinodeRecordType::Caa
+                        && r.record.tag.as_ref() == Some(&tag)
+                        && r.record.target == value
+                }) {
+                    // Already exists.
+                    return Ok(false);
+                }
+                self.create_domain_record(
+                    domain_id,
+                    LinodeDomainRecord {
+                        tag: Some(tag),
+                        ..LinodeDomainRecord::new(hostname, LinodeRecordType::Caa, value, ttl_sec)
+                    },
+                    logger,
+                )
+                .await?;
+                true
+            }
+            DnsRecord::Srv {
+                priority,
+                weight,
+                port,
+                target,
+            } => {
+                let srv_records: Vec<_> = records
+                    .iter()
+                    .filter(|r| {
+                        r.record.record_type == LinodeRecordType::Srv
+                            && r.record.hostname().eq_ignore_ascii_case(hostname)
+                    })
+                    .collect();
+                let normalized = target.trim_end_matches('.').to_ascii_lowercase();
+                if let [record] = srv_records.as_slice() {
+                    if record.record.srv() == Some((priority, weight, port, normalized)) {
+                        // Already up to date.
+                        return Ok(false);
+                    }
+                }
+                for record in srv_records {
+                    self.delete_domain_record(domain_id, record.id).await?;
+                }
+                let record =
+                    LinodeDomainRecord::new_srv(hostname, priority, weight, port, target, ttl_sec)
+                        .ok_or_else(|| {
+                            Error::Http(
+                                StatusCode::BAD_REQUEST,
+                                format!("{hostname}: SRV records must be named _service._protocol"),
+                            )
+                        })?;
+                self.create_domain_record(domain_id, record, logger).await?;
+                true
+            }
+            DnsRecord::None => {
+                let mut changed = false;
+                for record_id in records
+                    .iter()
+                    .filter(|r| match r.record.record_type {
+                        LinodeRecordType::Txt => r.record.name.eq_ignore_ascii_case(hostname),
+                        LinodeRecordType::Srv => r.record.hostname().eq_ignore_ascii_case(hostname),
+                        _ => false,
+                    })
+                    .map(|r| r.id)
+                {
+                    self.delete_domain_record(domain_id, record_id).await?;
+                    changed = true;
+                }
+                changed
+            }
+            _ => {
+                logger.trace("non-metadata record ignored".to_string());
+                false
+            }
+        };
+        Ok(changed)
+    }
+
+    /// Update (or remove) the route(s) to a host, given the records of its domain.
+    async fn apply_route(
+        &self,
+        zone: &LinodeZone<'_>,
+        hostname: &str,
+        value: DnsRecord,
+        ttl_sec: usize,
+    ) -> Result<bool, Error> {
+        let LinodeZone {
+            domain,
+            domain_id,
+            records,
+            logger,
+        } = *zone;
+        let id_records: Vec<_> = records
+            .iter()
+            .filter(|r| r.record.name.eq_ignore_ascii_case(hostname))
+            .collect();
+
+        let changed = match value {
+            DnsRecord::A(ipgeos) => {
+                // For now, Linode ignores regions.
+                let mut ip_addrs = HashSet::new();
+                for ip in ipgeos.keys() {
+                    ip_addrs.insert(*ip);
+                }
+                self.upsert_a_record(
+                    domain,
+                    domain_id,
+                    hostname,
+                    ttl_sec,
+                    &id_records,
+                    ip_addrs,
+                    logger,
+                )
+                .await?
+            }
+            DnsRecord::Cname(link) => {
+                let mut removals: Vec<usize> = Vec::new();
+                let mut found: bool = false;
+                for LinodeRecordResponse {
+                    id,
+                    record:
+                        LinodeDomainRecord {
+                            record_type,
+                            target,
+                            ..
+                        },
+                } in id_records.iter()
+                {
+                    match record_type {
+                        LinodeRecordType::A => {
+                            removals.push(*id);
+                        }
+                        LinodeRecordType::Cname => {
+                            if !found && *target == link {
+                                found = true;
+                            } else {
+                                removals.push(*id);
+                            }
+                        }
+                        _ => {
+                            // Ignore TXT records, etc.
+                        }
+                    }
+                }
+
+                let changed = !found || !removals.is_empty();
+                for record_id in removals {
+                    self.delete_domain_record(domain_id, record_id).await?;
+                }
+
+                if !found {
+                    self.create_domain_record(
+                        domain_id,
+                        LinodeDomainRecord::new(hostname, LinodeRecordType::Cname, link, ttl_sec),
+                        logger,
+                    )
+                    .await?;
+                }
+                changed
+            }
+            DnsRecord::None => {
+                let mut changed = false;
+                for LinodeRecordResponse {
+                    id: record_id,
+                    record: LinodeDomainRecord { record_type, .. },
+                    ..
+                } in id_records.iter()
+                {
+                    match record_type {
+                        LinodeRecordType::A | LinodeRecordType::Cname => {
+                            self.delete_domain_record(domain_id, *record_id).await?;
+                            changed = true;
+                        }
+                        _ => {
+                            // Ignore TXT records, etc.
+                        }
+                    }
+                }
+                changed
+            }
+            DnsRecord::Alias { .. } => {
+                return Err(Error::Http(
+                    StatusCode::BAD_REQUEST,
+                    format!("{hostname}: alias records are not supported by Linode"),
+                ))
+            }
+            _ => {
+                logger.trace("non route record ignored".to_string());
+                false
+            }
+        };
+
+        Ok(changed)
     }
 
     async fn create_domain_record(
@@ -80,34 +410,10 @@ impl LinodeDns {
             .execute(request)
             .await
             .map_err(Self::map_error)?;
-        let text = response.text().await.map_err(Self::map_error)?;
-        match serde_json::from_str(&text) {
-            Ok(response) => Ok(response),
-            Err(_) => {
-                println!(">> {diagnostic}\n<< {text}");
-                #[derive(Deserialize)]
-                struct LinodeReason {
-                    reason: String,
-                }
-                #[derive(Deserialize)]
-                struct LinodeError {
-                    errors: Vec<LinodeReason>,
-                }
-                match serde_json::from_str(&text) {
-                    Ok(LinodeError { errors }) => {
-                        let r = errors.into_iter().next().map(|r| r.reason);
-                        Err(Error::Http(
-                            StatusCode::FAILED_DEPENDENCY,
-                            format!("linode error: {r:?}"),
-                        ))
-                    }
-                    Err(_) => Err(Error::Http(
-                        StatusCode::FAILED_DEPENDENCY,
-                        format!("cannot parse linode error"),
-                    )),
-                }
-            }
-        }
+        Self::parse_response(response).await.map_err(|e| {
+            println!(">> {diagnostic}\n<< {e}");
+            e
+        })
     }
 
     async fn delete_domain_record(&self, domain_id: usize, id: usize) -> Result<(), Error> {
@@ -120,10 +426,12 @@ impl LinodeDns {
             .delete(endpoint)
             .build()
             .map_err(Self::map_error)?;
-        self.client
+        let response = self
+            .client
             .execute(request)
             .await
             .map_err(Self::map_error)?;
+        Self::parse_response::<IgnoredAny>(response).await?;
         Ok(())
     }
 
@@ -135,16 +443,23 @@ impl LinodeDns {
             .find(|d| d.domain == domain_name)
             .map(|d| d.id)
             .ok_or(Error::Http(
-                StatusCode::FAILED_DEPENDENCY,
+                StatusCode::NOT_FOUND,
                 format!("Could not find domain {domain_name}"),
             ))
     }
 
+    async fn get_linode_domain(&self, domain_id: usize) -> Result<LinodeDomainResponse, Error> {
+        let endpoint = format!("https://api.linode.com/v4/domains/{}", domain_id);
+        let request = self.client.get(endpoint);
+        let response = request.send().await.map_err(Self::map_error)?;
+        Self::parse_response(response).await
+    }
+
     async fn list_linode_domains(&self) -> Result<ListLinodeDomainsResponse, Error> {
         let endpoint = "https://api.linode.com/v4/domains";
         let request = self.client.get(endpoint);
         let response = request.send().await.map_err(Self::map_error)?;
-        response.json().await.map_err(Self::map_error)
+        Self::parse_response(response).await
     }
 
     async fn list_linode_records(
@@ -154,13 +469,43 @@ impl LinodeDns {
         let endpoint = format!("https://api.linode.com/v4/domains/{}/records", domain_id);
         let request = self.client.get(endpoint);
         let response = request.send().await.map_err(Self::map_error)?;
-        response.json().await.map_err(Self::map_error)
+        Self::parse_response(response).await
     }
 
     fn map_error(e: reqwest::Error) -> Error {
         Error::Http(StatusCode::FAILED_DEPENDENCY, format!("{}", e))
     }
 
+    /// Parses a Linode response, inspecting its status before its body.
+    async fn parse_response<T: DeserializeOwned>(response: Response) -> Result<T, Error> {
+        let status = response.status();
+        let text = response.text().await.map_err(Self::map_error)?;
+        if !status.is_success() {
+            #[derive(Deserialize)]
+            struct LinodeReason {
+                reason: String,
+            }
+            #[derive(Deserialize)]
+            struct LinodeError {
+                errors: Vec<LinodeReason>,
+            }
+            let message = match serde_json::from_str(&text) {
+                Ok(LinodeError { errors }) => {
+                    let r = errors.into_iter().next().map(|r| r.reason);
+                    format!("linode error: {r:?}")
+                }
+                Err(_) => format!("cannot parse linode error: {text}"),
+            };
+            return Err(Error::from_provider_status(status, message));
+        }
+        serde_json::from_str(&text).map_err(|e| {
+            Error::Http(
+                StatusCode::FAILED_DEPENDENCY,
+                format!("cannot parse linode response: {e}"),
+            )
+        })
+    }
+
     fn parse_ip(target: &str, domain: &str, hostname: &str) -> Result<IpAddr, Error> {
         IpAddr::from_str(target).map_err(|_| {
             Error::Http(
@@ -170,16 +515,23 @@ impl LinodeDns {
         })
     }
 
+    fn ttl_sec(&self, ttl: Option<usize>) -> usize {
+        match ttl {
+            Some(ttl) if ttl != 0 => ttl,
+            _ => self.ttl_secs,
+        }
+    }
+
     async fn upsert_a_record(
         &self,
         domain: &str,
         domain_id: usize,
         hostname: &str,
         ttl_sec: usize,
-        id_records: &Vec<&LinodeRecordResponse>,
+        id_records: &[&LinodeRecordResponse],
         ip_addrs: HashSet<IpAddr>,
         logger: &StringLogger,
-    ) -> Result<(), Error> {
+    ) -> Result<bool, Error> {
         let mut removals: Vec<usize> = Vec::new();
         let mut found: HashSet<IpAddr> = HashSet::new();
         for LinodeRecordResponse {
@@ -211,15 +563,16 @@ impl LinodeDns {
         let mut adds: Vec<LinodeDomainRecord> = Vec::new();
         for ip in ip_addrs {
             if !found.contains(&ip) {
-                adds.push(LinodeDomainRecord {
-                    name: hostname.to_string(),
-                    record_type: LinodeRecordType::A,
-                    target: ip.to_string(),
+                adds.push(LinodeDomainRecord::new(
+                    hostname,
+                    LinodeRecordType::A,
+                    ip.to_string(),
                     ttl_sec,
-                });
+                ));
             }
         }
 
+        let changed = !removals.is_empty() || !adds.is_empty();
         for record_id in removals {
             self.delete_domain_record(domain_id, record_id).await?;
         }
@@ -229,7 +582,7 @@ impl LinodeDns {
                 .await?;
         }
 
-        Ok(())
+        Ok(changed)
     }
 }
 
@@ -244,18 +597,31 @@ impl CloudDns for LinodeDns {
 
         let mut a_ips: HashMap<String, HashSet<IpAddr>> = HashMap::new();
         let mut other: HashMap<String, DnsRecord> = HashMap::new();
-
-        for LinodeRecordResponse {
-            record:
-                LinodeDomainRecord {
-                    name: hostname,
-                    target,
-                    record_type,
-                    ..
-                },
-            ..
-        } in list.data.into_iter()
-        {
+        let mut caa: Vec<(String, DnsRecord)> = Vec::new();
+        let mut srv: Vec<(String, DnsRecord)> = Vec::new();
+
+        for LinodeRecordResponse { record, .. } in list.data.into_iter() {
+            if let Some((priority, weight, port, target)) = record.srv() {
+                srv.push((
+                    record.hostname().to_ascii_lowercase(),
+                    DnsRecord::Srv {
+                        priority,
+                        weight,
+                        port,
+                        target,
+                    },
+                ));
+                continue;
+            }
+            let LinodeDomainRecord {
+                name: hostname,
+                target,
+                record_type,
+                tag,
+                ..
+            } = record;
+            // DNS is case-insensitive.
+            let hostname = hostname.to_ascii_lowercase();
             match record_type {
                 LinodeRecordType::A => {
                     let ip = Self::parse_ip(&target, &domain, &hostname)?;
@@ -267,6 +633,16 @@ impl CloudDns for LinodeDns {
                 LinodeRecordType::Txt => {
                     other.insert(hostname, DnsRecord::Txt(target));
                 }
+                LinodeRecordType::Caa => {
+                    caa.push((
+                        hostname,
+                        DnsRecord::Caa {
+                            flags: 0,
+                            tag: tag.unwrap_or_default(),
+                            value: target,
+                        },
+                    ));
+                }
                 _ => {}
             }
         }
@@ -274,7 +650,7 @@ impl CloudDns for LinodeDns {
         // May be more capacity than required, but always enough.
         let mut dns_records = HashSet::with_capacity(list_len);
 
-        for (hostname, record) in other.into_iter() {
+        for (hostname, record) in other.into_iter().chain(caa).chain(srv) {
             dns_records.insert((hostname, record));
         }
 
@@ -286,75 +662,96 @@ impl CloudDns for LinodeDns {
         Ok(DnsRecordSet(dns_records))
     }
 
+    /// Linode serves every domain from the same nameservers and doesn't expose the
+    /// SOA serial.
+    async fn read_zone_info(&self, domain: &str) -> Result<ZoneInfo, Error> {
+        let domain_id = self.get_domain_id(domain).await?;
+        let detail = self.get_linode_domain(domain_id).await?;
+        Ok(ZoneInfo {
+            nameservers: (1..=5).map(|n| format!("ns{n}.linode.com")).collect(),
+            soa_serial: None,
+            soa_refresh_secs: match detail.refresh_sec {
+                0 => Self::DEFAULT_REFRESH_SECS,
+                refresh_sec => refresh_sec,
+            },
+        })
+    }
+
+    async fn update_dns_caa(
+        &self,
+        domain: &str,
+        hostname: &str,
+        values: Vec<DnsRecord>,
+        ttl: Option<usize>,
+    ) -> Result<DnsUpdateOutcome, Error> {
+        let logger = StringLogger::default();
+        let domain_id = self.get_domain_id(domain).await?;
+        let ttl_sec = self.ttl_sec(ttl);
+        let records = self.list_linode_records(domain_id).await?.data;
+        let zone = LinodeZone {
+            domain,
+            domain_id,
+            records: &records,
+            logger: &logger,
+        };
+        let changed = self.apply_caa(&zone, hostname, values, ttl_sec).await?;
+        Ok(DnsUpdateOutcome::new(changed, &logger))
+    }
+
     async fn update_dns_metadata(
         &self,
         domain: &str,
         hostname: &str,
         value: DnsRecord,
         ttl: Option<usize>,
-    ) -> Result<String, Error> {
+    ) -> Result<DnsUpdateOutcome, Error> {
         let logger = StringLogger::default();
         let domain_id = self.get_domain_id(domain).await?;
-
-        let ttl_sec = if let Some(ttl) = ttl {
-            if ttl == 0 {
-                Self::TTL_SECS
-            } else {
-                ttl
-            }
-        } else {
-            Self::TTL_SECS
+        let ttl_sec = self.ttl_sec(ttl);
+        let records = self.list_linode_records(domain_id).await?.data;
+        let zone = LinodeZone {
+            domain,
+            domain_id,
+            records: &records,
+            logger: &logger,
         };
+        let changed = self.apply_metadata(&zone, hostname, value, ttl_sec).await?;
+        Ok(DnsUpdateOutcome::new(changed, &logger))
+    }
 
-        let response = self.list_linode_records(domain_id).await?;
-
-        let id_records: Vec<_> = response
-            .data
-            .iter()
-            .filter(|r| {
-                r.record.name == hostname
-                    && match r.record.record_type {
-                        LinodeRecordType::Txt | LinodeRecordType::Mx | LinodeRecordType::Srv => {
-                            true
-                        }
-                        _ => false,
-                    }
-            })
-            .collect();
-
-        match value {
-            DnsRecord::Txt(text) => {
-                for record_id in id_records
-                    .iter()
-                    .filter(|r| r.record.record_type == LinodeRecordType::Txt)
-                    .map(|r| r.id)
-                {
-                    self.delete_domain_record(domain_id, record_id).await?;
-                }
-                self.create_domain_record(
-                    domain_id,
-                    LinodeDomainRecord {
-                        name: hostname.to_string(),
-                        record_type: LinodeRecordType::Txt,
-                        target: text,
-                        ttl_sec,
-                    },
-                    &logger,
-                )
+    /// Update multiple DNS records, reading the domain (zone) once and then applying
+    /// only the necessary changes.
+    async fn update_dns_records(
+        &self,
+        domain: &str,
+        record_set: DnsRecordSet,
+    ) -> Result<DnsUpdateOutcome, Error> {
+        let logger = StringLogger::default();
+        let domain_id = self.get_domain_id(domain).await?;
+        let records = self.list_linode_records(domain_id).await?.data;
+        let zone = LinodeZone {
+            domain,
+            domain_id,
+            records: &records,
+            logger: &logger,
+        };
+        let mut changed = false;
+        for (hostname, values) in record_set.caa_by_hostname() {
+            changed |= self
+                .apply_caa(&zone, &hostname, values, self.ttl_secs)
+                .await?;
+        }
+        for (hostname, record) in record_set.metadata().into_iter().chain(record_set.srv()) {
+            changed |= self
+                .apply_metadata(&zone, &hostname, record, self.ttl_secs)
                 .await?;
-            }
-            DnsRecord::None => {
-                for record_id in id_records
-                    .iter()
-                    .filter(|r| r.record.record_type == LinodeRecordType::Txt)
-                    .map(|r| r.id)
-                {
-                    self.delete_domain_record(domain_id, record_id).await?;
-                }
-            }
-            _ => logger.trace("non-metadata record ignored".to_string()),
         }
-        Ok(logger.to_string())
+        for (hostname, record) in record_set.routes() {
+            changed |= self
+                .apply_route(&zone, &hostname, record, self.ttl_secs)
+                .await?;
+        }
+        Ok(DnsUpdateOutcome::new(changed, &logger))
     }
 
     async fn update_dns_route(
@@ -363,117 +760,30 @@ impl CloudDns for LinodeDns {
         hostname: &str,
         value: DnsRecord,
         ttl: Option<usize>,
-    ) -> Result<String, Error> {
+    ) -> Result<DnsUpdateOutcome, Error> {
         let logger = StringLogger::default();
         let domain_id = self.get_domain_id(domain).await?;
-
-        let ttl_sec = if let Some(ttl) = ttl {
-            if ttl == 0 {
-                Self::TTL_SECS
-            } else {
-                ttl
-            }
-        } else {
-            Self::TTL_SECS
+        let ttl_sec = self.ttl_sec(ttl);
+        let records = self.list_linode_records(domain_id).await?.data;
+        let zone = LinodeZone {
+            domain,
+            domain_id,
+            records: &records,
+            logger: &logger,
         };
-        let response = self.list_linode_records(domain_id).await?;
-
-        let id_records: Vec<_> = response
-            .data
-            .iter()
-            .filter(|r| r.record.name == hostname)
-            .collect();
-
-        match value {
-            DnsRecord::A(ipgeos) => {
-                // For now, Linode ignores regions.
-                let mut ip_addrs = HashSet::new();
-                for ip in ipgeos.keys() {
-                    ip_addrs.insert(*ip);
-                }
-                self.upsert_a_record(
-                    domain,
-                    domain_id,
-                    hostname,
-                    ttl_sec,
-                    &id_records,
-                    ip_addrs,
-                    &logger,
-                )
-                .await?
-            }
-            DnsRecord::Cname(link) => {
-                let mut removals: Vec<usize> = Vec::new();
-                let mut found: bool = false;
-                for LinodeRecordResponse {
-                    id,
-                    record:
-                        LinodeDomainRecord {
-                            record_type,
-                            target,
-                            ..
-                        },
-                } in id_records.iter()
-                {
-                    match record_type {
-                        LinodeRecordType::A => {
-                            removals.push(*id);
-                        }
-                        LinodeRecordType::Cname => {
-                            if !found && *target == link {
-                                found = true;
-                            } else {
-                                removals.push(*id);
-                            }
-                        }
-                        _ => {
-                            // Ignore TXT records, etc.
-                        }
-                    }
-                }
-
-                for record_id in removals {
-                    self.delete_domain_record(domain_id, record_id).await?;
-                }
-
-                if !found {
-                    self.create_domain_record(
-                        domain_id,
-                        LinodeDomainRecord {
-                            name: hostname.to_string(),
-                            record_type: LinodeRecordType::Cname,
-                            target: link,
-                            ttl_sec,
-                        },
-                        &logger,
-                    )
-                    .await?;
-                }
-            }
-            DnsRecord::None => {
-                for LinodeRecordResponse {
-                    id: record_id,
-                    record: LinodeDomainRecord { record_type, .. },
-                    ..
-                } in id_records.iter()
-                {
-                    match record_type {
-                        LinodeRecordType::A | LinodeRecordType::Cname => {
-                            self.delete_domain_record(domain_id, *record_id).await?;
-                        }
-                        _ => {
-                            // Ignore TXT records, etc.
-                        }
-                    }
-                }
-            }
-            _ => logger.trace("non route record ignored".to_string()),
-        }
-
-        Ok(logger.to_string())
+        let changed = self.apply_route(&zone, hostname, value, ttl_sec).await?;
+        Ok(DnsUpdateOutcome::new(changed, &logger))
     }
 }
 
+/// A domain (zone) and its records, read once before applying changes to it.
+struct LinodeZone<'a> {
+    domain: &'a str,
+    domain_id: usize,
+    records: &'a [LinodeRecordResponse],
+    logger: &'a StringLogger,
+}
+
 #[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 struct LinodeDomainRecord {
     name: String,
@@ -481,6 +791,98 @@ struct LinodeDomainRecord {
     ttl_sec: usize,
     #[serde(rename = "type")]
     record_type: LinodeRecordType,
+    /// The property of a CAA record, e.g. `issue`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    /// The service of a SRV record, e.g. `minecraft` (Linode adds the underscore).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    service: Option<String>,
+    /// The protocol of a SRV record, e.g. `tcp` (Linode adds the underscore).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    protocol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weight: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+}
+
+impl LinodeDomainRecord {
+    fn new(hostname: &str, record_type: LinodeRecordType, target: String, ttl_sec: usize) -> Self {
+        Self {
+            name: hostname.to_string(),
+            target,
+            ttl_sec,
+            record_type,
+            tag: None,
+            service: None,
+            protocol: None,
+            priority: None,
+            weight: None,
+            port: None,
+        }
+    }
+
+    /// Returns a SRV record, or `None` if `hostname` isn't of the form
+    /// `_service._protocol[.name]`.
+    fn new_srv(
+        hostname: &str,
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: String,
+        ttl_sec: usize,
+    ) -> Option<Self> {
+        let mut labels = hostname.splitn(3, '.');
+        let service = labels.next()?.strip_prefix('_')?;
+        let protocol = labels.next()?.strip_prefix('_')?;
+        Some(Self {
+            service: Some(service.to_owned()),
+            protocol: Some(protocol.to_owned()),
+            priority: Some(priority),
+            weight: Some(weight),
+            port: Some(port),
+            ..Self::new(
+                labels.next().unwrap_or_default(),
+                LinodeRecordType::Srv,
+                target,
+                ttl_sec,
+            )
+        })
+    }
+
+    /// Returns the hostname, which for SRV records is prefixed by `_service._protocol`.
+    fn hostname(&self) -> String {
+        match (&self.record_type, &self.service, &self.protocol) {
+            (LinodeRecordType::Srv, Some(service), Some(protocol)) => {
+                let service = service.trim_start_matches('_').trim_end_matches('.');
+                let protocol = protocol.trim_start_matches('_').trim_end_matches('.');
+                let prefix = format!("_{service}._{protocol}");
+                if self.name.is_empty() {
+                    prefix
+                } else if self.name.starts_with(&prefix) {
+                    // Linode may or may not include the prefix in the name.
+                    self.name.clone()
+                } else {
+                    format!("{prefix}.{}", self.name)
+                }
+            }
+            _ => self.name.clone(),
+        }
+    }
+
+    /// Returns the priority, weight, port, and (lowercase) target of a SRV record.
+    fn srv(&self) -> Option<(u16, u16, u16, String)> {
+        (self.record_type == LinodeRecordType::Srv).then(|| {
+            (
+                self.priority.unwrap_or_default(),
+                self.weight.unwrap_or_default(),
+                self.port.unwrap_or_default(),
+                self.target.trim_end_matches('.').to_ascii_lowercase(),
+            )
+        })
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -502,12 +904,18 @@ enum LinodeRecordType {
     Srv,
     Caa,
     Ptr,
+    /// A record type this crate doesn't know about (yet).
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 #[derive(Debug, Deserialize)]
 struct LinodeDomainResponse {
     id: usize,
     domain: String,
+    /// Zero means the default.
+    #[serde(default)]
+    refresh_sec: u32,
 }
 
 #[derive(Debug, Deserialize)]
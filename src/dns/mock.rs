@@ -0,0 +1,189 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{CloudDns, DnsRecord, DnsRecordSet, DnsUpdateOutcome, ZoneInfo};
+use crate::common::Error;
+use async_trait::async_trait;
+use hyper::StatusCode;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+/// This struct implements `CloudDns` in memory, so DNS orchestration logic can be
+/// tested without network access.
+#[derive(Default)]
+pub struct MockDns {
+    /// Records of each domain (zone).
+    domains: Mutex<HashMap<String, HashSet<(String, DnsRecord)>>>,
+    /// Errors returned by the next calls, in order.
+    errors: Mutex<VecDeque<Error>>,
+    /// Zone info of each domain (zone), if not the default.
+    zone_infos: Mutex<HashMap<String, ZoneInfo>>,
+}
+
+impl MockDns {
+    /// Create a mock with no domains.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Cause the next call to any `CloudDns` method to fail with the specified error.
+    /// If called more than once, the errors are returned by successive calls.
+    pub fn inject_error(&self, error: Error) {
+        self.errors.lock().unwrap().push_back(error);
+    }
+
+    /// Add (or replace) a domain (zone) with the specified records.
+    pub fn with_records(self, domain: &str, record_set: DnsRecordSet) -> Self {
+        self.domains
+            .lock()
+            .unwrap()
+            .insert(domain.to_string(), record_set.0);
+        self
+    }
+
+    /// Set the zone info of a domain (zone), which otherwise has the default.
+    pub fn with_zone_info(self, domain: &str, zone_info: ZoneInfo) -> Self {
+        self.zone_infos
+            .lock()
+            .unwrap()
+            .insert(domain.to_string(), zone_info);
+        self
+    }
+
+    fn take_error(&self) -> Result<(), Error> {
+        match self.errors.lock().unwrap().pop_front() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Replace the records of `hostname` that `matches` with `values`, if any.
+    fn update(
+        &self,
+        domain: &str,
+        hostname: &str,
+        values: impl IntoIterator<Item = DnsRecord>,
+        matches: fn(&DnsRecord) -> bool,
+    ) -> Result<DnsUpdateOutcome, Error> {
+        self.take_error()?;
+        let mut domains = self.domains.lock().unwrap();
+        let records = domains.get_mut(domain).ok_or_else(|| {
+            Error::Http(
+                StatusCode::NOT_FOUND,
+                format!("Could not find domain {domain}"),
+            )
+        })?;
+        let before = records.clone();
+        records.retain(|(h, record)| !h.eq_ignore_ascii_case(hostname) || !matches(record));
+        let values: Vec<_> = values.into_iter().collect();
+        let log = format!("{hostname}.{domain} = {values:?}");
+        for value in values {
+            records.insert((hostname.to_ascii_lowercase(), value));
+        }
+        Ok(DnsUpdateOutcome {
+            changed: *records != before,
+            log,
+        })
+    }
+}
+
+#[async_trait]
+impl CloudDns for MockDns {
+    async fn read_dns_records(&self, domain: &str) -> Result<DnsRecordSet, Error> {
+        self.take_error()?;
+        self.domains
+            .lock()
+            .unwrap()
+            .get(domain)
+            .map(|records| DnsRecordSet(records.clone()))
+            .ok_or_else(|| {
+                Error::Http(
+                    StatusCode::NOT_FOUND,
+                    format!("Could not find domain {domain}"),
+                )
+            })
+    }
+
+    async fn read_zone_info(&self, domain: &str) -> Result<ZoneInfo, Error> {
+        self.take_error()?;
+        if !self.domains.lock().unwrap().contains_key(domain) {
+            return Err(Error::Http(
+                StatusCode::NOT_FOUND,
+                format!("Could not find domain {domain}"),
+            ));
+        }
+        Ok(self
+            .zone_infos
+            .lock()
+            .unwrap()
+            .get(domain)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn update_dns_caa(
+        &self,
+        domain: &str,
+        hostname: &str,
+        values: Vec<DnsRecord>,
+        _ttl: Option<usize>,
+    ) -> Result<DnsUpdateOutcome, Error> {
+        let values = values
+            .into_iter()
+            .filter(|value| matches!(value, DnsRecord::Caa { .. }));
+        self.update(domain, hostname, values, |record| {
+            matches!(record, DnsRecord::Caa { .. })
+        })
+    }
+
+    async fn update_dns_metadata(
+        &self,
+        domain: &str,
+        hostname: &str,
+        value: DnsRecord,
+        _ttl: Option<usize>,
+    ) -> Result<DnsUpdateOutcome, Error> {
+        let is_metadata =
+            |record: &DnsRecord| matches!(record, DnsRecord::Srv { .. } | DnsRecord::Txt(_));
+        match value {
+            DnsRecord::Srv { .. } => self.update(domain, hostname, Some(value), |record| {
+                matches!(record, DnsRecord::Srv { .. })
+            }),
+            DnsRecord::Txt(_) => self.update(domain, hostname, Some(value), |record| {
+                matches!(record, DnsRecord::Txt(_))
+            }),
+            // A host may have several CAA records, so add rather than replace.
+            DnsRecord::Caa { .. } => self.update(domain, hostname, Some(value), |_| false),
+            DnsRecord::None => self.update(domain, hostname, None, is_metadata),
+            _ => Ok(DnsUpdateOutcome {
+                changed: false,
+                log: "non-metadata record ignored".to_string(),
+            }),
+        }
+    }
+
+    async fn update_dns_route(
+        &self,
+        domain: &str,
+        hostname: &str,
+        value: DnsRecord,
+        _ttl: Option<usize>,
+    ) -> Result<DnsUpdateOutcome, Error> {
+        let is_route = |record: &DnsRecord| {
+            matches!(
+                record,
+                DnsRecord::A(_) | DnsRecord::Alias { .. } | DnsRecord::Cname(_)
+            )
+        };
+        match value {
+            DnsRecord::A(_) | DnsRecord::Alias { .. } | DnsRecord::Cname(_) => {
+                self.update(domain, hostname, Some(value), is_route)
+            }
+            DnsRecord::None => self.update(domain, hostname, None, is_route),
+            _ => Ok(DnsUpdateOutcome {
+                changed: false,
+                log: "non route record ignored".to_string(),
+            }),
+        }
+    }
+}
@@ -7,9 +7,15 @@ mod aws;
 mod cloud_dns;
 /// Support for Linode (aka Akami)
 mod linode;
+/// In-memory DNS for testing.
+mod mock;
 /// Unit tests
 mod tests;
 
 pub use self::aws::AwsDns;
-pub use self::cloud_dns::{CloudDns, CloudDnsClient, DnsRecord, DnsRecordSet, DnsRecordSetBuilder};
+pub use self::cloud_dns::{
+    CloudDns, CloudDnsClient, DnsChanges, DnsRecord, DnsRecordSet, DnsRecordSetBuilder,
+    DnsUpdateOutcome, ZoneInfo,
+};
 pub use self::linode::LinodeDns;
+pub use self::mock::MockDns;
@@ -4,9 +4,13 @@
 #[cfg(test)]
 mod dns_tests {
     use crate::common::CubConfig;
+    use crate::common::Error;
+    use crate::datacenter::CloudDatacenter;
     use crate::dns::cloud_dns::{CloudDns, CloudDnsClient};
-    use crate::dns::{AwsDns, DnsRecord, DnsRecordSet};
+    use crate::dns::{AwsDns, DnsChanges, DnsRecord, DnsRecordSet, MockDns, ZoneInfo};
+    use hyper::StatusCode;
     use std::net::IpAddr;
+    use std::sync::Arc;
 
     const AWS_DOMAIN: &str = "mazean.com";
     const LINODE_DOMAIN: &str = "zentakil.com";
@@ -26,6 +30,482 @@ mod dns_tests {
             .expect("dns_test.toml")
     }
 
+    #[cfg(feature = "bitcode")]
+    #[test]
+    fn dns_bitcode_tests() {
+        let ip_addr: IpAddr = "10.0.0.1".parse().unwrap();
+        let record_set = DnsRecordSet::builder()
+            .cname("www", "example.com")
+            .txt("meta", "hello")
+            .ag(
+                "game",
+                [(
+                    ip_addr,
+                    Some(CloudDatacenter::from_linode_region("us-east")),
+                )]
+                .into(),
+            )
+            .build();
+        let encoded = bitcode::encode(&record_set);
+        let decoded: DnsRecordSet = bitcode::decode(&encoded).expect("decode");
+        assert_eq!(decoded.routes(), record_set.routes());
+        assert_eq!(decoded.metadata(), record_set.metadata());
+        println!(
+            "{} records encoded in {} bytes",
+            record_set.0.len(),
+            encoded.len()
+        );
+    }
+
+    #[test]
+    fn dns_diff_tests() {
+        let ip1: IpAddr = "10.0.0.1".parse().unwrap();
+        let ip2: IpAddr = "10.0.0.2".parse().unwrap();
+        let current = DnsRecordSet::builder()
+            .a("game", [ip1].into())
+            .cname("www", "example.org")
+            .txt("meta", "hello")
+            .txt("old", "bye")
+            .build();
+        let desired = DnsRecordSet::builder()
+            .a("game", [ip1, ip2].into())
+            .cname("www", "example.org")
+            .txt("meta", "hello")
+            .txt("www", "new")
+            .build();
+        assert_eq!(
+            desired.diff(&current),
+            DnsChanges {
+                adds: vec![("www".to_string(), DnsRecord::Txt("new".to_string()))],
+                updates: vec![(
+                    "game".to_string(),
+                    DnsRecord::A([(ip1, None), (ip2, None)].into())
+                )],
+                deletes: vec![("old".to_string(), DnsRecord::Txt("bye".to_string()))],
+            }
+        );
+        assert!(current.diff(&current).is_empty());
+    }
+
+    #[tokio::test]
+    async fn dns_alias_tests() {
+        let domain = "example.com";
+        let alias = DnsRecord::Alias {
+            target: "d111111abcdef8.cloudfront.net".to_string(),
+            hosted_zone_id: "Z2FDTNDATAQYW2".to_string(),
+        };
+        let record_set = DnsRecordSet::builder()
+            .alias("", "d111111abcdef8.cloudfront.net", "Z2FDTNDATAQYW2")
+            .txt("", "verification")
+            .build();
+        assert_eq!(record_set.routes().get(""), Some(&alias));
+
+        let mock_dns = MockDns::new().with_records(
+            domain,
+            DnsRecordSet::builder().cname("www", "example.org").build(),
+        );
+        mock_dns
+            .update_dns_route(domain, "www", alias.clone(), None)
+            .await
+            .expect("update route");
+        let records = mock_dns.read_dns_records(domain).await.expect("read");
+        assert_eq!(records.routes().get("www"), Some(&alias));
+        assert_eq!(records.routes().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn dns_hostname_tests() {
+        let domain = "example.com";
+        let wildcard = DnsRecordSet::wildcard("Api");
+        assert_eq!(wildcard, "*.api");
+        assert_eq!(DnsRecordSet::wildcard(""), "*");
+        let record_set = DnsRecordSet::builder()
+            .cname(&wildcard, "example.org")
+            .txt("MiXeD", "hello")
+            .build();
+        assert!(record_set.routes().contains_key("*.api"));
+        assert!(record_set.metadata().contains_key("mixed"));
+
+        let mock_dns = MockDns::new().with_records(domain, record_set);
+        mock_dns
+            .update_dns_metadata(domain, "MIXED", DnsRecord::Txt("bye".to_string()), None)
+            .await
+            .expect("update metadata");
+        let records = mock_dns.read_dns_records(domain).await.expect("read");
+        assert_eq!(records.metadata().len(), 1);
+        assert_eq!(
+            records.metadata().get("mixed"),
+            Some(&DnsRecord::Txt("bye".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_dns_tests() {
+        let domain = "example.com";
+        let ip_addr: IpAddr = "10.0.0.1".parse().unwrap();
+        let mock_dns = MockDns::new().with_records(
+            domain,
+            DnsRecordSet::builder()
+                .cname("www", "example.org")
+                .txt("www", "hello")
+                .build(),
+        );
+
+        mock_dns
+            .update_dns_route(domain, "www", DnsRecord::new_a(ip_addr), None)
+            .await
+            .expect("update route");
+        mock_dns
+            .update_dns_metadata(domain, "meta", DnsRecord::Txt("hi".to_string()), None)
+            .await
+            .expect("update metadata");
+        let records = mock_dns.read_dns_records(domain).await.expect("read");
+        assert_eq!(
+            records.routes().get("www"),
+            Some(&DnsRecord::new_a(ip_addr))
+        );
+        assert_eq!(
+            records.metadata().get("www"),
+            Some(&DnsRecord::Txt("hello".to_string()))
+        );
+        assert_eq!(
+            records.metadata().get("meta"),
+            Some(&DnsRecord::Txt("hi".to_string()))
+        );
+
+        mock_dns
+            .update_dns_route(domain, "www", DnsRecord::None, None)
+            .await
+            .expect("remove route");
+        let records = mock_dns.read_dns_records(domain).await.expect("read");
+        assert!(records.routes().is_empty());
+        assert_eq!(records.metadata().len(), 2);
+
+        assert!(matches!(
+            mock_dns.read_dns_records("example.net").await,
+            Err(Error::Http(StatusCode::NOT_FOUND, _))
+        ));
+        mock_dns.inject_error(Error::String("injected".to_string()));
+        assert!(matches!(
+            mock_dns.read_dns_records(domain).await,
+            Err(Error::String(_))
+        ));
+        assert!(mock_dns.read_dns_records(domain).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn update_dns_records_tests() {
+        let domain = "example.com";
+        let ip_addr: IpAddr = "10.0.0.1".parse().unwrap();
+        let mock_dns = MockDns::new().with_records(
+            domain,
+            DnsRecordSet::builder()
+                .cname("www", "example.org")
+                .txt("meta", "hello")
+                .build(),
+        );
+        let desired = || {
+            DnsRecordSet::builder()
+                .cname("www", "example.org")
+                .txt("meta", "goodbye")
+                .a("game", [ip_addr].into())
+                .build()
+        };
+        mock_dns
+            .update_dns_records(domain, desired())
+            .await
+            .expect("update records");
+        let records = mock_dns.read_dns_records(domain).await.expect("read");
+        assert_eq!(records.routes(), desired().routes());
+        assert_eq!(records.metadata(), desired().metadata());
+    }
+
+    #[tokio::test]
+    async fn dns_update_outcome_tests() {
+        let domain = "example.com";
+        let mock_dns = MockDns::new().with_records(domain, DnsRecordSet::default());
+        let txt = || DnsRecord::Txt("hello".to_string());
+
+        let first = mock_dns
+            .update_dns_metadata(domain, "meta", txt(), None)
+            .await
+            .expect("first update");
+        assert!(first.changed);
+        assert!(first.log.contains("meta.example.com"));
+        let second = mock_dns
+            .update_dns_metadata(domain, "meta", txt(), None)
+            .await
+            .expect("second update");
+        assert!(!second.changed);
+
+        let desired = || DnsRecordSet::builder().cname("www", "example.org").build();
+        let outcome = mock_dns
+            .update_dns_records(domain, desired())
+            .await
+            .expect("update records");
+        assert!(outcome.changed);
+        let outcome = mock_dns
+            .update_dns_records(domain, desired())
+            .await
+            .expect("update records again");
+        assert!(!outcome.changed);
+        assert!(outcome.log.is_empty());
+        assert_eq!(outcome.to_string(), outcome.log);
+    }
+
+    #[tokio::test]
+    async fn caa_tests() {
+        let domain = "example.com";
+        let letsencrypt = DnsRecord::Caa {
+            flags: 0,
+            tag: "issue".to_string(),
+            value: "letsencrypt.org".to_string(),
+        };
+        let mock_dns = MockDns::new().with_records(
+            domain,
+            DnsRecordSet::builder()
+                .txt("", "hello")
+                .caa("", 0, "issue", "letsencrypt.org")
+                .build(),
+        );
+        let desired = || {
+            DnsRecordSet::builder()
+                .txt("", "hello")
+                .caa("", 0, "issue", "letsencrypt.org")
+                .caa("", 0, "issuewild", ";")
+                .build()
+        };
+
+        let current = mock_dns.read_dns_records(domain).await.expect("read");
+        assert_eq!(current.caa(), vec![("".to_string(), letsencrypt.clone())]);
+        assert_eq!(current.metadata().len(), 1);
+        let changes = desired().diff(&current);
+        assert_eq!(changes.adds.len(), 1);
+        assert!(changes.updates.is_empty());
+        assert!(changes.deletes.is_empty());
+        assert!(current.diff(&desired()).deletes.len() == 1);
+
+        mock_dns
+            .update_dns_records(domain, desired())
+            .await
+            .expect("update records");
+        let records = mock_dns.read_dns_records(domain).await.expect("read");
+        assert_eq!(records.caa(), desired().caa());
+        assert_eq!(records.metadata(), desired().metadata());
+        assert_eq!(records.caa()[0].1, letsencrypt);
+        assert_eq!(letsencrypt.record_type(), "CAA");
+    }
+
+    #[tokio::test]
+    async fn caa_replace_tests() {
+        let domain = "example.com";
+        let mock_dns = MockDns::new().with_records(
+            domain,
+            DnsRecordSet::builder()
+                .txt("", "hello")
+                .caa("", 0, "issue", "letsencrypt.org")
+                .caa("", 0, "issuewild", ";")
+                .build(),
+        );
+        let desired = || {
+            DnsRecordSet::builder()
+                .caa("", 0, "issue", "sectigo.com")
+                .caa("", 0, "issuewild", ";")
+                .build()
+        };
+
+        let outcome = mock_dns
+            .update_dns_records(domain, desired())
+            .await
+            .expect("update records");
+        assert!(outcome.changed);
+        let records = mock_dns.read_dns_records(domain).await.expect("read");
+        assert_eq!(records.caa(), desired().caa());
+        assert_eq!(records.metadata().len(), 1);
+        let outcome = mock_dns
+            .update_dns_records(domain, desired())
+            .await
+            .expect("update records again");
+        assert!(!outcome.changed);
+
+        // Revoking one authority is a change even though nothing is added.
+        let revoked = || DnsRecordSet::builder().caa("", 0, "issuewild", ";").build();
+        assert!(
+            mock_dns
+                .update_dns_records(domain, revoked())
+                .await
+                .expect("revoke")
+                .changed
+        );
+        let records = mock_dns.read_dns_records(domain).await.expect("read");
+        assert_eq!(records.caa(), revoked().caa());
+
+        mock_dns
+            .update_dns_caa(domain, "", Vec::new(), None)
+            .await
+            .expect("remove all");
+        let records = mock_dns.read_dns_records(domain).await.expect("read");
+        assert!(records.caa().is_empty());
+        assert_eq!(records.metadata().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn srv_tests() {
+        let domain = "example.com";
+        let minecraft = DnsRecord::Srv {
+            priority: 0,
+            weight: 5,
+            port: 25565,
+            target: "mc.example.com".to_string(),
+        };
+        let mock_dns = MockDns::new().with_records(
+            domain,
+            DnsRecordSet::builder()
+                .txt("_minecraft._tcp", "hello")
+                .srv("_minecraft._tcp", 0, 5, 25565, "old.example.com")
+                .build(),
+        );
+        let desired = || {
+            DnsRecordSet::builder()
+                .txt("_minecraft._tcp", "hello")
+                .srv("_Minecraft._tcp", 0, 5, 25565, "mc.example.com")
+                .build()
+        };
+        assert_eq!(minecraft.record_type(), "SRV");
+        assert_eq!(
+            desired().srv(),
+            [("_minecraft._tcp".to_string(), minecraft.clone())].into()
+        );
+        assert_eq!(desired().metadata().len(), 1);
+
+        let current = mock_dns.read_dns_records(domain).await.expect("read");
+        let changes = desired().diff(&current);
+        assert!(changes.adds.is_empty());
+        assert_eq!(
+            changes.updates,
+            vec![("_minecraft._tcp".to_string(), minecraft.clone())]
+        );
+        assert!(changes.deletes.is_empty());
+
+        let outcome = mock_dns
+            .update_dns_records(domain, desired())
+            .await
+            .expect("update records");
+        assert!(outcome.changed);
+        let records = mock_dns.read_dns_records(domain).await.expect("read");
+        assert_eq!(records.srv(), desired().srv());
+        assert_eq!(records.metadata(), desired().metadata());
+
+        mock_dns
+            .update_dns_metadata(domain, "_minecraft._tcp", DnsRecord::None, None)
+            .await
+            .expect("clear metadata");
+        let records = mock_dns.read_dns_records(domain).await.expect("read");
+        assert!(records.srv().is_empty());
+        assert!(records.metadata().is_empty());
+    }
+
+    #[tokio::test]
+    async fn zone_info_tests() {
+        let domain = "example.com";
+        let zone_info = ZoneInfo {
+            nameservers: vec!["ns1.example.net".to_string(), "ns2.example.net".to_string()],
+            soa_serial: Some(2024010101),
+            soa_refresh_secs: 7200,
+        };
+        let mock_dns = MockDns::new()
+            .with_records(domain, DnsRecordSet::default())
+            .with_records("example.org", DnsRecordSet::default())
+            .with_zone_info(domain, zone_info.clone());
+        assert_eq!(
+            mock_dns.read_zone_info(domain).await.expect("zone info"),
+            zone_info
+        );
+        assert_eq!(
+            mock_dns
+                .read_zone_info("example.org")
+                .await
+                .expect("default"),
+            ZoneInfo::default()
+        );
+        assert!(matches!(
+            mock_dns.read_zone_info("example.net").await,
+            Err(Error::Http(StatusCode::NOT_FOUND, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn update_all_tests() {
+        let domain = "example.com";
+        let empty = || DnsRecordSet::builder().build();
+        let desired = DnsRecordSet::builder()
+            .cname("www", "example.org")
+            .txt("meta", "hello")
+            .build();
+        let aws = Arc::new(MockDns::new().with_records(domain, empty()));
+        let linode = Arc::new(MockDns::new().with_records(domain, empty()));
+        let client = CloudDnsClient::with_providers(Some(aws.clone()), Some(linode.clone()));
+        let outcome = client
+            .update_all(domain, desired.clone())
+            .await
+            .expect("update all");
+        assert!(outcome.changed);
+        assert!(outcome.log.contains("aws update"));
+        assert!(outcome.log.contains("linode update"));
+        for api in [&aws, &linode] {
+            let records = api.read_dns_records(domain).await.expect("read");
+            assert_eq!(records.routes(), desired.routes());
+            assert_eq!(records.metadata(), desired.metadata());
+        }
+
+        linode.inject_error(Error::String("linode is down".to_string()));
+        let Err(Error::String(log)) = client.update_all(domain, desired).await else {
+            panic!("expected linode failure");
+        };
+        assert!(log.contains("aws update of example.com succeeded"));
+        assert!(log.contains("linode update of example.com failed"));
+    }
+
+    #[tokio::test]
+    async fn unconfigured_provider_tests() {
+        let linode_only = CubConfig::builder()
+            .toml_str("[linode]\npersonal_access_token = \"TBD\"\n")
+            .build()
+            .expect("unconfigured_provider_tests.toml");
+        assert!(linode_only.has_section("linode"));
+        assert!(!linode_only.has_section("aws"));
+        let client = CloudDnsClient::new(&linode_only).await;
+        assert!(client.nameserver_api(Some("linode")).await.is_ok());
+        assert!(client.nameserver_api(None).await.is_ok());
+        assert!(matches!(
+            client.nameserver_api(Some("aws")).await,
+            Err(Error::String(_))
+        ));
+        assert!(client.nameserver_api(Some("bind")).await.is_err());
+
+        let client = CloudDnsClient::with_providers(None, None);
+        assert!(client.nameserver_api(None).await.is_err());
+        assert!(client
+            .update_all("example.com", DnsRecordSet::default())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn aws_dns_config_tests() {
+        // A malformed TTL means the default TTL, rather than a panic.
+        let cub_config = CubConfig::builder()
+            .toml_str(
+                r#"
+            [aws]
+            dns_ttl_secs = "soon"
+            "#,
+            )
+            .build()
+            .expect("dns_test.toml");
+        let _aws_dns = AwsDns::new(&cub_config).await;
+    }
+
     #[tokio::test]
     #[should_panic]
     async fn aws_dns_read_tests() {
@@ -49,7 +529,7 @@ mod dns_tests {
         let hostname1 = "test12345";
         let data1 = "Foo9876".to_string();
         match aws_dns
-            .update_dns_metadata(AWS_DOMAIN, &hostname1, DnsRecord::Txt(data1), None)
+            .update_dns_metadata(AWS_DOMAIN, hostname1, DnsRecord::Txt(data1), None)
             .await
         {
             Ok(result) => println!("Updated meta data: {result}"),
@@ -59,7 +539,7 @@ mod dns_tests {
         let hostname2 = "test12346";
         let data2 = "foo.softbear.com".to_string();
         match aws_dns
-            .update_dns_route(AWS_DOMAIN, &hostname2, DnsRecord::Cname(data2), None)
+            .update_dns_route(AWS_DOMAIN, hostname2, DnsRecord::Cname(data2), None)
             .await
         {
             Ok(result) => println!("Updated route: {result}"),
@@ -69,7 +549,7 @@ mod dns_tests {
         let hostname3 = "test12347";
         let ip_addr: IpAddr = "127.0.0.1".parse().expect("invalid IP addr");
         match aws_dns
-            .update_dns_route(AWS_DOMAIN, &hostname3, DnsRecord::new_a(ip_addr), None)
+            .update_dns_route(AWS_DOMAIN, hostname3, DnsRecord::new_a(ip_addr), None)
             .await
         {
             Ok(result) => println!("Updated route: {result}"),
@@ -86,6 +566,7 @@ mod dns_tests {
         let _records = match cloud_dns
             .nameserver_api(Some("aws"))
             .await
+            .expect("nameserver API")
             .read_dns_records(domain)
             .await
         {
@@ -102,7 +583,8 @@ mod dns_tests {
         match cloud_dns
             .nameserver_api(Some("aws"))
             .await
-            .update_dns_metadata(domain, &hostname1, DnsRecord::Txt(data1), None)
+            .expect("nameserver API")
+            .update_dns_metadata(domain, hostname1, DnsRecord::Txt(data1), None)
             .await
         {
             Ok(result) => println!("Updated meta data: {result}"),
@@ -114,7 +596,8 @@ mod dns_tests {
         match cloud_dns
             .nameserver_api(Some("aws"))
             .await
-            .update_dns_route(domain, &hostname2, DnsRecord::Cname(data2), None)
+            .expect("nameserver API")
+            .update_dns_route(domain, hostname2, DnsRecord::Cname(data2), None)
             .await
         {
             Ok(result) => println!("Updated route: {result}"),
@@ -126,7 +609,8 @@ mod dns_tests {
         match cloud_dns
             .nameserver_api(Some("aws"))
             .await
-            .update_dns_route(domain, &hostname3, DnsRecord::new_a(ip_addr), None)
+            .expect("nameserver API")
+            .update_dns_route(domain, hostname3, DnsRecord::new_a(ip_addr), None)
             .await
         {
             Ok(result) => println!("Updated route: {result}"),
@@ -143,6 +627,7 @@ mod dns_tests {
         let _records = match linode_dns
             .nameserver_api(Some("linode"))
             .await
+            .expect("nameserver API")
             .read_dns_records(domain)
             .await
         {
@@ -159,7 +644,8 @@ mod dns_tests {
         match linode_dns
             .nameserver_api(Some("linode"))
             .await
-            .update_dns_metadata(domain, &hostname1, DnsRecord::Txt(data1), None)
+            .expect("nameserver API")
+            .update_dns_metadata(domain, hostname1, DnsRecord::Txt(data1), None)
             .await
         {
             Ok(result) => println!("Updated meta data: {result}"),
@@ -171,7 +657,8 @@ mod dns_tests {
         match linode_dns
             .nameserver_api(Some("linode"))
             .await
-            .update_dns_route(domain, &hostname2, DnsRecord::Cname(data2), None)
+            .expect("nameserver API")
+            .update_dns_route(domain, hostname2, DnsRecord::Cname(data2), None)
             .await
         {
             Ok(result) => println!("Updated route: {result}"),
@@ -183,7 +670,8 @@ mod dns_tests {
         match linode_dns
             .nameserver_api(Some("linode"))
             .await
-            .update_dns_route(domain, &hostname3, DnsRecord::new_a(ip_addr), None)
+            .expect("nameserver API")
+            .update_dns_route(domain, hostname3, DnsRecord::new_a(ip_addr), None)
             .await
         {
             Ok(result) => println!("Updated route: {result}"),
@@ -2,11 +2,14 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 use super::LinodeHosts;
-use crate::common::{CubConfig, Error};
+use crate::common::{strip_provider_prefix, CubConfig, Error};
 use crate::datacenter::CloudDatacenter;
+use crate::NonZeroUnixSeconds;
 use async_trait::async_trait;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
 use std::net::IpAddr;
 use std::sync::Arc;
 
@@ -15,13 +18,189 @@ use std::sync::Arc;
 pub struct HostResourceId(pub String);
 crate::impl_wrapper_str!(HostResourceId);
 
+impl HostResourceId {
+    /// Returns the ID without the expected provider prefix, e.g. "123" for "linode/123".
+    pub fn strip_provider_prefix(&self, provider: &str) -> Result<&str, Error> {
+        strip_provider_prefix(&self.0, provider)
+    }
+}
+
+/// A newly allocated virtual host.
+#[derive(Clone)]
+pub struct CreatedHost {
+    /// The host's resource ID.
+    pub id: HostResourceId,
+    /// The host's public IP address.
+    pub ip_addr: IpAddr,
+    /// The host's root password, if the provider sets one.
+    pub root_password: Option<String>,
+}
+
+impl Debug for CreatedHost {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CreatedHost")
+            .field("id", &self.id)
+            .field("ip_addr", &self.ip_addr)
+            .field("root_password", &self.root_password.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+/// An existing virtual host, as returned by `CloudHosts::list_hosts_detailed`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HostInfo {
+    /// The host's resource ID.
+    pub id: HostResourceId,
+    /// The host's public IP address.
+    pub ip_addr: IpAddr,
+    /// The host's label, if any.
+    pub label: Option<String>,
+    /// When the host was created, if known.
+    pub created: Option<NonZeroUnixSeconds>,
+    /// The host's datacenter (region), if known.
+    pub datacenter: Option<CloudDatacenter>,
+}
+
 /// Host parameters.
+///
+/// Recognized keys (which providers ignore if they don't support them):
+/// - `firewall_name`: Linode firewall to attach to the host, which must be a key of
+///   `firewall_ids` in the `[linode]` config (otherwise the "default" firewall, if any).
+/// - `root_password`: Linode root password (otherwise one is randomly generated).
+///
+/// Any parameter may also be referenced in the script as `{{key}}` (see `substitute`).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct HostParameters(pub HashMap<String, String>);
 
+impl HostParameters {
+    /// Key of the firewall name parameter.
+    pub const FIREWALL_NAME: &'static str = "firewall_name";
+    /// Key of the root password parameter.
+    pub const ROOT_PASSWORD: &'static str = "root_password";
+
+    /// Creates a host parameters builder.
+    pub fn builder() -> HostParametersBuilder {
+        HostParametersBuilder::default()
+    }
+
+    /// Returns the name of the firewall to attach to the host, if any.
+    pub fn firewall_name(&self) -> Option<&str> {
+        self.get(Self::FIREWALL_NAME)
+    }
+
+    /// Returns the root password to set on the host, if any.
+    pub fn root_password(&self) -> Option<&str> {
+        self.get(Self::ROOT_PASSWORD)
+    }
+
+    /// Returns the value of the parameter with the specified key, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(|v| v.as_str())
+    }
+
+    /// Replaces `{{hostname}}` in `script` with `hostname` and any other `{{key}}` with
+    /// the value of that parameter.  Unknown keys are left as-is, and substituted values
+    /// are not themselves searched for keys.
+    pub fn substitute(&self, script: &str, hostname: &str) -> String {
+        let mut output = String::with_capacity(script.len());
+        let mut rest = script;
+        while let Some(start) = rest.find("{{") {
+            output.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let value = rest[2..].find("}}").and_then(|end| {
+                let key = rest[2..2 + end].trim();
+                let value = if key == "hostname" {
+                    Some(hostname)
+                } else {
+                    self.get(key)
+                };
+                value.map(|value| (end, value))
+            });
+            match value {
+                Some((end, value)) => {
+                    output.push_str(value);
+                    rest = &rest[end + 4..];
+                }
+                None => {
+                    output.push_str("{{");
+                    rest = &rest[2..];
+                }
+            }
+        }
+        output.push_str(rest);
+        output
+    }
+}
+
+/// Host parameters builder.
+#[derive(Default)]
+pub struct HostParametersBuilder {
+    parameters: HashMap<String, String>,
+}
+
+impl HostParametersBuilder {
+    /// Returns the host parameters.
+    pub fn build(self) -> HostParameters {
+        HostParameters(self.parameters)
+    }
+
+    /// Sets the name of the firewall to attach to the host.
+    pub fn firewall(self, firewall_name: &str) -> Self {
+        self.parameter(HostParameters::FIREWALL_NAME, firewall_name)
+    }
+
+    /// Sets the root password of the host.
+    pub fn root_password(self, root_password: &str) -> Self {
+        self.parameter(HostParameters::ROOT_PASSWORD, root_password)
+    }
+
+    /// Sets a parameter which has no typed setter.
+    pub fn parameter(mut self, key: &str, value: &str) -> Self {
+        self.parameters.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+/// Specification of a virtual host to allocate with `CloudHosts::create_hosts`.  The
+/// fields are the arguments of `CloudHosts::create_host`.
+#[derive(Clone, Debug)]
+pub struct HostSpec {
+    /// The host's label.
+    pub label: String,
+    /// The group (tag) of the host, if any.
+    pub group: Option<String>,
+    /// The host's hostname, which is substituted for `{{hostname}}` in the script.
+    pub hostname: String,
+    /// Where to allocate the host.
+    pub datacenter: CloudDatacenter,
+    /// The script to run when the host boots for the first time.
+    pub script: String,
+    /// Additional parameters, if any.
+    pub parameters: Option<HostParameters>,
+}
+
+impl HostSpec {
+    /// Creates a host spec without a group or parameters.
+    pub fn new(label: &str, hostname: &str, datacenter: CloudDatacenter, script: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            group: None,
+            hostname: hostname.to_string(),
+            datacenter,
+            script: script.to_string(),
+            parameters: None,
+        }
+    }
+}
+
+/// The maximum number of hosts that `CloudHosts::create_hosts` allocates at a time.
+pub(crate) const CREATE_HOSTS_CONCURRENCY: usize = 4;
+
 /// Cloud hosts
 #[async_trait]
 pub trait CloudHosts {
-    /// Allocate a new virtual host.
+    /// Allocate a new virtual host.  The script is first templated with the hostname and
+    /// parameters (see `HostParameters::substitute`).
     async fn create_host(
         &self,
         label: &str,
@@ -30,7 +209,31 @@ pub trait CloudHosts {
         datacenter: CloudDatacenter,
         script: &str,
         parameters: Option<HostParameters>,
-    ) -> Result<(HostResourceId, IpAddr), Error>;
+    ) -> Result<CreatedHost, Error>;
+
+    /// Allocate several virtual hosts concurrently, a few at a time.  Returns the result
+    /// for each spec, in order, so some hosts may have been allocated even if others
+    /// failed.
+    async fn create_hosts(&self, specs: Vec<HostSpec>) -> Vec<Result<CreatedHost, Error>>
+    where
+        Self: Sync,
+    {
+        futures::stream::iter(specs)
+            .map(|spec| async move {
+                self.create_host(
+                    &spec.label,
+                    spec.group.as_deref(),
+                    &spec.hostname,
+                    spec.datacenter,
+                    &spec.script,
+                    spec.parameters,
+                )
+                .await
+            })
+            .buffered(CREATE_HOSTS_CONCURRENCY)
+            .collect()
+            .await
+    }
 
     /// Delete virtual host.
     async fn delete_host(&self, id: &HostResourceId) -> Result<(), Error>;
@@ -39,7 +242,17 @@ pub trait CloudHosts {
     async fn list_datacenters(&self) -> Result<Vec<CloudDatacenter>, Error>;
 
     /// List virtual hosts.
-    async fn list_hosts(&self) -> Result<Vec<(HostResourceId, IpAddr, Option<String>)>, Error>;
+    async fn list_hosts(&self) -> Result<Vec<(HostResourceId, IpAddr, Option<String>)>, Error> {
+        Ok(self
+            .list_hosts_detailed()
+            .await?
+            .into_iter()
+            .map(|host| (host.id, host.ip_addr, host.label))
+            .collect())
+    }
+
+    /// List virtual hosts, including when and where they were created.
+    async fn list_hosts_detailed(&self) -> Result<Vec<HostInfo>, Error>;
 
     /// Provider name.  For example, "linode".
     fn provider_name(&self) -> &'static str;
@@ -68,12 +281,17 @@ impl CloudHostsClient {
         datacenter: CloudDatacenter,
         script: &str,
         parameters: Option<HostParameters>,
-    ) -> Result<(HostResourceId, IpAddr), Error> {
+    ) -> Result<CreatedHost, Error> {
         self.linode
             .create_host(label, group, hostname, datacenter, script, parameters)
             .await
     }
 
+    /// Allocate several virtual hosts concurrently.
+    pub async fn create_hosts(&self, specs: Vec<HostSpec>) -> Vec<Result<CreatedHost, Error>> {
+        self.linode.create_hosts(specs).await
+    }
+
     /// Delete virtual host.
     pub async fn delete_host(&self, id: &HostResourceId) -> Result<(), Error> {
         self.linode.delete_host(id).await
@@ -89,6 +307,11 @@ impl CloudHostsClient {
         self.linode.list_hosts().await
     }
 
+    /// List hosts, including when and where they were created.
+    pub async fn list_hosts_detailed(&self) -> Result<Vec<HostInfo>, Error> {
+        self.linode.list_hosts_detailed().await
+    }
+
     /// Choose which provider to use.
     pub async fn provider_name(
         &self,
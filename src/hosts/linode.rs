@@ -1,20 +1,25 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::{CloudHosts, HostParameters, HostResourceId};
+use super::cloud_hosts::CREATE_HOSTS_CONCURRENCY;
+use super::{CloudHosts, CreatedHost, HostInfo, HostParameters, HostResourceId, HostSpec};
 use crate::common::{CubConfig, Error};
 use crate::datacenter::CloudDatacenter;
 use crate::log::StringLogger;
-use crate::time_id::ID64;
+use crate::{NonZeroUnixSeconds, UnixTime};
 use async_trait::async_trait;
+use futures::StreamExt;
 use hyper::{http::HeaderValue, HeaderMap, StatusCode};
-use reqwest::Client;
+use rand::rngs::OsRng;
+use rand::Rng;
+use reqwest::{Client, Response};
+use serde::de::{DeserializeOwned, IgnoredAny};
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
 use std::hash::{Hash, Hasher};
 use std::net::IpAddr;
-use std::num::NonZeroU64;
 use std::time::Duration;
 
 /// Linode virtual host.
@@ -63,7 +68,7 @@ impl LinodeHosts {
             .map(|(k, v)| {
                 (
                     k,
-                    Self::strip_resource_id_prefix(&v)
+                    v.strip_provider_prefix(LINODE_PROVIDER_NAME)
                         .unwrap()
                         .parse::<usize>()
                         .expect("firewall ID must be an unsigned integer"),
@@ -73,10 +78,10 @@ impl LinodeHosts {
 
         let mut default_headers = HeaderMap::new();
 
-        default_headers.insert(
-            reqwest::header::AUTHORIZATION,
-            HeaderValue::from_str(&format!("Bearer {}", personal_access_token)).unwrap(),
-        );
+        let mut auth_header =
+            HeaderValue::from_str(&format!("Bearer {}", personal_access_token)).unwrap();
+        auth_header.set_sensitive(true);
+        default_headers.insert(reqwest::header::AUTHORIZATION, auth_header);
         default_headers.insert(
             reqwest::header::USER_AGENT,
             HeaderValue::from_str("softbear cloud control").unwrap(),
@@ -96,6 +101,31 @@ impl LinodeHosts {
         }
     }
 
+    /// Create a Linode firewall, which may then be referenced by `firewall_ids` config.
+    pub async fn create_firewall(
+        &self,
+        label: &str,
+        rules: LinodeFirewallRules,
+    ) -> Result<HostResourceId, Error> {
+        let logger = StringLogger::new(self.debug);
+        logger.trace(format!("create linode firewall {label}"));
+        let endpoint = "https://api.linode.com/v4/networking/firewalls";
+        let record = LinodeFirewall {
+            label: label.to_string(),
+            rules,
+        };
+        let request = self.client.post(endpoint);
+        let request = request.json(&record).build().map_err(Self::map_error)?;
+        logger.trace(format!("{record:?}")); // For now.
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(Self::map_error)?;
+        let LinodeFirewallResponse { id, .. } = Self::parse_response(response).await?;
+        Ok(HostResourceId(format!("{LINODE_PROVIDER_NAME}/{id}")))
+    }
+
     /// Create a `LinodeScript` for Linode.
     /// It is OK for more than one script to have the same label.
     pub async fn create_script(&self, label: &str, script: &str) -> Result<HostResourceId, Error> {
@@ -119,34 +149,75 @@ impl LinodeHosts {
             .execute(request)
             .await
             .map_err(Self::map_error)?;
-        let result = response.text().await.map_err(Self::map_error)?;
-        let LinodeScriptResponse { id, .. } = Self::parse_result(&result)?;
+        let LinodeScriptResponse { id, .. } = Self::parse_response(response).await?;
         Ok(HostResourceId(format!("{LINODE_PROVIDER_NAME}/{id}")))
     }
 
     /// Delete a `LinodeScript` for Linode.
     pub async fn delete_script(&self, resource_id: &HostResourceId) -> Result<(), Error> {
-        let script_id = Self::strip_resource_id_prefix(resource_id)?;
+        let script_id = resource_id.strip_provider_prefix(LINODE_PROVIDER_NAME)?;
         let endpoint = format!("https://api.linode.com/v4/linode/stackscripts/{script_id}");
         let request = self
             .client
             .delete(&endpoint)
             .build()
             .map_err(Self::map_error)?;
-        self.client
+        let response = self
+            .client
             .execute(request)
             .await
             .map_err(Self::map_error)?;
+        Self::parse_response::<IgnoredAny>(response).await?;
         Ok(())
     }
 
+    /// Find one of our `LinodeScript` whose label has the specified content hash.
+    async fn find_script(&self, hash: u64) -> Result<Option<HostResourceId>, Error> {
+        let endpoint = "https://api.linode.com/v4/linode/stackscripts";
+        let filter = format!(r#"{{"mine":true,"label":{{"+contains":"Script {hash}"}}}}"#);
+        let request = self.client.get(endpoint).header("X-Filter", filter);
+        let response = request.send().await.map_err(Self::map_error)?;
+        let list: ListLinodeScriptsResponse = Self::parse_response(response).await?;
+        let suffix = format!(" Script {hash}");
+        Ok(list
+            .data
+            .into_iter()
+            .find(|s| s.record.label.ends_with(&suffix))
+            .map(|s| HostResourceId(format!("{LINODE_PROVIDER_NAME}/{}", s.id))))
+    }
+
+    /// List all Linode firewalls, with their labels and rules.
+    pub async fn list_firewalls(
+        &self,
+    ) -> Result<Vec<(HostResourceId, String, LinodeFirewallRules)>, Error> {
+        let endpoint = "https://api.linode.com/v4/networking/firewalls";
+        let request = self.client.get(endpoint);
+        let response = request.send().await.map_err(Self::map_error)?;
+        let list: ListLinodeFirewallsResponse = Self::parse_response(response).await?;
+        Ok(list
+            .data
+            .into_iter()
+            .map(
+                |LinodeFirewallResponse {
+                     id,
+                     record: LinodeFirewall { label, rules },
+                 }| {
+                    (
+                        HostResourceId(format!("{LINODE_PROVIDER_NAME}/{id}")),
+                        label,
+                        rules,
+                    )
+                },
+            )
+            .collect())
+    }
+
     /// List all `LinodeScript` for Linode.
     pub async fn list_scripts(&self) -> Result<Vec<(HostResourceId, String)>, Error> {
         let endpoint = format!("https://api.linode.com/v4/linode/stackscripts");
         let request = self.client.get(&endpoint);
         let response = request.send().await.map_err(Self::map_error)?;
-        let result = response.text().await.map_err(Self::map_error)?;
-        let list: ListLinodeScriptsResponse = Self::parse_result(&result)?;
+        let list: ListLinodeScriptsResponse = Self::parse_response(response).await?;
         Ok(list
             .data
             .into_iter()
@@ -165,113 +236,154 @@ impl LinodeHosts {
             .collect::<Vec<_>>())
     }
 
+    /// Generates a root password from a cryptographically secure RNG.  Linode requires
+    /// at least 3 of lowercase, uppercase, digits and punctuation.
+    fn generate_password() -> String {
+        const CHARS: &[u8] =
+            b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!@#$%^&*-_=+";
+        loop {
+            let password: String = (0..32)
+                .map(|_| CHARS[OsRng.gen_range(0..CHARS.len())] as char)
+                .collect();
+            let classes = [
+                password.bytes().any(|c| c.is_ascii_lowercase()),
+                password.bytes().any(|c| c.is_ascii_uppercase()),
+                password.bytes().any(|c| c.is_ascii_digit()),
+                password.bytes().any(|c| c.is_ascii_punctuation()),
+            ];
+            if classes.into_iter().all(|c| c) {
+                return password;
+            }
+        }
+    }
+
+    /// Parses a Linode timestamp, which is in UTC, e.g. `2018-01-01T00:01:01`.
+    pub(crate) fn parse_timestamp(timestamp: &str) -> Option<NonZeroUnixSeconds> {
+        let (date, time) = timestamp.trim_end_matches('Z').split_once('T')?;
+        let mut date = date.splitn(3, '-').map(|n| n.parse().ok());
+        let mut time = time.splitn(3, ':').map(|n| n.parse().ok());
+        NonZeroUnixSeconds::from_ymdhms(
+            date.next()??,
+            date.next()??,
+            date.next()??,
+            time.next()??,
+            time.next()??,
+            time.next()??,
+        )
+        .ok()
+    }
+
     fn map_error(e: reqwest::Error) -> Error {
         Error::Http(StatusCode::FAILED_DEPENDENCY, format!("{}", e))
     }
 
-    fn parse_result<'a, T: Deserialize<'a>>(text: &'a String) -> Result<T, Error> {
-        match serde_json::from_str(&text) {
-            Ok(response) => Ok(response),
-            Err(_) => {
-                #[derive(Deserialize)]
-                struct LinodeReason {
-                    field: String,
-                    reason: String,
-                }
-                #[derive(Deserialize)]
-                struct LinodeError {
-                    errors: Vec<LinodeReason>,
-                }
-                match serde_json::from_str(&text) {
-                    Ok(LinodeError { errors }) => {
-                        let r = errors
-                            .into_iter()
-                            .next()
-                            .map(|LinodeReason { field, reason }| format!("{field}: {reason}"));
-                        Err(Error::Http(
-                            StatusCode::FAILED_DEPENDENCY,
-                            format!("linode error: {r:?}"),
-                        ))
-                    }
-                    Err(_) => Err(Error::Http(
-                        StatusCode::FAILED_DEPENDENCY,
-                        format!("cannot parse linode error: {text}"),
-                    )),
-                }
+    /// Parses a Linode response, inspecting its status before its body.
+    async fn parse_response<T: DeserializeOwned>(response: Response) -> Result<T, Error> {
+        let status = response.status();
+        let text = response.text().await.map_err(Self::map_error)?;
+        if !status.is_success() {
+            #[derive(Deserialize)]
+            struct LinodeReason {
+                #[serde(default)]
+                field: Option<String>,
+                reason: String,
             }
+            #[derive(Deserialize)]
+            struct LinodeError {
+                errors: Vec<LinodeReason>,
+            }
+            let message = match serde_json::from_str(&text) {
+                Ok(LinodeError { errors }) => {
+                    let r = errors
+                        .into_iter()
+                        .next()
+                        .map(|LinodeReason { field, reason }| {
+                            if let Some(field) = field {
+                                format!("{field}: {reason}")
+                            } else {
+                                reason
+                            }
+                        });
+                    format!("linode error: {r:?}")
+                }
+                Err(_) => format!("cannot parse linode error: {text}"),
+            };
+            return Err(Error::from_provider_status(status, message));
         }
+        serde_json::from_str(&text).map_err(|e| {
+            Error::Http(
+                StatusCode::FAILED_DEPENDENCY,
+                format!("cannot parse linode response: {e}"),
+            )
+        })
     }
 
-    fn strip_resource_id_prefix(resource_id: &HostResourceId) -> Result<String, Error> {
-        let mut split = resource_id.0.splitn(2, '/');
-        if split
-            .next()
-            .map(|s| s != LINODE_PROVIDER_NAME)
-            .unwrap_or(true)
-        {
-            Err(Error::Http(
-                StatusCode::NOT_ACCEPTABLE,
-                format!("{}: expected 'linode' prefix in resource ID", resource_id.0),
-            ))
-        } else if let Some(payload) = split.next() {
-            Ok(payload.to_string())
+    /// Returns the number of hosts deployed with a `LinodeScript`.
+    async fn script_deployments(&self, resource_id: &HostResourceId) -> Result<usize, Error> {
+        let script_id = resource_id.strip_provider_prefix(LINODE_PROVIDER_NAME)?;
+        let endpoint = format!("https://api.linode.com/v4/linode/stackscripts/{script_id}");
+        let request = self.client.get(&endpoint);
+        let response = request.send().await.map_err(Self::map_error)?;
+        let LinodeScriptResponse {
+            deployments_active, ..
+        } = Self::parse_response(response).await?;
+        Ok(deployments_active)
+    }
+
+    /// Returns the ID of the firewall named by the parameters, if any, otherwise of the
+    /// "default" firewall, if configured.
+    fn firewall_id(&self, parameters: Option<&HostParameters>) -> Result<Option<usize>, Error> {
+        if let Some(firewall_name) = parameters.and_then(HostParameters::firewall_name) {
+            self.firewall_ids
+                .get(firewall_name)
+                .copied()
+                .map(Some)
+                .ok_or(Error::Http(
+                    StatusCode::NOT_FOUND,
+                    format!("{firewall_name}: firewall not found"),
+                ))
         } else {
-            Err(Error::Http(
-                StatusCode::NOT_ACCEPTABLE,
-                format!("{}: invalid cloud resource ID", resource_id.0),
-            ))
+            Ok(self.firewall_ids.get("default").copied())
         }
     }
-}
 
-#[async_trait]
-impl CloudHosts for LinodeHosts {
-    async fn create_host(
+    /// Returns the ID of one of our `LinodeScript` with identical content, creating it if
+    /// there is none, rather than accumulating duplicates.
+    async fn find_or_create_script(&self, label: &str, script: &str) -> Result<usize, Error> {
+        let hash = Self::compute_hash(script);
+        let resource_id = if let Some(resource_id) = self.find_script(hash).await? {
+            StringLogger::new(self.debug).trace(format!("reuse linode script {resource_id}"));
+            resource_id
+        } else {
+            self.create_script(&format!("Linode {label} Script {hash}"), script)
+                .await?
+        };
+        resource_id
+            .strip_provider_prefix(LINODE_PROVIDER_NAME)?
+            .parse()
+            .map_err(|_| {
+                Error::Http(
+                    StatusCode::NOT_ACCEPTABLE,
+                    format!("{resource_id}: not a valid script ID"),
+                )
+            })
+    }
+
+    /// Create a Linode instance that runs an existing `LinodeScript`.
+    async fn create_instance(
         &self,
         label: &str,
         group: Option<&str>,
-        hostname: &str,
         datacenter: CloudDatacenter,
-        script: &str,
-        parameters: Option<HostParameters>,
-    ) -> Result<(HostResourceId, IpAddr), Error> {
-        let default_firewall_name = "default".to_string();
-        let firewall_parameter_name = "firewall_name".to_string();
-        let firewall_id = if let Some(firewall_name) = parameters
-            .as_ref()
-            .and_then(|HostParameters(p)| p.get(&firewall_parameter_name))
-        {
-            Some(self.firewall_ids.get(firewall_name).ok_or(Error::Http(
-                StatusCode::NOT_FOUND,
-                format!("{firewall_name}: firewall not found"),
-            ))?)
-        } else {
-            self.firewall_ids.get(&default_firewall_name)
-        }
-        .copied();
-        let script = script.replace("{{hostname}}", hostname);
-        let hash = Self::compute_hash(&script);
+        script_id: usize,
+        firewall_id: Option<usize>,
+        parameters: Option<&HostParameters>,
+    ) -> Result<CreatedHost, Error> {
         let logger = StringLogger::new(self.debug);
-
-        logger.trace(format!(
-            "create linode instance {label} ({hostname}) in {datacenter:?} with script {hash}"
-        ));
-
-        let resource_id = self
-            .create_script(&format!("Linode {label} Script {hash}"), &script)
-            .await?;
-        let script_id: usize =
-            if let Ok(script_id) = Self::strip_resource_id_prefix(&resource_id)?.parse() {
-                script_id
-            } else {
-                return Err(Error::Http(
-                    StatusCode::NOT_ACCEPTABLE,
-                    format!("{resource_id}: not a valid script ID"),
-                ));
-            };
-
-        let r: NonZeroU64 = ID64::<0>::generate().into();
-        let root_pass = Some(format!("aA!@{r}$%zZ"));
+        let root_password = parameters
+            .and_then(HostParameters::root_password)
+            .map(|p| p.to_string())
+            .unwrap_or_else(Self::generate_password);
         let endpoint = format!("https://api.linode.com/v4/linode/instances");
 
         let record = LinodeInstance {
@@ -280,7 +392,7 @@ impl CloudHosts for LinodeHosts {
             label: label.to_string(),
             tags: group.into_iter().map(|s| s.to_owned()).collect(),
             region: datacenter.to_linode_region()?,
-            root_pass,
+            root_pass: Some(root_password.clone()),
             stackscript_id: Some(script_id),
             firewall_id,
             private_ip: false,
@@ -299,10 +411,9 @@ impl CloudHosts for LinodeHosts {
             .execute(request)
             .await
             .map_err(Self::map_error)?;
-        let result = response.text().await.map_err(Self::map_error)?;
         let LinodeInstanceResponse {
             id: host_id, ipv4, ..
-        } = Self::parse_result(&result)?;
+        } = Self::parse_response(response).await?;
         let ip_addr: IpAddr = ipv4
             .first()
             .and_then(|s| s.parse().ok())
@@ -310,14 +421,92 @@ impl CloudHosts for LinodeHosts {
                 StatusCode::FAILED_DEPENDENCY,
                 format!("{ipv4:?} does not contain an IP address"),
             ))?;
-        Ok((
-            HostResourceId(format!("{LINODE_PROVIDER_NAME}/{host_id}/{script_id}")),
+        Ok(CreatedHost {
+            id: HostResourceId(format!("{LINODE_PROVIDER_NAME}/{host_id}/{script_id}")),
             ip_addr,
-        ))
+            root_password: Some(root_password),
+        })
     }
+}
 
+#[async_trait]
+impl CloudHosts for LinodeHosts {
+    async fn create_host(
+        &self,
+        label: &str,
+        group: Option<&str>,
+        hostname: &str,
+        datacenter: CloudDatacenter,
+        script: &str,
+        parameters: Option<HostParameters>,
+    ) -> Result<CreatedHost, Error> {
+        let firewall_id = self.firewall_id(parameters.as_ref())?;
+        let script = parameters
+            .as_ref()
+            .unwrap_or(&HostParameters::default())
+            .substitute(script, hostname);
+        let logger = StringLogger::new(self.debug);
+        logger.trace(format!(
+            "create linode instance {label} ({hostname}) in {datacenter:?}"
+        ));
+        let script_id = self.find_or_create_script(label, &script).await?;
+        self.create_instance(
+            label,
+            group,
+            datacenter,
+            script_id,
+            firewall_id,
+            parameters.as_ref(),
+        )
+        .await
+    }
+
+    /// Unlike the default, which calls `create_host` concurrently, each distinct script
+    /// is found or created once, up front, so that concurrent calls don't each create a
+    /// duplicate of a script they all failed to find.
+    async fn create_hosts(&self, specs: Vec<HostSpec>) -> Vec<Result<CreatedHost, Error>> {
+        let mut script_ids = HashMap::<u64, usize>::new();
+        let mut prepared = Vec::with_capacity(specs.len());
+        for spec in specs {
+            let script = spec
+                .parameters
+                .as_ref()
+                .unwrap_or(&HostParameters::default())
+                .substitute(&spec.script, &spec.hostname);
+            let hash = Self::compute_hash(&script);
+            let ids: Result<(usize, Option<usize>), Error> = async {
+                let firewall_id = self.firewall_id(spec.parameters.as_ref())?;
+                let script_id = if let Some(&script_id) = script_ids.get(&hash) {
+                    script_id
+                } else {
+                    let script_id = self.find_or_create_script(&spec.label, &script).await?;
+                    script_ids.insert(hash, script_id);
+                    script_id
+                };
+                Ok((script_id, firewall_id))
+            }
+            .await;
+            prepared.push((spec, ids));
+        }
+        futures::stream::iter(prepared)
+            .map(|(spec, ids)| async move {
+                let (script_id, firewall_id) = ids?;
+                self.create_instance(
+                    &spec.label,
+                    spec.group.as_deref(),
+                    spec.datacenter,
+                    script_id,
+                    firewall_id,
+                    spec.parameters.as_ref(),
+                )
+                .await
+            })
+            .buffered(CREATE_HOSTS_CONCURRENCY)
+            .collect()
+            .await
+    }
     async fn delete_host(&self, resource_id: &HostResourceId) -> Result<(), Error> {
-        let sans_prefix = Self::strip_resource_id_prefix(resource_id)?;
+        let sans_prefix = resource_id.strip_provider_prefix(LINODE_PROVIDER_NAME)?;
         let mut split = sans_prefix.splitn(2, '/');
         let host_id: usize = split.next().and_then(|s| s.parse().ok()).ok_or_else(|| {
             Error::Http(
@@ -326,13 +515,19 @@ impl CloudHosts for LinodeHosts {
             )
         })?;
 
+        // Scripts may be shared by hosts, so only delete one if no other host uses it.  The
+        // count is read before the host is deleted, so it is racy: a host created meanwhile
+        // may lose its script, and concurrent deletes may each leave the script in place.
         let script_id: Option<usize> = split.next().and_then(|s| s.parse::<usize>().ok());
-        if let Some(script_id) = script_id {
-            self.delete_script(&HostResourceId(format!(
-                "{LINODE_PROVIDER_NAME}/{script_id}"
-            )))
-            .await?;
-        }
+        let unused_script_id = if let Some(script_id) = script_id {
+            let script_resource_id = HostResourceId(format!("{LINODE_PROVIDER_NAME}/{script_id}"));
+            match self.script_deployments(&script_resource_id).await {
+                Ok(deployments) if deployments <= 1 => Some(script_resource_id),
+                _ => None,
+            }
+        } else {
+            None
+        };
 
         let endpoint = format!("https://api.linode.com/v4/linode/instances/{host_id}");
         let request = self
@@ -340,10 +535,15 @@ impl CloudHosts for LinodeHosts {
             .delete(&endpoint)
             .build()
             .map_err(Self::map_error)?;
-        self.client
+        let response = self
+            .client
             .execute(request)
             .await
             .map_err(Self::map_error)?;
+        Self::parse_response::<IgnoredAny>(response).await?;
+        if let Some(script_resource_id) = unused_script_id {
+            self.delete_script(&script_resource_id).await?;
+        }
         Ok(())
     }
 
@@ -369,18 +569,17 @@ impl CloudHosts for LinodeHosts {
     }
 
     /// Return a list of cloud hosts.  Unfortunately, this does not contain script IDs.
-    async fn list_hosts(&self) -> Result<Vec<(HostResourceId, IpAddr, Option<String>)>, Error> {
+    async fn list_hosts_detailed(&self) -> Result<Vec<HostInfo>, Error> {
         let endpoint = format!("https://api.linode.com/v4/linode/instances");
         let request = self.client.get(&endpoint);
         let response = request.send().await.map_err(Self::map_error)?;
-        let result = response.text().await.map_err(Self::map_error)?;
-        let list: ListLinodeInstancesResponse = Self::parse_result(&result)?;
+        let list: ListLinodeInstancesResponse = Self::parse_response(response).await?;
         let mut result: Vec<_> = vec![];
         for LinodeInstanceResponse {
             id: host_id,
             ipv4,
-            record: LinodeInstance { label, .. },
-            ..
+            created,
+            record: LinodeInstance { label, region, .. },
         } in list.data.into_iter()
         {
             let ip_addr: IpAddr = ipv4
@@ -390,11 +589,13 @@ impl CloudHosts for LinodeHosts {
                     StatusCode::FAILED_DEPENDENCY,
                     format!("{ipv4:?} does not contain an IP address"),
                 ))?;
-            result.push((
-                HostResourceId(format!("{LINODE_PROVIDER_NAME}/{host_id}")),
+            result.push(HostInfo {
+                id: HostResourceId(format!("{LINODE_PROVIDER_NAME}/{host_id}")),
                 ip_addr,
-                Some(label),
-            ))
+                label: Some(label),
+                created: created.as_deref().and_then(Self::parse_timestamp),
+                datacenter: Some(CloudDatacenter::from_linode_region(&region)),
+            })
         }
         Ok(result)
     }
@@ -409,7 +610,7 @@ const LINODE_PROVIDER_NAME: &str = "linode";
 const LINODE_TYPE: &str = "g6-nanode-1";
 const SWAP_SIZE_MB: usize = 128;
 
-#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Eq, PartialEq, Serialize, Deserialize)]
 struct LinodeInstance {
     authorized_keys: Option<Vec<String>>,
     image: String,
@@ -431,10 +632,31 @@ struct LinodeInstance {
     swap_size: Option<usize>,
 }
 
+impl Debug for LinodeInstance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LinodeInstance")
+            .field("authorized_keys", &self.authorized_keys)
+            .field("image", &self.image)
+            .field("label", &self.label)
+            .field("tags", &self.tags)
+            .field("region", &self.region)
+            .field("root_pass", &self.root_pass.as_ref().map(|_| "***"))
+            .field("stackscript_id", &self.stackscript_id)
+            .field("firewall_id", &self.firewall_id)
+            .field("private_ip", &self.private_ip)
+            .field("linode_type", &self.linode_type)
+            .field("swap_size", &self.swap_size)
+            .finish()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct LinodeInstanceResponse {
     id: usize,
     ipv4: Vec<String>,
+    /// When the instance was created, in UTC, e.g. `2018-01-01T00:01:01`.
+    #[serde(default)]
+    created: Option<String>,
     #[serde(flatten)]
     record: LinodeInstance,
 }
@@ -453,7 +675,8 @@ struct LinodeScript {
 struct LinodeScriptResponse {
     id: usize,
     // username: String,
-    // deployments_active: usize,
+    #[serde(default)]
+    deployments_active: usize,
     #[serde(flatten)]
     record: LinodeScript,
 }
@@ -467,3 +690,92 @@ struct ListLinodeInstancesResponse {
 struct ListLinodeScriptsResponse {
     data: Vec<LinodeScriptResponse>,
 }
+
+/// Whether a Linode firewall accepts or drops traffic.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LinodeFirewallAction {
+    /// Allow traffic.
+    Accept,
+    /// Block traffic.
+    #[default]
+    Drop,
+}
+
+/// Network protocol to which a Linode firewall rule applies.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LinodeFirewallProtocol {
+    /// TCP.
+    Tcp,
+    /// UDP.
+    Udp,
+    /// ICMP.
+    Icmp,
+    /// IP encapsulation.
+    Ipencap,
+}
+
+/// Addresses to which a Linode firewall rule applies, e.g. "0.0.0.0/0".
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LinodeFirewallAddresses {
+    /// IPv4 addresses or ranges.
+    #[serde(default)]
+    pub ipv4: Vec<String>,
+    /// IPv6 addresses or ranges.
+    #[serde(default)]
+    pub ipv6: Vec<String>,
+}
+
+/// A Linode firewall rule.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LinodeFirewallRule {
+    /// Whether matching traffic is accepted or dropped.
+    pub action: LinodeFirewallAction,
+    /// Addresses to which the rule applies.
+    pub addresses: LinodeFirewallAddresses,
+    /// Optional description of the rule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Optional label of the rule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Ports or port ranges, e.g. "22, 80, 443, 8000-8080" (not allowed for ICMP).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ports: Option<String>,
+    /// Protocol to which the rule applies.
+    pub protocol: LinodeFirewallProtocol,
+}
+
+/// Rules of a Linode firewall.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LinodeFirewallRules {
+    /// Rules for incoming traffic.
+    #[serde(default)]
+    pub inbound: Vec<LinodeFirewallRule>,
+    /// What happens to incoming traffic which matches no rule.
+    pub inbound_policy: LinodeFirewallAction,
+    /// Rules for outgoing traffic.
+    #[serde(default)]
+    pub outbound: Vec<LinodeFirewallRule>,
+    /// What happens to outgoing traffic which matches no rule.
+    pub outbound_policy: LinodeFirewallAction,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct LinodeFirewall {
+    label: String,
+    rules: LinodeFirewallRules,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinodeFirewallResponse {
+    id: usize,
+    #[serde(flatten)]
+    record: LinodeFirewall,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListLinodeFirewallsResponse {
+    data: Vec<LinodeFirewallResponse>,
+}
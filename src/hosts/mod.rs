@@ -8,5 +8,11 @@ mod linode;
 /// Unit tests
 mod tests;
 
-pub use self::cloud_hosts::{CloudHosts, CloudHostsClient, HostParameters, HostResourceId};
-pub use self::linode::LinodeHosts;
+pub use self::cloud_hosts::{
+    CloudHosts, CloudHostsClient, CreatedHost, HostInfo, HostParameters, HostParametersBuilder,
+    HostResourceId, HostSpec,
+};
+pub use self::linode::{
+    LinodeFirewallAction, LinodeFirewallAddresses, LinodeFirewallProtocol, LinodeFirewallRule,
+    LinodeFirewallRules, LinodeHosts,
+};
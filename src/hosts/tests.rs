@@ -3,8 +3,194 @@
 
 #[cfg(test)]
 mod hosts_test {
-    use crate::common::CubConfig;
-    use crate::hosts::{CloudHosts, LinodeHosts};
+    use crate::common::{CubConfig, Error};
+    use crate::datacenter::CloudDatacenter;
+    use crate::hosts::{
+        CloudHosts, CreatedHost, HostInfo, HostParameters, HostResourceId, HostSpec,
+        LinodeFirewallAction, LinodeFirewallAddresses, LinodeFirewallProtocol, LinodeFirewallRule,
+        LinodeFirewallRules, LinodeHosts,
+    };
+    use crate::{NonZeroUnixSeconds, UnixTime};
+    use async_trait::async_trait;
+    use hyper::StatusCode;
+    use std::net::IpAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn host_resource_id_tests() {
+        let id = HostResourceId("linode/123/456".to_string());
+        assert_eq!(id.strip_provider_prefix("linode").unwrap(), "123/456");
+        assert!(id.strip_provider_prefix("hetzner").is_err());
+        assert!(HostResourceId("linode/".to_string())
+            .strip_provider_prefix("linode")
+            .is_err());
+        assert!(HostResourceId("linode".to_string())
+            .strip_provider_prefix("linode")
+            .is_err());
+    }
+
+    #[test]
+    fn substitute_tests() {
+        let parameters = HostParameters::builder()
+            .parameter("region", "us-east")
+            .parameter("join_token", "{{region}}")
+            .parameter("hostname", "ignored")
+            .build();
+        assert_eq!(
+            parameters.substitute(
+                "#!/bin/sh\nhostname {{hostname}}\nexport REGION={{ region }} TOKEN={{join_token}}\n",
+                "node1"
+            ),
+            "#!/bin/sh\nhostname node1\nexport REGION=us-east TOKEN={{region}}\n"
+        );
+        assert_eq!(
+            parameters.substitute("{{unknown}} {{region}} {{region", "node1"),
+            "{{unknown}} us-east {{region"
+        );
+        assert_eq!(
+            HostParameters::default().substitute("echo {{hostname}}{{hostname}}", "a"),
+            "echo aa"
+        );
+    }
+
+    #[test]
+    fn host_parameters_tests() {
+        let parameters = HostParameters::builder().firewall("web").build();
+        assert_eq!(parameters.firewall_name(), Some("web"));
+        assert_eq!(parameters.get("firewall_name"), Some("web"));
+        let parameters = HostParameters::builder().parameter("other", "x").build();
+        assert_eq!(parameters.firewall_name(), None);
+        assert_eq!(parameters.get("other"), Some("x"));
+    }
+
+    /// Allocates hosts slowly, failing those whose hostname starts with "bad".
+    #[derive(Default)]
+    struct SlowHosts {
+        active: AtomicUsize,
+        max_active: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl CloudHosts for SlowHosts {
+        async fn create_host(
+            &self,
+            label: &str,
+            _group: Option<&str>,
+            hostname: &str,
+            _datacenter: CloudDatacenter,
+            _script: &str,
+            _parameters: Option<HostParameters>,
+        ) -> Result<CreatedHost, Error> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            if hostname.starts_with("bad") {
+                return Err(Error::Http(
+                    StatusCode::FAILED_DEPENDENCY,
+                    format!("{hostname}: cannot create"),
+                ));
+            }
+            Ok(CreatedHost {
+                id: HostResourceId(format!("slow/{label}")),
+                ip_addr: IpAddr::from([127, 0, 0, 1]),
+                root_password: None,
+            })
+        }
+
+        async fn delete_host(&self, _id: &HostResourceId) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn list_datacenters(&self) -> Result<Vec<CloudDatacenter>, Error> {
+            Ok(vec![])
+        }
+
+        async fn list_hosts_detailed(&self) -> Result<Vec<HostInfo>, Error> {
+            Ok(vec![HostInfo {
+                id: HostResourceId("slow/1".to_string()),
+                ip_addr: IpAddr::from([127, 0, 0, 1]),
+                label: Some("label1".to_string()),
+                created: Some(NonZeroUnixSeconds::from_seconds(1514764861)),
+                datacenter: Some(CloudDatacenter::from_linode_region("us-east")),
+            }])
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "slow"
+        }
+    }
+
+    #[tokio::test]
+    async fn create_hosts_tests() {
+        let hosts = SlowHosts::default();
+        let datacenter = CloudDatacenter::from_linode_region("us-east");
+        let specs: Vec<_> = (0..10)
+            .map(|i| {
+                let hostname = if i == 3 {
+                    "bad3".to_string()
+                } else {
+                    format!("node{i}")
+                };
+                HostSpec::new(&format!("label{i}"), &hostname, datacenter.clone(), "")
+            })
+            .collect();
+        let results = hosts.create_hosts(specs).await;
+        assert_eq!(results.len(), 10);
+        for (i, result) in results.iter().enumerate() {
+            match result {
+                Ok(created) => assert_eq!(created.id.0, format!("slow/label{i}")),
+                Err(_) => assert_eq!(i, 3),
+            }
+        }
+        assert!(results[3].is_err());
+        let max_active = hosts.max_active.load(Ordering::SeqCst);
+        assert!(max_active > 1, "{max_active}");
+        assert!(max_active <= 4, "{max_active}");
+    }
+
+    #[test]
+    fn parse_timestamp_tests() {
+        let expected = Some(NonZeroUnixSeconds::from_seconds(1514764861));
+        assert_eq!(
+            LinodeHosts::parse_timestamp("2018-01-01T00:01:01"),
+            expected
+        );
+        assert_eq!(
+            LinodeHosts::parse_timestamp("2018-01-01T00:01:01Z"),
+            expected
+        );
+        for malformed in [
+            "",
+            "2018-01-01",
+            "2018-01-01 00:01:01",
+            "2018-01-01T00:01",
+            "2018-01-01T00:01:xx",
+            "2018-13-01T00:01:01",
+            "2018-01-01T00:01:01+01:00",
+        ] {
+            assert_eq!(LinodeHosts::parse_timestamp(malformed), None, "{malformed}");
+        }
+    }
+
+    #[tokio::test]
+    async fn list_hosts_tests() {
+        let hosts = SlowHosts::default();
+        let detailed = hosts.list_hosts_detailed().await.expect("detailed");
+        assert_eq!(
+            detailed[0].created,
+            NonZeroUnixSeconds::from_ymdhms(2018, 1, 1, 0, 1, 1).ok()
+        );
+        assert_eq!(
+            hosts.list_hosts().await.expect("list"),
+            vec![(
+                detailed[0].id.clone(),
+                detailed[0].ip_addr,
+                detailed[0].label.clone()
+            )]
+        );
+    }
 
     #[tokio::test]
     async fn linode_host_tests() {
@@ -42,6 +228,32 @@ mod hosts_test {
             linode_host.delete_script(&id).await.expect("delete_script");
             println!("delete script succeeded.");
         }
+        const FIREWALL_TEST: bool = false;
+        if FIREWALL_TEST {
+            let rules = LinodeFirewallRules {
+                inbound: vec![LinodeFirewallRule {
+                    action: LinodeFirewallAction::Accept,
+                    addresses: LinodeFirewallAddresses {
+                        ipv4: vec!["0.0.0.0/0".to_string()],
+                        ipv6: vec!["::/0".to_string()],
+                    },
+                    description: None,
+                    label: Some("allow-https".to_string()),
+                    ports: Some("443".to_string()),
+                    protocol: LinodeFirewallProtocol::Tcp,
+                }],
+                inbound_policy: LinodeFirewallAction::Drop,
+                outbound: vec![],
+                outbound_policy: LinodeFirewallAction::Accept,
+            };
+            let id = linode_host
+                .create_firewall("candywrapper_firewall01", rules)
+                .await
+                .expect("create_firewall");
+            println!("create firewall succeeded.  id={id:?}");
+            let firewalls = linode_host.list_firewalls().await.expect("list_firewalls");
+            println!("firewalls: {firewalls:?}");
+        }
         const HOST_TEST: bool = true;
         if HOST_TEST {
             let datacenter = datacenter_list[0].clone();
@@ -52,9 +264,12 @@ mod hosts_test {
                 .create_host(label, None, hostname, datacenter, script, None)
                 .await
             {
-                Ok((id, addr)) => {
-                    println!("create host succeeded.  id={id:?}, addr={addr}");
-                    linode_host.delete_host(&id).await.expect("delete_host");
+                Ok(created) => {
+                    println!("create host succeeded: {created:?}");
+                    linode_host
+                        .delete_host(&created.id)
+                        .await
+                        .expect("delete_host");
                     println!("delete host succeeded.");
                 }
                 Err(e) => println!("create host failed: {e:?}"),
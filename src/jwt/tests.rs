@@ -29,6 +29,24 @@ mod jwt_tests {
         };
     }
 
+    #[test]
+    fn jwt_debug_tests() {
+        let cub_config = CubConfig::builder()
+            .toml_str(
+                r#"
+                [jwt]
+                private_key_pem = "very secret"
+            "#,
+            )
+            .build()
+            .expect("jwt_debug_tests.toml");
+        let client = new_jwt_client(&cub_config);
+        let debug = format!("{client:?}");
+        println!("{debug}");
+        assert!(!debug.contains("very secret"));
+        assert!(debug.contains("***"));
+    }
+
     #[tokio::test]
     async fn jwt_signing_tests() {
         println!("JWT signing tests");
@@ -9,19 +9,33 @@ use serde::ser::Serialize;
 use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
 use std::str::FromStr;
 
 // RS256 is for asymmetric, HS256 is symmetric.
 const DEFAULT_ALGORITHM: &str = "RS256";
 
 /// JWT validation client.
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct JwtClient {
     algorithms: HashMap<String, String>,
     private_key_pem: Option<String>,
     public_key_pems: HashMap<String, String>,
 }
 
+impl Debug for JwtClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwtClient")
+            .field("algorithms", &self.algorithms)
+            .field(
+                "private_key_pem",
+                &self.private_key_pem.as_ref().map(|_| "***"),
+            )
+            .field("public_key_pems", &self.public_key_pems)
+            .finish()
+    }
+}
+
 /// Creates a JWT.
 pub fn create_jwt<T: Serialize>(
     client: &JwtClient,
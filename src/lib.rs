@@ -67,6 +67,12 @@ pub use yew_markdown::*;
 pub mod serde_utils;
 pub use serde_utils::*;
 
+#[cfg(feature = "storage")]
+/// A wrapper which provides access to object storage services.
+pub mod storage;
+#[cfg(feature = "storage")]
+pub use storage::*;
+
 #[cfg(feature = "stripe")]
 /// A wrapper which provides access to Stripe payments.
 pub mod stripe;
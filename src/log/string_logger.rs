@@ -16,6 +16,8 @@ pub struct LoggerInner {
 pub struct StringLogger {
     pub(crate) debug: bool,
     pub(crate) inner: Arc<Mutex<LoggerInner>>,
+    /// Prepended to each line, e.g. to correlate the lines of a request.
+    pub(crate) prefix: Option<Arc<str>>,
 }
 
 impl StringLogger {
@@ -92,10 +94,10 @@ impl StringLogger {
     /// Add an indented trace line to this logger.
     pub fn indent(&self, line: String, indentation: &str) {
         if !line.is_empty() {
-            let indented_line = format!(
+            let indented_line = self.prefixed(format!(
                 "{indentation}{}",
                 line.replace('\n', &format!("\n{indentation}"))
-            );
+            ));
             if self.debug {
                 println!("{indented_line}");
             }
@@ -119,6 +121,14 @@ impl StringLogger {
         Self {
             debug,
             inner: Arc::new(Mutex::new(Default::default())),
+            prefix: None,
+        }
+    }
+
+    fn prefixed(&self, line: String) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{prefix}{line}"),
+            None => line,
         }
     }
 
@@ -145,6 +155,7 @@ impl StringLogger {
         Self {
             debug: self.debug,
             inner: self.inner.clone(),
+            prefix: self.prefix.clone(),
         }
     }
 
@@ -165,9 +176,17 @@ impl StringLogger {
         }
     }
 
+    /// Prepend the specified prefix to each line subsequently added to this logger (or
+    /// its references), e.g. the `RequestId` followed by a space.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into().into());
+        self
+    }
+
     /// Add an ordinary trace line to this logger.
     pub fn trace(&self, line: String) {
         if !line.is_empty() {
+            let line = self.prefixed(line);
             if self.debug {
                 println!("{line}");
             }
@@ -180,6 +199,7 @@ impl StringLogger {
     /// Add a warning or error line to this logger.
     pub fn warn(&self, line: String) {
         if !line.is_empty() {
+            let line = self.prefixed(line);
             if self.debug {
                 println!("{line}");
             }
@@ -200,6 +220,7 @@ impl Clone for StringLogger {
                 lines: vec![self.to_string()],
                 warn: self.contains_warnings(),
             })),
+            prefix: self.prefix.clone(),
         }
     }
 }
@@ -57,4 +57,17 @@ mod tests {
         log6.prepend(log5);
         println!("Testing prepend:\n{}", log6.to_string());
     }
+
+    #[test]
+    fn prefix_tests() {
+        let log = StringLogger::default().with_prefix("[123] ");
+        log.trace("started".to_string());
+        log.reference().warn("failed".to_string());
+        log.indent("detail".to_string(), "  ");
+        assert_eq!(
+            log.to_string(),
+            "[123] started\n[123] failed\n[123]   detail"
+        );
+        assert!(log.contains_warnings());
+    }
 }
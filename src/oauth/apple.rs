@@ -0,0 +1,229 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{Embed, OAuthProvider, OAuthService, RedirectKind, Url};
+use crate::common::{AuthenticatedId, CubConfig, Error, Identity, UserName};
+use crate::{NonZeroUnixSeconds, UnixTime};
+use async_trait::async_trait;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU64;
+use std::time::Duration;
+
+const APPLE_ISSUER: &str = "https://appleid.apple.com";
+/// Apple allows up to 6 months, but the client secret is created for each request.
+const CLIENT_SECRET_TTL_SECONDS: u64 = 5 * 60;
+
+pub struct AppleOAuth2Service {
+    /// The Services ID (e.g. `com.example.signin`).
+    client_id: String,
+    encoding_key: EncodingKey,
+    key_id: String,
+    localhost_redirect_url: Option<String>,
+    redirect_url: String,
+    team_id: String,
+}
+
+impl AppleOAuth2Service {
+    pub fn new(cub_config: &CubConfig) -> Result<Self, Error> {
+        #[derive(Deserialize)]
+        struct AppleConfig {
+            client_id: String,
+            key_id: String,
+            localhost_redirect_url: Option<String>,
+            /// Contents of the `.p8` file downloaded from Apple.
+            private_key_pem: String,
+            redirect_url: String,
+            team_id: String,
+        }
+        #[derive(Deserialize)]
+        struct ConfigToml {
+            apple: AppleConfig,
+        }
+        let ConfigToml {
+            apple:
+                AppleConfig {
+                    client_id,
+                    key_id,
+                    localhost_redirect_url,
+                    private_key_pem,
+                    redirect_url,
+                    team_id,
+                },
+        } = cub_config.get().map_err(|e| Error::String(e.to_string()))?;
+        let encoding_key = EncodingKey::from_ec_pem(private_key_pem.as_bytes())
+            .map_err(|e| Error::String(format!("cannot parse Apple private key: {e:?}")))?;
+        Ok(Self {
+            client_id,
+            encoding_key,
+            key_id,
+            localhost_redirect_url,
+            redirect_url,
+            team_id,
+        })
+    }
+
+    async fn authenticated_by(&self, redirect_url: &str, code: &str) -> Result<Identity, Error> {
+        let client_secret = self.client_secret()?;
+        let token_payload: Vec<(&'static str, &str)> = vec![
+            ("client_id", &self.client_id),
+            ("client_secret", &client_secret),
+            ("code", code),
+            ("grant_type", "authorization_code"),
+            ("redirect_uri", redirect_url),
+        ];
+
+        let http_client = Self::create_http_client()?;
+        let token_endpoint = format!("{APPLE_ISSUER}/auth/token");
+        let token_response = http_client
+            .request(Method::POST, token_endpoint)
+            .form(&token_payload)
+            .send()
+            .await
+            .map_err(|e| Error::String(e.to_string()))?;
+        if !token_response.status().is_success() {
+            return match token_response.text().await {
+                Ok(body) => Err(Error::String(format!("apple token post: {body}"))),
+                Err(e) => Err(Error::String(format!("token: {e}"))),
+            };
+        }
+        #[derive(Deserialize)]
+        struct AppleTokenResponse {
+            id_token: String,
+        }
+        let token_text = token_response
+            .text()
+            .await
+            .map_err(|e| Error::String(format!("apple token response: {e}")))?;
+        let AppleTokenResponse { id_token } = serde_json::from_str(&token_text)
+            .map_err(|e| Error::String(format!("apple token parse: {e}\n{token_text}")))?;
+
+        // Apple has no userinfo endpoint, so the user is identified by the id_token.
+        // https://developer.apple.com/documentation/sign_in_with_apple/sign_in_with_apple_rest_api/authenticating_users_with_sign_in_with_apple
+        #[derive(Deserialize)]
+        struct AppleIdTokenClaims {
+            #[serde(default)]
+            email: Option<String>,
+            sub: String,
+        }
+        // The id_token came directly from Apple over TLS, so (as permitted by OpenID
+        // Connect) its signature isn't checked, but its issuer and audience are.
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.insecure_disable_signature_validation();
+        validation.set_audience(&[&self.client_id]);
+        validation.set_issuer(&[APPLE_ISSUER]);
+        let AppleIdTokenClaims { email, sub } =
+            decode::<AppleIdTokenClaims>(&id_token, &DecodingKey::from_secret(&[]), &validation)
+                .map_err(|e| Error::String(format!("apple id_token: {e:?}")))?
+                .claims;
+        Ok(Identity {
+            login_id: AuthenticatedId(format!("apple/{}", sub)),
+            user_name: email.map(UserName),
+        })
+    }
+
+    /// Apple requires the client secret to be an ES256 JWT signed by the private key.
+    fn client_secret(&self) -> Result<String, Error> {
+        #[derive(Serialize)]
+        struct ClientSecretClaims<'a> {
+            aud: &'static str,
+            exp: u64,
+            iat: u64,
+            iss: &'a str,
+            sub: &'a str,
+        }
+        let now = NonZeroUnixSeconds::now();
+        let claims = ClientSecretClaims {
+            aud: APPLE_ISSUER,
+            exp: now.add_seconds(CLIENT_SECRET_TTL_SECONDS).0.into(),
+            iat: now.0.into(),
+            iss: &self.team_id,
+            sub: &self.client_id,
+        };
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(self.key_id.clone());
+        encode(&header, &claims, &self.encoding_key)
+            .map_err(|e| Error::String(format!("cannot create Apple client secret: {e:?}")))
+    }
+
+    fn create_http_client() -> Result<reqwest::Client, Error> {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(3))
+            .build()
+            .map_err(|e| Error::String(format!("cannot create http client: {e}")))
+    }
+
+    fn redirect_url(&self, url_kind: RedirectKind) -> &str {
+        url_kind.select(&self.redirect_url, self.localhost_redirect_url.as_deref())
+    }
+
+    fn redirect_to(&self, redirect_url: &str) -> Url {
+        // No scopes are requested, because Apple would then require `response_mode=form_post`
+        // rather than passing the code as a query parameter like other providers.
+        Url::parse_with_params(
+            &format!("{APPLE_ISSUER}/auth/authorize"),
+            &[
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", redirect_url),
+                ("response_type", "code"),
+                ("response_mode", "query"),
+            ],
+        )
+        .unwrap()
+    }
+}
+
+#[async_trait]
+impl OAuthService for AppleOAuth2Service {
+    async fn authenticated_with(
+        &self,
+        url_kind: RedirectKind,
+        code: String,
+    ) -> Result<Identity, Error> {
+        self.authenticated_by(self.redirect_url(url_kind), &code)
+            .await
+    }
+
+    async fn detail(
+        &self,
+        _oauth_id: Option<&AuthenticatedId>,
+        name: &str,
+    ) -> Result<String, Error> {
+        Err(Error::String(format!(
+            "{name}: not a supported detail for Apple"
+        )))
+    }
+
+    fn provider(&self) -> OAuthProvider {
+        OAuthProvider::Apple
+    }
+
+    fn redirect_with(&self, url_kind: RedirectKind) -> Url {
+        self.redirect_to(self.redirect_url(url_kind))
+    }
+
+    async fn send_embed(
+        &self,
+        channel_name: &str,
+        _embed: &Embed,
+        _ping: bool,
+        _reply_to_id: Option<NonZeroU64>,
+    ) -> Result<(), Error> {
+        Err(Error::String(format!(
+            "{channel_name}: not a supported channel for Apple"
+        )))
+    }
+
+    async fn send_message(
+        &self,
+        channel_name: &str,
+        _message: &str,
+        _ping: bool,
+        _reply_to_id: Option<NonZeroU64>,
+    ) -> Result<(), Error> {
+        Err(Error::String(format!(
+            "{channel_name}: not a supported channel for Apple"
+        )))
+    }
+}
@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::{discord, google, OAuthProvider, OAuthService};
+use super::{apple, discord, google, Embed, OAuthProvider, OAuthService, RedirectKind};
 use crate::common::{AuthenticatedId, CubConfig, Error, Identity};
 use std::collections::HashMap;
 use std::num::NonZeroU64;
@@ -10,6 +10,9 @@ use std::num::NonZeroU64;
 pub type Url = reqwest::Url;
 
 /// The `OAuthClient` calls the necessary OAuth2 provider APIs to authenticate a user.
+/// It holds a service for each provider that is configured, and each method selects the
+/// service for the specified `OAuthProvider`, e.g. `redirect(provider)` to begin signing
+/// in and `authenticate(provider, code)` to finish.  Unconfigured providers return an error.
 pub struct OAuthClient {
     provider_clients: HashMap<OAuthProvider, Box<dyn OAuthService + Send + Sync>>,
 }
@@ -18,6 +21,9 @@ impl OAuthClient {
     /// Returns a new Oauth2 wrapper service.
     pub fn new(cub_config: &CubConfig) -> Self {
         let mut provider_clients: HashMap<_, Box<dyn OAuthService + Send + Sync>> = HashMap::new();
+        if let Ok(p) = apple::AppleOAuth2Service::new(cub_config) {
+            provider_clients.insert(p.provider(), Box::new(p));
+        }
         if let Ok(p) = discord::DiscordOAuth2Service::new(cub_config) {
             provider_clients.insert(p.provider(), Box::new(p));
         }
@@ -27,8 +33,9 @@ impl OAuthClient {
         Self { provider_clients }
     }
 
-    /// Handles the callback from an OAuth2 provider.
-    pub async fn authenticated(
+    /// Handles the callback from an OAuth2 provider, exchanging the `code` for the
+    /// `Identity` of the user.
+    pub async fn authenticate(
         &self,
         provider: OAuthProvider,
         code: String,
@@ -38,6 +45,41 @@ impl OAuthClient {
             .await
     }
 
+    /// Handles the callback from an OAuth2 provider, which must have been reached via
+    /// `redirect_with` of the same `RedirectKind`.
+    pub async fn authenticated_with(
+        &self,
+        provider: OAuthProvider,
+        url_kind: RedirectKind,
+        code: String,
+    ) -> Result<Identity, Error> {
+        self.get_provider_client(provider)?
+            .authenticated_with(url_kind, code)
+            .await
+    }
+
+    /// Handles the callback from an OAuth2 provider.  Equivalent to `authenticate`.
+    pub async fn authenticated(
+        &self,
+        provider: OAuthProvider,
+        code: String,
+    ) -> Result<Identity, Error> {
+        self.authenticate(provider, code).await
+    }
+
+    /// Handles the callback from an OAuth2 provider, but fails unless the user has the
+    /// specified role (e.g. a role in the Discord guild).
+    pub async fn authenticated_with_required_role(
+        &self,
+        provider: OAuthProvider,
+        code: String,
+        role_name: &str,
+    ) -> Result<Identity, Error> {
+        self.get_provider_client(provider)?
+            .authenticated_with_required_role(code, role_name)
+            .await
+    }
+
     /// For diagnostic purposes.
     pub async fn authenticated_by_localhost(
         &self,
@@ -79,16 +121,40 @@ impl OAuthClient {
             .collect()
     }
 
-    /// Returns a `Url` that redirects to the specified OAuth2 provider.
+    /// Returns a `Url` that redirects to the specified OAuth2 provider, to begin signing in.
     pub fn redirect(&self, provider: OAuthProvider) -> Result<Url, Error> {
         Ok(self.get_provider_client(provider)?.redirect())
     }
 
-    /// For diagnostic purposes.  Only supported for Discord.
+    /// Returns a `Url` that redirects to the specified OAuth2 provider, which will
+    /// redirect back to the URL of the specified kind.
+    pub fn redirect_with(
+        &self,
+        provider: OAuthProvider,
+        url_kind: RedirectKind,
+    ) -> Result<Url, Error> {
+        Ok(self.get_provider_client(provider)?.redirect_with(url_kind))
+    }
+
+    /// For diagnostic purposes.
     pub fn redirect_to_localhost(&self, provider: OAuthProvider) -> Result<Url, Error> {
         Ok(self.get_provider_client(provider)?.redirect_to_localhost())
     }
 
+    /// Sends a rich message via the provider, if possible.
+    pub async fn send_embed(
+        &self,
+        provider: OAuthProvider,
+        channel_name: &str,
+        embed: &Embed,
+        ping: bool,
+        reply_to_id: Option<NonZeroU64>,
+    ) -> Result<(), Error> {
+        self.get_provider_client(provider)?
+            .send_embed(channel_name, embed, ping, reply_to_id)
+            .await
+    }
+
     /// Sends a message via the provider, if possible.
     pub async fn send_message(
         &self,
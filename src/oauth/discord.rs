@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::{OAuthProvider, OAuthService, Url};
+use super::{Embed, OAuthProvider, OAuthService, RedirectKind, Url};
 use crate::common::{AuthenticatedId, CubConfig, Error, Identity, UserName};
 use crate::serde_utils::is_default;
 use crate::{NonZeroUnixSeconds, UnixTime};
@@ -20,10 +20,20 @@ use std::time::Duration;
 
 const DEBUG: bool = false;
 
-pub struct DiscordOAuth2Service {
+/// Maximum number of times a rate limited bot request is retried.
+const RATE_LIMIT_MAX_RETRIES: usize = 3;
+/// Longest `retry_after` that will be waited for, in seconds.
+const RATE_LIMIT_MAX_WAIT_SECS: f64 = 10.0;
+
+/// Bot API client, which is only needed to read guild roles and send messages.
+struct DiscordBot {
     guild_id: NonZeroU64,
-    http_auth_client: reqwest::Client,
     http_api_client: reqwest::Client,
+}
+
+pub struct DiscordOAuth2Service {
+    bot: Option<DiscordBot>,
+    http_auth_client: reqwest::Client,
     localhost_redirect_url: Option<String>,
     oauth2_client: BasicClient,
     channel_name_to_id_cache: Mutex<HashMap<String, (String, NonZeroUnixSeconds)>>,
@@ -33,10 +43,10 @@ impl DiscordOAuth2Service {
     pub fn new(cub_config: &CubConfig) -> Result<Self, Error> {
         #[derive(Deserialize)]
         struct DiscordConfig {
-            bot_token: String,
+            bot_token: Option<String>,
             client_id: String,
             client_secret: String,
-            guild_id: String,
+            guild_id: Option<String>,
             localhost_redirect_url: Option<String>,
             redirect_url: String,
         }
@@ -56,24 +66,41 @@ impl DiscordOAuth2Service {
                 },
         } = cub_config.get().map_err(|e| Error::String(e.to_string()))?;
 
-        let bot_token_header = HeaderValue::from_str(&format!("Bot {}", bot_token))
-            .map(|h| {
-                let mut default_headers = HeaderMap::new();
-                default_headers.insert(reqwest::header::AUTHORIZATION, h);
-                default_headers
-            })
-            .expect("invalid Discord bot token");
+        // The bot is optional, for apps which only use Discord to log in.
+        let bot = match (bot_token, guild_id) {
+            (Some(bot_token), Some(guild_id)) => {
+                let bot_token_header = HeaderValue::from_str(&format!("Bot {}", bot_token))
+                    .map(|mut h| {
+                        h.set_sensitive(true);
+                        let mut default_headers = HeaderMap::new();
+                        default_headers.insert(reqwest::header::AUTHORIZATION, h);
+                        default_headers
+                    })
+                    .expect("invalid Discord bot token");
+                let guild_id =
+                    NonZeroU64::new(guild_id.parse::<u64>().expect("invalid Discord guild ID"))
+                        .expect("Discord guild ID was 0");
+                let http_api_client = reqwest::Client::builder()
+                    .timeout(Duration::from_secs(3))
+                    .default_headers(bot_token_header)
+                    .build()
+                    .unwrap();
+                Some(DiscordBot {
+                    guild_id,
+                    http_api_client,
+                })
+            }
+            (None, None) => None,
+            _ => {
+                return Err(Error::String(
+                    "Discord bot_token and guild_id must be configured together".to_string(),
+                ))
+            }
+        };
 
         let auth_url = String::from("https://discord.com/api/oauth2/authorize?response_type=code");
         let token_url = String::from("https://discord.com/api/oauth2/token");
 
-        let guild_id = NonZeroU64::new(guild_id.parse::<u64>().expect("invalid Discord guild ID"))
-            .expect("Discord guild ID was 0");
-        let http_api_client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(3))
-            .default_headers(bot_token_header)
-            .build()
-            .unwrap();
         let http_auth_client = reqwest::Client::builder()
             .timeout(Duration::from_secs(4))
             .build()
@@ -87,8 +114,7 @@ impl DiscordOAuth2Service {
         .set_redirect_uri(RedirectUrl::new(redirect_url).expect("invalid redirect URL"));
 
         Ok(Self {
-            guild_id,
-            http_api_client,
+            bot,
             http_auth_client,
             localhost_redirect_url,
             oauth2_client,
@@ -148,7 +174,62 @@ impl DiscordOAuth2Service {
         })
     }
 
-    async fn get_roles_csv(&self, discord_id: NonZeroU64) -> Result<String, Error> {
+    /// Returns the bot, or an error if `bot_token` and `guild_id` aren't configured.
+    fn bot(&self) -> Result<&DiscordBot, Error> {
+        self.bot.as_ref().ok_or_else(|| {
+            Error::String("Discord bot_token and guild_id are not configured".to_string())
+        })
+    }
+
+    /// Sends a bot API request, sleeping and retrying (a bounded number of times) if
+    /// Discord responds 429 Too Many Requests.
+    async fn send_api_request(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, Error> {
+        // https://discord.com/developers/docs/topics/rate-limits#exceeding-a-rate-limit
+        #[derive(Deserialize)]
+        struct RateLimited {
+            retry_after: f64,
+        }
+
+        let mut retries = 0;
+        loop {
+            let attempt = request
+                .try_clone()
+                .ok_or_else(|| Error::String("Discord request not retryable".to_string()))?;
+            let response = attempt
+                .send()
+                .await
+                .map_err(|e| Error::String(e.to_string()))?;
+            if response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS
+                || retries >= RATE_LIMIT_MAX_RETRIES
+            {
+                return Ok(response);
+            }
+            let text = response
+                .text()
+                .await
+                .map_err(|e| Error::String(e.to_string()))?;
+            let retry_after = serde_json::from_str::<RateLimited>(&text)
+                .map(|r| r.retry_after)
+                .unwrap_or(1.0);
+            if DEBUG {
+                println!("Discord rate limited, retry after {retry_after}s: {text}");
+            }
+            if retry_after.is_nan() || retry_after > RATE_LIMIT_MAX_WAIT_SECS {
+                return Err(Error::String(format!(
+                    "Discord rate limited for {retry_after}s: {text}"
+                )));
+            }
+            tokio::time::sleep(Duration::from_secs_f64(retry_after.max(0.0))).await;
+            retries += 1;
+        }
+    }
+
+    async fn get_roles(&self, discord_id: NonZeroU64) -> Result<Vec<String>, Error> {
+        let bot = self.bot()?;
+
         // https://discord.com/developers/docs/resources/guild#guild-member-object
         #[derive(Debug, Deserialize)]
         struct Membership {
@@ -157,18 +238,15 @@ impl DiscordOAuth2Service {
 
         let members_endpoint = format!(
             "https://discord.com/api/guilds/{}/members/{}",
-            self.guild_id, discord_id
+            bot.guild_id, discord_id
         );
         if DEBUG {
             // println!("members_endpoint is {}", members_endpoint);
         }
 
         let response = self
-            .http_api_client
-            .get(members_endpoint)
-            .send()
-            .await
-            .map_err(|e| Error::String(e.to_string()))?;
+            .send_api_request(bot.http_api_client.get(members_endpoint))
+            .await?;
         let status_code = response.status();
         if status_code != reqwest::StatusCode::OK {
             let text = response
@@ -193,17 +271,14 @@ impl DiscordOAuth2Service {
             name: String,
         }
 
-        let roles_endpoint = format!("https://discord.com/api/guilds/{}/roles", self.guild_id);
+        let roles_endpoint = format!("https://discord.com/api/guilds/{}/roles", bot.guild_id);
         if DEBUG {
             println!("roles_endpoint is {}", roles_endpoint);
         }
 
         let roles: Vec<Role> = self
-            .http_api_client
-            .get(roles_endpoint)
-            .send()
-            .await
-            .map_err(|e| Error::String(e.to_string()))?
+            .send_api_request(bot.http_api_client.get(roles_endpoint))
+            .await?
             .json::<Vec<Role>>()
             .await
             .map_err(|e| Error::String(e.to_string()))?;
@@ -222,114 +297,15 @@ impl DiscordOAuth2Service {
             .filter(|name| name.is_some())
             .map(|name| name.unwrap().to_owned())
             .collect();
-        let roles_csv = user_roles.join(",");
         if DEBUG {
-            println!("roles are {roles_csv}");
-        }
-
-        Ok(roles_csv)
-    }
-
-    fn parse_oauth_id(oauth_id: &AuthenticatedId) -> Result<NonZeroU64, Error> {
-        let Some((prefix, discord_id_s)) = oauth_id.as_str().split_once('/') else {
-            return Err(Error::String(format!("{oauth_id}: invalid oauth ID")));
-        };
-        if prefix != "discord" {
-            return Err(Error::String(format!("{oauth_id}: not a Discord ID")));
-        }
-        discord_id_s
-            .parse()
-            .map_err(|_| Error::String(format!("{oauth_id}: invalid number")))
-    }
-}
-
-#[async_trait]
-impl OAuthService for DiscordOAuth2Service {
-    async fn authenticated(&self, code: String) -> Result<Identity, Error> {
-        self.auth_token_to_identity(
-            self.oauth2_client
-                .exchange_code(AuthorizationCode::new(code))
-                .request_async(async_http_client)
-                .await
-                .map_err(|e| Error::String(format!("{e:?}")))?,
-        )
-        .await
-    }
-
-    // For diagnostic purposes.
-    async fn authenticated_by_localhost(&self, code: String) -> Result<Identity, Error> {
-        let Some(localhost_redirect_url) = self.localhost_redirect_url.clone() else {
-            return self.authenticated(code).await;
-        };
-        let Ok(url) = RedirectUrl::new(localhost_redirect_url) else {
-            return self.authenticated(code).await;
-        };
-        let client = self.oauth2_client.clone().set_redirect_uri(url);
-        self.auth_token_to_identity(
-            client
-                .exchange_code(AuthorizationCode::new(code))
-                .request_async(async_http_client)
-                .await
-                .map_err(|e| Error::String(e.to_string()))?,
-        )
-        .await
-    }
-
-    async fn detail(
-        &self,
-        oauth_id: Option<&AuthenticatedId>,
-        name: &str,
-    ) -> Result<String, Error> {
-        match (oauth_id, name) {
-            (Some(oauth_id), "roles") => {
-                let discord_id = Self::parse_oauth_id(oauth_id)?;
-                Ok(self
-                    .get_roles_csv(discord_id)
-                    .await
-                    .map_err(|e| Error::String(format!("cannot get Discord roles: {e}")))?)
-            }
-            _ => Err(Error::String(format!(
-                "{name}: not a supported detail for Discord"
-            ))),
+            println!("roles are {user_roles:?}");
         }
-    }
-
-    fn provider(&self) -> OAuthProvider {
-        OAuthProvider::Discord
-    }
 
-    fn redirect(&self) -> Url {
-        let (auth_url, _csrf_token) = self
-            .oauth2_client
-            .authorize_url(CsrfToken::new_random)
-            .add_scope(Scope::new("identify".to_string()))
-            .url();
-        auth_url
+        Ok(user_roles)
     }
 
-    // For diagnostic purposes.
-    fn redirect_to_localhost(&self) -> Url {
-        let Some(localhost_redirect_url) = self.localhost_redirect_url.clone() else {
-            return self.redirect();
-        };
-        let Ok(url) = RedirectUrl::new(localhost_redirect_url) else {
-            return self.redirect();
-        };
-        let client = self.oauth2_client.clone().set_redirect_uri(url);
-        let (auth_url, _csrf_token) = client
-            .authorize_url(CsrfToken::new_random)
-            .add_scope(Scope::new("identify".to_string()))
-            .url();
-        auth_url
-    }
-
-    async fn send_message(
-        &self,
-        channel_name: &str,
-        message: &str,
-        ping: bool,
-        reply_to_id: Option<NonZeroU64>,
-    ) -> Result<(), Error> {
+    async fn channel_id(&self, channel_name: &str) -> Result<String, Error> {
+        let bot = self.bot()?;
         let channel_id = {
             let cache = self.channel_name_to_id_cache.lock().unwrap();
             cache.get(channel_name).cloned().and_then(|(id, time)| {
@@ -342,8 +318,8 @@ impl OAuthService for DiscordOAuth2Service {
             })
         };
 
-        let channel_id = if let Some(channel_id) = channel_id {
-            channel_id
+        if let Some(channel_id) = channel_id {
+            Ok(channel_id)
         } else {
             #[derive(Deserialize)]
             struct Channel {
@@ -352,14 +328,11 @@ impl OAuthService for DiscordOAuth2Service {
             }
 
             let channels: Vec<Channel> = self
-                .http_api_client
-                .get(format!(
+                .send_api_request(bot.http_api_client.get(format!(
                     "https://discord.com/api/guilds/{}/channels",
-                    self.guild_id
-                ))
-                .send()
-                .await
-                .map_err(|e| Error::String(e.to_string()))?
+                    bot.guild_id
+                )))
+                .await?
                 .json::<Vec<Channel>>()
                 .await
                 .map_err(|e| Error::String(e.to_string()))?;
@@ -375,8 +348,20 @@ impl OAuthService for DiscordOAuth2Service {
                 (channel_id.clone(), NonZeroUnixSeconds::now()),
             );
 
-            channel_id
-        };
+            Ok(channel_id)
+        }
+    }
+
+    async fn post_message(
+        &self,
+        channel_name: &str,
+        message: Option<&str>,
+        embed: Option<&Embed>,
+        ping: bool,
+        reply_to_id: Option<NonZeroU64>,
+    ) -> Result<(), Error> {
+        let bot = self.bot()?;
+        let channel_id = self.channel_id(channel_name).await?;
 
         #[derive(Serialize)]
         struct MessageReference {
@@ -387,7 +372,10 @@ impl OAuthService for DiscordOAuth2Service {
 
         #[derive(Serialize)]
         struct CreateMessage<'a> {
-            content: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            content: Option<&'a str>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            embeds: Vec<&'a Embed>,
             message_reference: Option<MessageReference>,
             #[serde(skip_serializing_if = "is_default")]
             flags: u64,
@@ -395,25 +383,134 @@ impl OAuthService for DiscordOAuth2Service {
 
         let create_message = CreateMessage {
             content: message,
+            embeds: embed.into_iter().collect(),
             message_reference: reply_to_id.map(|id| MessageReference {
                 message_id: id.to_string(),
             }),
             flags: if ping { 0 } else { SUPPRESS_NOTIFICATIONS },
         };
 
-        self.http_api_client
-            .post(format!(
-                "https://discord.com/api/channels/{}/messages",
-                channel_id
-            ))
-            .json(&create_message)
-            .send()
+        // Only one request is sent per message.  Its response is checked so that a
+        // rejected message isn't mistaken for a sent one.
+        let response = self
+            .send_api_request(
+                bot.http_api_client
+                    .post(format!(
+                        "https://discord.com/api/channels/{}/messages",
+                        channel_id
+                    ))
+                    .json(&create_message),
+            )
+            .await?;
+        let status_code = response.status();
+        if !status_code.is_success() {
+            let text = response
+                .text()
+                .await
+                .map_err(|e| Error::String(e.to_string()))?;
+            return Err(Error::String(format!(
+                "Discord message error {status_code}: {text}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the OAuth2 client, redirecting to the URL of the specified kind (if it's
+    /// configured and valid).
+    fn oauth2_client(&self, url_kind: RedirectKind) -> BasicClient {
+        let url = match url_kind {
+            RedirectKind::Production => None,
+            RedirectKind::Localhost => self.localhost_redirect_url.clone(),
+        };
+        match url.map(RedirectUrl::new) {
+            Some(Ok(url)) => self.oauth2_client.clone().set_redirect_uri(url),
+            _ => self.oauth2_client.clone(),
+        }
+    }
+
+    fn parse_oauth_id(oauth_id: &AuthenticatedId) -> Result<NonZeroU64, Error> {
+        let (Some(prefix), Some(discord_id_s)) = (oauth_id.provider(), oauth_id.subject()) else {
+            return Err(Error::String(format!("{oauth_id}: invalid oauth ID")));
+        };
+        if prefix != OAuthProvider::Discord.as_str() {
+            return Err(Error::String(format!("{oauth_id}: not a Discord ID")));
+        }
+        discord_id_s
+            .parse()
+            .map_err(|_| Error::String(format!("{oauth_id}: invalid number")))
+    }
+}
+
+#[async_trait]
+impl OAuthService for DiscordOAuth2Service {
+    async fn authenticated_with(
+        &self,
+        url_kind: RedirectKind,
+        code: String,
+    ) -> Result<Identity, Error> {
+        self.auth_token_to_identity(
+            self.oauth2_client(url_kind)
+                .exchange_code(AuthorizationCode::new(code))
+                .request_async(async_http_client)
+                .await
+                .map_err(|e| Error::String(format!("{e:?}")))?,
+        )
+        .await
+    }
+
+    async fn detail(
+        &self,
+        oauth_id: Option<&AuthenticatedId>,
+        name: &str,
+    ) -> Result<String, Error> {
+        match (oauth_id, name) {
+            (Some(oauth_id), "roles") => Ok(self.roles(oauth_id).await?.join(",")),
+            _ => Err(Error::String(format!(
+                "{name}: not a supported detail for Discord"
+            ))),
+        }
+    }
+
+    fn provider(&self) -> OAuthProvider {
+        OAuthProvider::Discord
+    }
+
+    async fn roles(&self, oauth_id: &AuthenticatedId) -> Result<Vec<String>, Error> {
+        let discord_id = Self::parse_oauth_id(oauth_id)?;
+        self.get_roles(discord_id)
             .await
-            .map_err(|e| Error::String(e.to_string()))?
-            .text()
+            .map_err(|e| Error::String(format!("cannot get Discord roles: {e}")))
+    }
+
+    fn redirect_with(&self, url_kind: RedirectKind) -> Url {
+        let (auth_url, _csrf_token) = self
+            .oauth2_client(url_kind)
+            .authorize_url(CsrfToken::new_random)
+            .add_scope(Scope::new("identify".to_string()))
+            .url();
+        auth_url
+    }
+
+    async fn send_embed(
+        &self,
+        channel_name: &str,
+        embed: &Embed,
+        ping: bool,
+        reply_to_id: Option<NonZeroU64>,
+    ) -> Result<(), Error> {
+        self.post_message(channel_name, None, Some(embed), ping, reply_to_id)
             .await
-            .map_err(|e| Error::String(e.to_string()))?;
+    }
 
-        Ok(())
+    async fn send_message(
+        &self,
+        channel_name: &str,
+        message: &str,
+        ping: bool,
+        reply_to_id: Option<NonZeroU64>,
+    ) -> Result<(), Error> {
+        self.post_message(channel_name, Some(message), None, ping, reply_to_id)
+            .await
     }
 }
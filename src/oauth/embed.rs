@@ -0,0 +1,73 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use crate::serde_utils::is_default;
+use serde::{Deserialize, Serialize};
+
+/// Rich message content, e.g. a Discord embed.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Embed {
+    /// Title.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Description, below the title.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// URL of the title.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Color as `0xRRGGBB`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<u32>,
+    /// Fields, below the description.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<EmbedField>,
+}
+
+/// A name and value within an `Embed`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct EmbedField {
+    /// Name of field.
+    pub name: String,
+    /// Value of field.
+    pub value: String,
+    /// Whether the field may be displayed on the same line as other fields.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub inline: bool,
+}
+
+impl Embed {
+    /// Build color.
+    pub fn color(mut self, value: u32) -> Self {
+        self.color = Some(value);
+        self
+    }
+
+    /// Build description.
+    pub fn description(mut self, value: String) -> Self {
+        self.description = Some(value);
+        self
+    }
+
+    /// Build field.
+    pub fn field(mut self, name: String, value: String, inline: bool) -> Self {
+        self.fields.push(EmbedField {
+            name,
+            value,
+            inline,
+        });
+        self
+    }
+
+    /// Build title.
+    pub fn title(mut self, value: String) -> Self {
+        self.title = Some(value);
+        self
+    }
+
+    /// Build URL.
+    pub fn url(mut self, value: String) -> Self {
+        self.url = Some(value);
+        self
+    }
+}
@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::{OAuthProvider, OAuthService, Url};
+use super::{Embed, OAuthProvider, OAuthService, RedirectKind, Url};
 use crate::common::{AuthenticatedId, CubConfig, Error, Identity, UserName};
 use async_trait::async_trait;
 use reqwest::Method;
@@ -46,23 +46,18 @@ impl GoogleOAuth2Service {
         })
     }
 
-    async fn authenticated_by(
-        &self,
-        redirect_url: &String,
-        code: &String,
-    ) -> Result<Identity, Error> {
+    async fn authenticated_by(&self, redirect_url: &str, code: &str) -> Result<Identity, Error> {
         let GoogleOAuth2Service {
             client_id,
             client_secret,
             ..
         } = self;
 
-        let grant_type = "authorization_code".to_string();
-        let token_payload: Vec<(&'static str, &String)> = vec![
+        let token_payload: Vec<(&'static str, &str)> = vec![
             ("client_id", client_id),
             ("client_secret", client_secret),
             ("code", code),
-            ("grant_type", &grant_type),
+            ("grant_type", "authorization_code"),
             ("redirect_uri", redirect_url),
         ];
 
@@ -126,6 +121,10 @@ impl GoogleOAuth2Service {
         })
     }
 
+    fn redirect_url(&self, url_kind: RedirectKind) -> &str {
+        url_kind.select(&self.redirect_url, self.localhost_redirect_url.as_deref())
+    }
+
     fn create_http_client() -> Result<reqwest::Client, Error> {
         reqwest::Client::builder()
             .timeout(Duration::from_secs(3))
@@ -133,7 +132,7 @@ impl GoogleOAuth2Service {
             .map_err(|e| Error::String(format!("cannot create http client: {e}")))
     }
 
-    fn redirect_to(&self, redirect_url: &String) -> Url {
+    fn redirect_to(&self, redirect_url: &str) -> Url {
         let GoogleOAuth2Service { client_id, .. } = self;
         let response_type = "code";
         let scope = "openid email";
@@ -146,23 +145,13 @@ impl GoogleOAuth2Service {
 
 #[async_trait]
 impl OAuthService for GoogleOAuth2Service {
-    async fn authenticated(&self, code: String) -> Result<Identity, Error> {
-        let GoogleOAuth2Service { redirect_url, .. } = self;
-        self.authenticated_by(redirect_url, &code).await
-    }
-
-    // For diagnostic purposes.
-    async fn authenticated_by_localhost(&self, code: String) -> Result<Identity, Error> {
-        let GoogleOAuth2Service {
-            localhost_redirect_url,
-            redirect_url,
-            ..
-        } = self;
-        if let Some(localhost_redirect_url) = localhost_redirect_url {
-            self.authenticated_by(localhost_redirect_url, &code).await
-        } else {
-            self.authenticated_by(redirect_url, &code).await
-        }
+    async fn authenticated_with(
+        &self,
+        url_kind: RedirectKind,
+        code: String,
+    ) -> Result<Identity, Error> {
+        self.authenticated_by(self.redirect_url(url_kind), &code)
+            .await
     }
 
     async fn detail(
@@ -179,23 +168,20 @@ impl OAuthService for GoogleOAuth2Service {
         OAuthProvider::Google
     }
 
-    fn redirect(&self) -> Url {
-        let GoogleOAuth2Service { redirect_url, .. } = self;
-        self.redirect_to(&redirect_url)
+    fn redirect_with(&self, url_kind: RedirectKind) -> Url {
+        self.redirect_to(self.redirect_url(url_kind))
     }
 
-    // For diagnostic purposes.
-    fn redirect_to_localhost(&self) -> Url {
-        let GoogleOAuth2Service {
-            localhost_redirect_url,
-            redirect_url,
-            ..
-        } = self;
-        if let Some(localhost_redirect_url) = localhost_redirect_url {
-            self.redirect_to(localhost_redirect_url)
-        } else {
-            self.redirect_to(redirect_url)
-        }
+    async fn send_embed(
+        &self,
+        channel_name: &str,
+        _embed: &Embed,
+        _ping: bool,
+        _reply_to_id: Option<NonZeroU64>,
+    ) -> Result<(), Error> {
+        Err(Error::String(format!(
+            "{channel_name}: not a supported channel for Google"
+        )))
     }
 
     async fn send_message(
@@ -1,12 +1,18 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+mod apple;
 /// A client of one or more OAuth2 provider APIs.
 mod client;
 mod discord;
+/// Rich message content.
+mod embed;
 mod google;
 /// A wrapper around a particular OAuth2 provider API.
 mod provider;
+/// Unit tests.
+mod tests;
 
 pub use self::client::{new_oauth_client, OAuthClient, Url};
-pub use self::provider::{OAuthProvider, OAuthService};
+pub use self::embed::{Embed, EmbedField};
+pub use self::provider::{OAuthProvider, OAuthService, RedirectKind};
@@ -1,27 +1,63 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::Url;
+use super::{Embed, Url};
 use crate::common::{AuthenticatedId, Error, Identity};
 use async_trait::async_trait;
+use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use std::num::NonZeroU64;
+use std::str::FromStr;
 
 /// The `OAuthProvider` enum contains the list of supported `OAuth2` providers.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Hash, Serialize)]
 pub enum OAuthProvider {
+    /// https://apple.com
+    Apple,
     /// https://discord.com
     Discord,
     /// https://google.com
     Google,
 }
 
+impl OAuthProvider {
+    /// Returns the provider's human-readable name, e.g. "Google".
+    pub fn as_display_name(&self) -> &'static str {
+        match self {
+            Self::Apple => "Apple",
+            Self::Discord => "Discord",
+            Self::Google => "Google",
+        }
+    }
+
+    /// Returns the provider's name as used in `AuthenticatedId` prefixes, e.g. "google".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Apple => "apple",
+            Self::Discord => "discord",
+            Self::Google => "google",
+        }
+    }
+}
+
+/// Displays the human-readable name, e.g. "Google".  Use `as_str` to persist.
 impl Display for OAuthProvider {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
-        match self {
-            Self::Discord => Display::fmt("Discord", f),
-            Self::Google => Display::fmt("Google", f),
+        Display::fmt(self.as_display_name(), f)
+    }
+}
+
+/// Parse provider name (case insensitive) into `OAuthProvider` enum.
+impl FromStr for OAuthProvider {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_ref() {
+            "apple" => Ok(OAuthProvider::Apple),
+            "discord" => Ok(OAuthProvider::Discord),
+            "google" => Ok(OAuthProvider::Google),
+            _ => Err(Error::String(format!("{s}: not an oauth2 provider"))),
         }
     }
 }
@@ -30,13 +66,31 @@ impl Display for OAuthProvider {
 impl TryFrom<String> for OAuthProvider {
     type Error = Error;
     fn try_from(oauth_provider: String) -> Result<Self, Error> {
-        match oauth_provider.as_str() {
-            "Discord" | "discord" => Ok(OAuthProvider::Discord),
-            "Google" | "google" => Ok(OAuthProvider::Google),
-            _ => Err(Error::String(format!(
-                "{}: not an oauth2 provider",
-                oauth_provider
-            ))),
+        oauth_provider.parse()
+    }
+}
+
+/// Where an OAuth2 provider redirects back to after the user signs in.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum RedirectKind {
+    /// The configured `redirect_url`.
+    #[default]
+    Production,
+    /// For diagnostic purposes, the configured `localhost_redirect_url` (or the
+    /// `redirect_url` if there isn't one).
+    Localhost,
+}
+
+impl RedirectKind {
+    /// Selects the redirect URL of this kind.
+    pub(crate) fn select<'a>(
+        self,
+        redirect_url: &'a str,
+        localhost_redirect_url: Option<&'a str>,
+    ) -> &'a str {
+        match self {
+            Self::Production => redirect_url,
+            Self::Localhost => localhost_redirect_url.unwrap_or(redirect_url),
         }
     }
 }
@@ -45,18 +99,71 @@ impl TryFrom<String> for OAuthProvider {
 #[async_trait]
 pub trait OAuthService {
     /// Handles the callback from an OAuth2 provider.
-    async fn authenticated(&self, code: String) -> Result<Identity, Error>;
+    async fn authenticated(&self, code: String) -> Result<Identity, Error> {
+        self.authenticated_with(RedirectKind::Production, code)
+            .await
+    }
+    /// Handles the callback from an OAuth2 provider, which must have been reached via
+    /// the `redirect_with` of the same `RedirectKind`.
+    async fn authenticated_with(
+        &self,
+        url_kind: RedirectKind,
+        code: String,
+    ) -> Result<Identity, Error>;
+    /// Handles the callback from an OAuth2 provider, but fails unless the user has the
+    /// specified role (e.g. a role in the Discord guild).
+    async fn authenticated_with_required_role(
+        &self,
+        code: String,
+        role_name: &str,
+    ) -> Result<Identity, Error> {
+        let identity = self.authenticated(code).await?;
+        let roles = self.roles(&identity.login_id).await?;
+        if roles.iter().any(|role| role == role_name) {
+            Ok(identity)
+        } else {
+            Err(Error::Http(
+                StatusCode::FORBIDDEN,
+                format!("{}: lacks required role {role_name}", identity.login_id),
+            ))
+        }
+    }
     /// For diagnostic purposes.
-    async fn authenticated_by_localhost(&self, code: String) -> Result<Identity, Error>;
+    async fn authenticated_by_localhost(&self, code: String) -> Result<Identity, Error> {
+        self.authenticated_with(RedirectKind::Localhost, code).await
+    }
     /// Returns provider-specific details.
     async fn detail(&self, oauth_id: Option<&AuthenticatedId>, name: &str)
         -> Result<String, Error>;
     /// Returns provider.
     fn provider(&self) -> OAuthProvider;
+    /// Returns the names of the roles of the specified user (e.g. in the Discord guild),
+    /// if the provider supports roles.
+    async fn roles(&self, oauth_id: &AuthenticatedId) -> Result<Vec<String>, Error> {
+        Err(Error::String(format!(
+            "{oauth_id}: roles are not supported by {}",
+            self.provider()
+        )))
+    }
     /// Returns a `Url` that redirects to the specified OAuth2 provider.
-    fn redirect(&self) -> Url;
-    /// For diagnostic purposes.  Only supported for Discord.
-    fn redirect_to_localhost(&self) -> Url;
+    fn redirect(&self) -> Url {
+        self.redirect_with(RedirectKind::Production)
+    }
+    /// Returns a `Url` that redirects to the specified OAuth2 provider, which will
+    /// redirect back to the URL of the specified kind.
+    fn redirect_with(&self, url_kind: RedirectKind) -> Url;
+    /// For diagnostic purposes.
+    fn redirect_to_localhost(&self) -> Url {
+        self.redirect_with(RedirectKind::Localhost)
+    }
+    /// Sends a rich message via the provider, if possible.
+    async fn send_embed(
+        &self,
+        channel_name: &str,
+        embed: &Embed,
+        ping: bool,
+        reply_to_id: Option<NonZeroU64>,
+    ) -> Result<(), Error>;
     /// Sends a message via the provider, if possible.
     async fn send_message(
         &self,
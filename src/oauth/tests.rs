@@ -0,0 +1,208 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+#[cfg(test)]
+mod oauth_tests {
+    use crate::common::{AuthenticatedId, CubConfig, Error, Identity};
+    use crate::oauth::discord::DiscordOAuth2Service;
+    use crate::oauth::{Embed, OAuthClient, OAuthProvider, OAuthService, RedirectKind, Url};
+    use async_trait::async_trait;
+    use std::num::NonZeroU64;
+
+    #[test]
+    fn authenticated_id_tests() {
+        let id = AuthenticatedId("google/123".to_string());
+        assert_eq!(id.provider(), Some("google"));
+        assert_eq!(id.subject(), Some("123"));
+        let provider: OAuthProvider = id.provider().unwrap().parse().unwrap();
+        assert_eq!(provider, OAuthProvider::Google);
+        for invalid in ["google", "/123", "google/", ""] {
+            let id = AuthenticatedId(invalid.to_string());
+            assert_eq!(id.provider(), None);
+            assert_eq!(id.subject(), None);
+        }
+    }
+
+    #[test]
+    fn provider_round_trip_tests() {
+        for provider in [
+            OAuthProvider::Apple,
+            OAuthProvider::Discord,
+            OAuthProvider::Google,
+        ] {
+            let s = provider.as_str();
+            assert_eq!(s.parse::<OAuthProvider>().unwrap(), provider);
+            let display_name = provider.to_string();
+            assert_eq!(display_name, provider.as_display_name());
+            assert_eq!(display_name.parse::<OAuthProvider>().unwrap(), provider);
+        }
+        assert_eq!(
+            OAuthProvider::try_from("google".to_string()).unwrap(),
+            OAuthProvider::Google
+        );
+        assert_eq!(OAuthProvider::Apple.to_string(), "Apple");
+        assert_eq!(OAuthProvider::Discord.to_string(), "Discord");
+        assert!("myspace".parse::<OAuthProvider>().is_err());
+    }
+
+    #[tokio::test]
+    async fn unconfigured_provider_tests() {
+        let cub_config = CubConfig::builder()
+            .toml_str("")
+            .build()
+            .expect("unconfigured_provider_tests.toml");
+        let client = OAuthClient::new(&cub_config);
+        assert!(client.providers().is_empty());
+        assert!(client.redirect(OAuthProvider::Google).is_err());
+        assert!(client
+            .authenticate(OAuthProvider::Discord, "code".to_string())
+            .await
+            .is_err());
+    }
+
+    /// A service whose every user has the same roles.
+    struct RolesService(Vec<String>);
+
+    #[async_trait]
+    impl OAuthService for RolesService {
+        async fn authenticated_with(
+            &self,
+            _url_kind: RedirectKind,
+            code: String,
+        ) -> Result<Identity, Error> {
+            Ok(Identity {
+                login_id: AuthenticatedId(format!("discord/{code}")),
+                user_name: None,
+            })
+        }
+
+        async fn detail(
+            &self,
+            _oauth_id: Option<&AuthenticatedId>,
+            name: &str,
+        ) -> Result<String, Error> {
+            Err(Error::String(format!("{name}: not supported")))
+        }
+
+        fn provider(&self) -> OAuthProvider {
+            OAuthProvider::Discord
+        }
+
+        async fn roles(&self, _oauth_id: &AuthenticatedId) -> Result<Vec<String>, Error> {
+            Ok(self.0.clone())
+        }
+
+        fn redirect_with(&self, _url_kind: RedirectKind) -> Url {
+            Url::parse("https://example.com").unwrap()
+        }
+
+        async fn send_embed(
+            &self,
+            _channel_name: &str,
+            _embed: &Embed,
+            _ping: bool,
+            _reply_to_id: Option<NonZeroU64>,
+        ) -> Result<(), Error> {
+            Err(Error::String("not supported".to_string()))
+        }
+
+        async fn send_message(
+            &self,
+            _channel_name: &str,
+            _message: &str,
+            _ping: bool,
+            _reply_to_id: Option<NonZeroU64>,
+        ) -> Result<(), Error> {
+            Err(Error::String("not supported".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn required_role_tests() {
+        let service = RolesService(vec!["Admins, Owners".to_string(), "Members".to_string()]);
+        let required = |role_name: &'static str| {
+            service.authenticated_with_required_role("123".to_string(), role_name)
+        };
+        let identity = required("Members").await.expect("has role");
+        assert_eq!(identity.login_id.0, "discord/123");
+        assert!(required("Admins, Owners").await.is_ok());
+        // Part of a role name containing a comma isn't a role.
+        assert!(required("Owners").await.is_err());
+        assert!(required("Admins").await.is_err());
+        assert!(required("").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn discord_without_bot_tests() {
+        let login_only = r#"
+            [discord]
+            client_id = "1234"
+            client_secret = "secret"
+            redirect_url = "https://example.com/oauth2/discord"
+        "#;
+        let cub_config = CubConfig::builder()
+            .toml_str(login_only)
+            .build()
+            .expect("discord_without_bot_tests.toml");
+        let discord = DiscordOAuth2Service::new(&cub_config).expect("login only");
+        assert!(discord
+            .send_message("general", "hello", false, None)
+            .await
+            .is_err());
+
+        let cub_config = CubConfig::builder()
+            .toml_str(&format!("{login_only}bot_token = \"token\"\n"))
+            .build()
+            .expect("discord_without_bot_tests.toml");
+        assert!(DiscordOAuth2Service::new(&cub_config).is_err());
+    }
+
+    #[test]
+    fn redirect_kind_tests() {
+        let config = |localhost: &str| {
+            CubConfig::builder()
+                .toml_str(&format!(
+                    r#"
+                    [google]
+                    client_id = "1234"
+                    client_secret = "secret"
+                    redirect_url = "https://example.com/oauth2/google"
+                    {localhost}
+                "#
+                ))
+                .build()
+                .expect("redirect_kind_tests.toml")
+        };
+        let client = OAuthClient::new(&config(
+            r#"localhost_redirect_url = "http://localhost:8080/oauth2/google""#,
+        ));
+        let redirect_uri = |kind| {
+            let url = client.redirect_with(OAuthProvider::Google, kind).unwrap();
+            url.query_pairs()
+                .find(|(k, _)| k == "redirect_uri")
+                .map(|(_, v)| v.into_owned())
+                .unwrap()
+        };
+        assert_eq!(
+            redirect_uri(RedirectKind::Production),
+            "https://example.com/oauth2/google"
+        );
+        assert_eq!(
+            redirect_uri(RedirectKind::Localhost),
+            "http://localhost:8080/oauth2/google"
+        );
+        assert_eq!(
+            client.redirect(OAuthProvider::Google).unwrap(),
+            client
+                .redirect_with(OAuthProvider::Google, RedirectKind::Production)
+                .unwrap()
+        );
+
+        // Without a localhost URL, it falls back to production.
+        let client = OAuthClient::new(&config(""));
+        let url = client
+            .redirect_with(OAuthProvider::Google, RedirectKind::Localhost)
+            .unwrap();
+        assert!(url.as_str().contains("example.com"));
+    }
+}
@@ -4,6 +4,9 @@
 /// Macros for serializing default values.
 mod defaults;
 
+/// Unit tests.
+mod tests;
+
 /// Macros for serializing tuples.
 mod tuples;
 
@@ -0,0 +1,31 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+#[cfg(all(test, feature = "serde_json"))]
+mod serde_utils_tests {
+    use crate::serde_utils::deserialize_string_or_number;
+    use serde::Deserialize;
+
+    #[test]
+    fn string_or_number_tests() {
+        #[derive(Debug, Deserialize)]
+        struct Record {
+            #[serde(deserialize_with = "deserialize_string_or_number")]
+            id: String,
+            #[serde(deserialize_with = "deserialize_string_or_number")]
+            count: u64,
+        }
+        for (json, id, count) in [
+            (r#"{"id": "abc", "count": "7"}"#, "abc", 7),
+            (r#"{"id": 123, "count": 7}"#, "123", 7),
+            (r#"{"id": -5, "count": "0"}"#, "-5", 0),
+            (r#"{"id": 1.5, "count": 42}"#, "1.5", 42),
+        ] {
+            let record: Record = serde_json::from_str(json).expect(json);
+            assert_eq!(record.id, id);
+            assert_eq!(record.count, count);
+        }
+        assert!(serde_json::from_str::<Record>(r#"{"id": "x", "count": "many"}"#).is_err());
+        assert!(serde_json::from_str::<Record>(r#"{"id": true, "count": 1}"#).is_err());
+    }
+}
@@ -39,6 +39,37 @@ macro_rules! impl_wrapper_from_str {
     };
 }
 
+/// Implement `Deref`, `DerefMut` and `AsRef` to the inner type for wrapper tuples
+/// around any type, e.g. `Vec<u8>` or `Option<String>`.
+///
+/// # Example
+///
+/// `pub struct MyWrapper(pub Vec<u8>);`
+/// `impl_wrapper_deref!(MyWrapper, Vec<u8>);`
+#[macro_export]
+macro_rules! impl_wrapper_deref {
+    ($typ:ty, $inner:ty) => {
+        impl std::ops::Deref for $typ {
+            type Target = $inner;
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl std::ops::DerefMut for $typ {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+
+        impl AsRef<$inner> for $typ {
+            fn as_ref(&self) -> &$inner {
+                &self.0
+            }
+        }
+    };
+}
+
 /// Implement `Display` and `FromStr` for wrapper tuples.
 ///
 /// # Example
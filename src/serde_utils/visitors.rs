@@ -3,7 +3,7 @@
 
 use core::marker::PhantomData;
 use serde::de;
-use serde::de::Visitor;
+use serde::de::{Deserializer, Visitor};
 use std::fmt::{self, Display};
 use std::str::FromStr;
 
@@ -55,3 +55,53 @@ impl<'de, T: FromStr<Err: Display>> Visitor<'de> for FromStrVisitor<T> {
         T::from_str(value).map_err(|e| serde::de::Error::custom(e))
     }
 }
+
+/// Deserializes a field which may be either a string or a number into any type that
+/// implements `FromStr`, e.g. a `String` or `u64`.  For example:
+///     `#[serde(deserialize_with = "deserialize_string_or_number")]`
+pub fn deserialize_string_or_number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err: Display>,
+{
+    deserializer.deserialize_any(StringOrNumberVisitor::<T>(PhantomData))
+}
+
+/// Deserializes a string or number into any type that implements `FromStr`.
+struct StringOrNumberVisitor<T>(PhantomData<T>);
+
+impl<'de, T: FromStr<Err: Display>> Visitor<'de> for StringOrNumberVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a str or number")
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        T::from_str(value).map_err(serde::de::Error::custom)
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_str(&value.to_string())
+    }
+}
@@ -0,0 +1,321 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::ObjectStore;
+use crate::common::{CubConfig, Error};
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hyper::StatusCode;
+use jsonwebtoken::{crypto, encode, Algorithm, EncodingKey, Header};
+use reqwest::{Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const GCS_HOST: &str = "storage.googleapis.com";
+const GCS_SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+const GCS_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+/// Pre-signed URLs expire in 15 minutes, the same as those of S3.
+const PRESIGNED_EXPIRY_SECS: u64 = 900;
+
+/// This struct implements `ObjectStore` for Google Cloud Storage.
+pub struct GcsClient {
+    /// An access token and when it expires, if one has been obtained.
+    access_token: Mutex<Option<(String, Instant)>>,
+    client: Client,
+    /// The email address of the service account.
+    client_email: String,
+    encoding_key: EncodingKey,
+}
+
+impl GcsClient {
+    const TIMEOUT_SECS: u64 = 30;
+
+    /// Create an `ObjectStore` for Google Cloud Storage.  The `gcs` config must have
+    /// the `client_email` and `private_key_pem` of a service account.
+    pub fn new(cub_config: &CubConfig) -> Result<Self, Error> {
+        #[derive(Deserialize)]
+        struct GcsConfig {
+            client_email: String,
+            /// The `private_key` of the JSON key file downloaded from Google.
+            private_key_pem: String,
+        }
+        #[derive(Deserialize)]
+        struct ConfigToml {
+            gcs: GcsConfig,
+        }
+        let ConfigToml {
+            gcs:
+                GcsConfig {
+                    client_email,
+                    private_key_pem,
+                },
+        } = cub_config.get()?;
+        let encoding_key = EncodingKey::from_rsa_pem(private_key_pem.as_bytes())
+            .map_err(|e| Error::String(format!("cannot parse GCS private key: {e:?}")))?;
+        Ok(Self {
+            access_token: Mutex::new(None),
+            client: Client::builder()
+                .timeout(Duration::from_secs(Self::TIMEOUT_SECS))
+                .build()
+                .map_err(Self::map_error)?,
+            client_email,
+            encoding_key,
+        })
+    }
+
+    /// Returns a (possibly cached) OAuth2 access token for the service account.
+    async fn access_token(&self) -> Result<String, Error> {
+        if let Some((token, expiry)) = self.access_token.lock().unwrap().as_ref() {
+            if Instant::now() < *expiry {
+                return Ok(token.clone());
+            }
+        }
+        #[derive(Serialize)]
+        struct AssertionClaims<'a> {
+            aud: &'static str,
+            exp: u64,
+            iat: u64,
+            iss: &'a str,
+            scope: &'static str,
+        }
+        let now = Self::unix_seconds();
+        let claims = AssertionClaims {
+            aud: GCS_TOKEN_URL,
+            exp: now + 3600,
+            iat: now,
+            iss: &self.client_email,
+            scope: GCS_SCOPE,
+        };
+        let assertion = encode(&Header::new(Algorithm::RS256), &claims, &self.encoding_key)
+            .map_err(|e| Error::String(format!("cannot create GCS assertion: {e:?}")))?;
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            access_token: String,
+            expires_in: u64,
+        }
+        let request = self.client.post(GCS_TOKEN_URL).form(&[
+            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+            ("assertion", &assertion),
+        ]);
+        let response = request.send().await.map_err(Self::map_error)?;
+        let TokenResponse {
+            access_token,
+            expires_in,
+        } = Self::parse_response(response).await?;
+        // Renew the token a minute early, rather than risk using it as it expires.
+        let expiry = Instant::now() + Duration::from_secs(expires_in.saturating_sub(60));
+        *self.access_token.lock().unwrap() = Some((access_token.clone(), expiry));
+        Ok(access_token)
+    }
+
+    /// Returns the V4 canonical request and its query string for a pre-signed URL.
+    pub(super) fn canonical_request(
+        method: &str,
+        path: &str,
+        client_email: &str,
+        datetime: &str,
+    ) -> (String, String) {
+        let credential = format!("{client_email}/{}", Self::credential_scope(datetime));
+        let expires = PRESIGNED_EXPIRY_SECS.to_string();
+        // Parameters must be sorted by name.
+        let query = [
+            ("X-Goog-Algorithm", "GOOG4-RSA-SHA256"),
+            ("X-Goog-Credential", credential.as_str()),
+            ("X-Goog-Date", datetime),
+            ("X-Goog-Expires", expires.as_str()),
+            ("X-Goog-SignedHeaders", "host"),
+        ]
+        .iter()
+        .map(|(name, value)| format!("{name}={}", urlencoding::encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+        let canonical_request =
+            format!("{method}\n{path}\n{query}\nhost:{GCS_HOST}\n\nhost\nUNSIGNED-PAYLOAD");
+        (canonical_request, query)
+    }
+
+    fn credential_scope(datetime: &str) -> String {
+        format!("{}/auto/storage/goog4_request", &datetime[..8])
+    }
+
+    /// Returns the path of an object, in which each segment of the key is encoded.
+    pub(super) fn object_path(bucket: &str, key: &str) -> String {
+        let key = key
+            .split('/')
+            .map(|segment| urlencoding::encode(segment).into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        format!("/{bucket}/{key}")
+    }
+
+    fn map_error(e: reqwest::Error) -> Error {
+        Error::Http(StatusCode::FAILED_DEPENDENCY, format!("{}", e))
+    }
+
+    /// Parses a GCS response, inspecting its status before its body.
+    async fn parse_response<T: DeserializeOwned>(response: Response) -> Result<T, Error> {
+        let status = response.status();
+        let text = response.text().await.map_err(Self::map_error)?;
+        if !status.is_success() {
+            return Err(Self::status_error(status, &text));
+        }
+        serde_json::from_str(&text).map_err(|e| {
+            Error::Http(
+                StatusCode::FAILED_DEPENDENCY,
+                format!("cannot parse gcs response: {e}"),
+            )
+        })
+    }
+
+    /// Returns a V4 pre-signed URL, signed by the service account.
+    /// https://cloud.google.com/storage/docs/access-control/signing-urls-manually
+    fn presigned_url(&self, method: &str, bucket: &str, key: &str) -> Result<String, Error> {
+        // E.g. "20240102T030405Z".
+        let datetime = DateTime::<Utc>::from_timestamp(Self::unix_seconds() as i64, 0)
+            .ok_or_else(|| Error::String("cannot format GCS date".to_owned()))?
+            .format("%Y%m%dT%H%M%SZ")
+            .to_string();
+        let path = Self::object_path(bucket, key);
+        let (canonical_request, query) =
+            Self::canonical_request(method, &path, &self.client_email, &datetime);
+        let string_to_sign = format!(
+            "GOOG4-RSA-SHA256\n{datetime}\n{}\n{}",
+            Self::credential_scope(&datetime),
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let signature = crypto::sign(
+            string_to_sign.as_bytes(),
+            &self.encoding_key,
+            Algorithm::RS256,
+        )
+        .map_err(|e| Error::String(format!("cannot sign GCS URL: {e:?}")))?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|e| Error::String(format!("cannot decode GCS signature: {e}")))?;
+        Ok(format!(
+            "https://{GCS_HOST}{path}?{query}&X-Goog-Signature={}",
+            hex::encode(signature)
+        ))
+    }
+
+    /// Sends an authorized request.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        let access_token = self.access_token().await?;
+        request
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(Self::map_error)
+    }
+
+    fn status_error(status: StatusCode, text: &str) -> Error {
+        #[derive(Deserialize)]
+        struct GcsReason {
+            message: String,
+        }
+        #[derive(Deserialize)]
+        struct GcsError {
+            error: GcsReason,
+        }
+        let message = match serde_json::from_str(text) {
+            Ok(GcsError {
+                error: GcsReason { message },
+            }) => format!("gcs error: {message}"),
+            Err(_) => format!("cannot parse gcs error: {text}"),
+        };
+        Error::from_provider_status(status, message)
+    }
+
+    fn unix_seconds() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+#[async_trait]
+impl ObjectStore for GcsClient {
+    async fn get_item(&self, bucket: &str, key: &str) -> Result<Vec<u8>, Error> {
+        let endpoint = format!(
+            "https://{GCS_HOST}/storage/v1/b/{bucket}/o/{}?alt=media",
+            urlencoding::encode(key)
+        );
+        let response = self.send(self.client.get(endpoint)).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.map_err(Self::map_error)?;
+            return Err(Self::status_error(status, &text));
+        }
+        let bytes = response.bytes().await.map_err(Self::map_error)?;
+        Ok(bytes.to_vec())
+    }
+
+    async fn list_bucket(&self, bucket: &str) -> Result<Vec<String>, Error> {
+        #[derive(Deserialize)]
+        struct GcsObject {
+            name: String,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ListGcsObjectsResponse {
+            #[serde(default)]
+            items: Vec<GcsObject>,
+            next_page_token: Option<String>,
+        }
+        let endpoint = format!("https://{GCS_HOST}/storage/v1/b/{bucket}/o");
+        let mut keys = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self
+                .client
+                .get(&endpoint)
+                .query(&[("fields", "items(name),nextPageToken")]);
+            if let Some(page_token) = &page_token {
+                request = request.query(&[("pageToken", page_token)]);
+            }
+            let response = self.send(request).await?;
+            let ListGcsObjectsResponse {
+                items,
+                next_page_token,
+            } = Self::parse_response(response).await?;
+            keys.extend(items.into_iter().map(|GcsObject { name }| name));
+            if next_page_token.is_none() {
+                return Ok(keys);
+            }
+            page_token = next_page_token;
+        }
+    }
+
+    async fn presigned_download_url(&self, bucket: &str, key: &str) -> Result<String, Error> {
+        self.presigned_url("GET", bucket, key)
+    }
+
+    async fn presigned_upload_url(&self, bucket: &str, key: &str) -> Result<String, Error> {
+        self.presigned_url("PUT", bucket, key)
+    }
+
+    async fn put_item(
+        &self,
+        bucket: &str,
+        key: &str,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), Error> {
+        let endpoint = format!("https://{GCS_HOST}/upload/storage/v1/b/{bucket}/o");
+        let request = self
+            .client
+            .post(endpoint)
+            .query(&[("uploadType", "media"), ("name", key)])
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(data);
+        let response = self.send(request).await?;
+        let _: serde_json::Value = Self::parse_response(response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+/// Support for Google Cloud Storage.
+mod gcs;
+/// Object store trait
+mod object_store;
+/// Support for AWS S3.
+#[cfg(feature = "aws")]
+mod s3;
+/// Unit tests
+mod tests;
+
+pub use self::gcs::GcsClient;
+pub use self::object_store::ObjectStore;
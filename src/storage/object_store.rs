@@ -0,0 +1,30 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use crate::common::Error;
+use async_trait::async_trait;
+
+/// Object store trait, implemented by `GcsClient` and, with the `aws` feature, `S3Client`.
+#[async_trait]
+pub trait ObjectStore {
+    /// Retrieves an object from the specified bucket.
+    async fn get_item(&self, bucket: &str, key: &str) -> Result<Vec<u8>, Error>;
+
+    /// Lists the keys of the objects in the specified bucket.
+    async fn list_bucket(&self, bucket: &str) -> Result<Vec<String>, Error>;
+
+    /// Returns a pre-signed URL, valid for 15 minutes, to download an object.
+    async fn presigned_download_url(&self, bucket: &str, key: &str) -> Result<String, Error>;
+
+    /// Returns a pre-signed URL, valid for 15 minutes, to upload an object.
+    async fn presigned_upload_url(&self, bucket: &str, key: &str) -> Result<String, Error>;
+
+    /// Puts an object into the specified bucket.
+    async fn put_item(
+        &self,
+        bucket: &str,
+        key: &str,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), Error>;
+}
@@ -0,0 +1,39 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::ObjectStore;
+use crate::aws::{
+    get_s3_item, list_s3_bucket, presigned_s3_download_url, presigned_s3_upload_url, put_s3_item,
+    S3Client,
+};
+use crate::common::Error;
+use async_trait::async_trait;
+
+#[async_trait]
+impl ObjectStore for S3Client {
+    async fn get_item(&self, bucket: &str, key: &str) -> Result<Vec<u8>, Error> {
+        Ok(get_s3_item(self, bucket, key, None).await?.data)
+    }
+
+    async fn list_bucket(&self, bucket: &str) -> Result<Vec<String>, Error> {
+        list_s3_bucket(self, bucket).await
+    }
+
+    async fn presigned_download_url(&self, bucket: &str, key: &str) -> Result<String, Error> {
+        presigned_s3_download_url(self, bucket, key).await
+    }
+
+    async fn presigned_upload_url(&self, bucket: &str, key: &str) -> Result<String, Error> {
+        presigned_s3_upload_url(self, bucket, key).await
+    }
+
+    async fn put_item(
+        &self,
+        bucket: &str,
+        key: &str,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), Error> {
+        put_s3_item(self, bucket, key, data, content_type).await
+    }
+}
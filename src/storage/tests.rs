@@ -0,0 +1,55 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+#[cfg(test)]
+mod storage_tests {
+    use crate::storage::gcs::GcsClient;
+
+    #[test]
+    fn gcs_canonical_request_tests() {
+        let path = GcsClient::object_path("my-bucket", "videos/a b+c.mp4");
+        assert_eq!(path, "/my-bucket/videos/a%20b%2Bc.mp4");
+        let (canonical_request, query) = GcsClient::canonical_request(
+            "GET",
+            &path,
+            "uploader@example.iam.gserviceaccount.com",
+            "20240102T030405Z",
+        );
+        assert_eq!(
+            query,
+            "X-Goog-Algorithm=GOOG4-RSA-SHA256\
+             &X-Goog-Credential=uploader%40example.iam.gserviceaccount.com%2F20240102%2Fauto%2Fstorage%2Fgoog4_request\
+             &X-Goog-Date=20240102T030405Z\
+             &X-Goog-Expires=900\
+             &X-Goog-SignedHeaders=host"
+        );
+        assert_eq!(
+            canonical_request,
+            format!(
+                "GET\n/my-bucket/videos/a%20b%2Bc.mp4\n{query}\nhost:storage.googleapis.com\n\nhost\nUNSIGNED-PAYLOAD"
+            )
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    #[should_panic]
+    async fn gcs_tests() {
+        use crate::common::CubConfig;
+        use crate::storage::ObjectStore;
+
+        let cub_config = CubConfig::builder()
+            .toml_str(
+                r#"
+                [gcs]
+                client_email = "TBD"
+                private_key_pem = "TBD"
+                "#,
+            )
+            .build()
+            .expect("gcs_tests.toml");
+        let gcs_client = GcsClient::new(&cub_config).expect("GCS client");
+        let keys = gcs_client.list_bucket("TBD").await.expect("list bucket");
+        println!("{keys:?}");
+    }
+}
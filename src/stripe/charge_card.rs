@@ -83,6 +83,31 @@ pub struct ChargeCard {
     pub number: Option<u64>,
 }
 
+impl ChargeCard {
+    /// Summarizes the card's validation checks for fraud rules.  Any failed check is
+    /// `CardRisk::High`, a passed CVC check with no failures is `CardRisk::Low`, and
+    /// anything else (e.g. checks that were unavailable or not yet performed) is
+    /// `CardRisk::Unknown`.
+    pub fn risk_summary(&self) -> CardRisk {
+        match &self.checks {
+            Some(checks) if checks.has_failure() => CardRisk::High,
+            Some(checks) if checks.is_cvc_verified() => CardRisk::Low,
+            _ => CardRisk::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// Overall risk of a credit or debit card, interpreted from its `Checks`.
+pub enum CardRisk {
+    /// CVC verified, and no check failed.
+    Low,
+    /// Nothing failed, but nothing conclusive was verified either.
+    Unknown,
+    /// At least one check failed.
+    High,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 /// If a credit or debit card check is performed, the result may be: `pass`, `fail`, `unavailable`, or `unchecked`.
 pub enum CheckResult {
@@ -116,6 +141,35 @@ pub struct Checks {
     pub cvc_check: Option<CheckResult>,
 }
 
+impl Checks {
+    /// Returns `true` if the address line 1 check passed.
+    pub fn is_address_line1_verified(&self) -> bool {
+        self.address_line1_check == Some(CheckResult::Pass)
+    }
+
+    /// Returns `true` if the CVC check passed.
+    pub fn is_cvc_verified(&self) -> bool {
+        self.cvc_check == Some(CheckResult::Pass)
+    }
+
+    /// Returns `true` if the postal code check passed.
+    pub fn is_postal_code_verified(&self) -> bool {
+        self.address_postal_code_check == Some(CheckResult::Pass)
+    }
+
+    /// Returns `true` if any check failed.  `unavailable` (the issuer doesn't support
+    /// the check) and `unchecked` (not performed yet) are not failures.
+    pub fn has_failure(&self) -> bool {
+        [
+            &self.address_line1_check,
+            &self.address_postal_code_check,
+            &self.cvc_check,
+        ]
+        .into_iter()
+        .any(|c| *c == Some(CheckResult::Failed))
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 /// Funding type, e.g. `credit`, `debit`, `prepaid`, or `unknown`.
 pub enum Funding {
@@ -1,7 +1,10 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::{BillingAddress, Currency, PaymentMethod, PaymentMethodId, StripeClient, Subscription};
+use super::{
+    BillingAddress, Currency, PaymentMethod, PaymentMethodId, StripeClient, Subscription,
+    TestClockId,
+};
 use crate::common::Error;
 use crate::impl_wrapper_str;
 use crate::serde_utils::is_default;
@@ -10,6 +13,7 @@ use core::fmt::Debug;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 /// Customer ID.
 pub struct CustomerId(pub String);
@@ -74,6 +78,10 @@ pub struct Customer {
     /// Application specific metadata.
     pub metadata: HashMap<String, String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Test clock the customer is bound to, if any.
+    pub test_clock: Option<TestClockId>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// The customer's subscriptions, if any.
     //  (Obtained by a second query.)
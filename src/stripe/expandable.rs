@@ -0,0 +1,38 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(untagged)]
+/// Reference to a related Stripe object, which is just its ID unless the request asked
+/// Stripe to `expand[]` it inline.
+pub enum Expandable<I, T> {
+    /// Unexpanded ID.
+    Id(I),
+    /// Expanded object.
+    Object(Box<T>),
+}
+
+impl<I, T> Expandable<I, T> {
+    /// The ID, if not expanded.
+    pub fn as_id(&self) -> Option<&I> {
+        match self {
+            Self::Id(id) => Some(id),
+            Self::Object(_) => None,
+        }
+    }
+
+    /// The object, if expanded.
+    pub fn as_object(&self) -> Option<&T> {
+        match self {
+            Self::Id(_) => None,
+            Self::Object(object) => Some(object),
+        }
+    }
+
+    /// Returns `true` if expanded.
+    pub fn is_object(&self) -> bool {
+        matches!(self, Self::Object(_))
+    }
+}
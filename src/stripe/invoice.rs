@@ -0,0 +1,125 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::resource_list::StripeResource;
+use super::{Currency, CustomerId, StripeClient, SubscriptionId};
+use crate::common::Error;
+use crate::impl_wrapper_str;
+use crate::serde_utils::is_default;
+use crate::time_id::NonZeroUnixSeconds;
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+/// Invoice ID.
+pub struct InvoiceId(pub String);
+impl_wrapper_str!(InvoiceId);
+
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+/// Invoice.
+pub struct Invoice {
+    /// Unique identifier for the invoice.
+    pub id: InvoiceId,
+
+    #[serde(default)]
+    /// Final amount due, in minor units of `currency`.
+    pub amount_due: i64,
+
+    #[serde(default)]
+    /// Amount paid, in minor units of `currency`.
+    pub amount_paid: i64,
+
+    #[serde(default)]
+    /// Amount remaining to be paid, in minor units of `currency`.
+    pub amount_remaining: i64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Date/Time record was created.
+    pub created: Option<NonZeroUnixSeconds>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// 3 letter IS-4217 currency code, e.g. Currency::USD.
+    pub currency: Option<Currency>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Customer being billed.
+    pub customer: Option<CustomerId>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Date/Time payment is due, if the invoice is sent rather than charged automatically.
+    pub due_date: Option<NonZeroUnixSeconds>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// URL of a Stripe hosted page for viewing and paying the invoice.
+    pub hosted_invoice_url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// URL of the invoice PDF.
+    pub invoice_pdf: Option<String>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Live mode vs test mode.
+    pub livemode: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Human readable invoice number, assigned when the invoice is finalized.
+    pub number: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// End of the billing period.
+    pub period_end: Option<NonZeroUnixSeconds>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Start of the billing period.
+    pub period_start: Option<NonZeroUnixSeconds>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Invoice status.
+    pub status: Option<InvoiceStatus>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Subscription the invoice was generated for, if any.
+    pub subscription: Option<SubscriptionId>,
+
+    #[serde(default)]
+    /// Total after discounts and taxes, in minor units of `currency`.
+    pub total: i64,
+}
+
+impl StripeResource for Invoice {
+    fn resource_id(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Invoice status, e.g. `paid` or `open`.
+pub enum InvoiceStatus {
+    /// Draft, not yet finalized.
+    Draft,
+    /// Finalized and awaiting payment.
+    Open,
+    /// Paid.
+    Paid,
+    /// Unlikely to be paid.
+    Uncollectible,
+    /// Canceled.
+    Void,
+    /// A status this crate doesn't know about (yet).
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl StripeClient {
+    /// Load an existing Invoice.
+    pub async fn get_invoice(&self, invoice_id: &InvoiceId) -> Result<Invoice, Error> {
+        self.get(&format!("invoices/{invoice_id}")).await
+    }
+
+    /// List all invoices for the specified customer, most recent first.
+    pub async fn list_invoices(&self, customer_id: &CustomerId) -> Result<Vec<Invoice>, Error> {
+        self.list_all(&format!("invoices?customer={customer_id}"))
+            .await
+    }
+}
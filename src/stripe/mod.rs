@@ -7,6 +7,10 @@ mod billing_address;
 mod charge_card;
 /// Customer.
 mod customer;
+/// Expandable reference to a related object.
+mod expandable;
+/// Invoice.
+mod invoice;
 /// Payment method.
 mod payment_method;
 /// Price.
@@ -19,14 +23,20 @@ mod resource_list;
 mod stripe_client;
 /// Subscription.
 mod subscription;
+/// Test clock.
+mod test_clock;
 /// Tests.
 mod tests;
+/// Metered usage record.
+mod usage_record;
 
 pub use self::billing_address::{BillingAddress, BillingDetails};
-pub use self::charge_card::{Brand, ChargeCard, CheckResult, Checks, Funding};
+pub use self::charge_card::{Brand, CardRisk, ChargeCard, CheckResult, Checks, Funding};
 pub use self::customer::{Customer, CustomerId};
+pub use self::expandable::Expandable;
+pub use self::invoice::{Invoice, InvoiceId, InvoiceStatus};
 pub use self::payment_method::{PaymentMethod, PaymentMethodId};
-pub use self::price::{Currency, Price, PriceId, PriceType};
+pub use self::price::{Currency, Price, PriceId, PriceTier, PriceType, TiersMode};
 pub use self::product::{Product, ProductId};
 pub use self::resource_list::StripeResourceList;
 pub use self::stripe_client::{new_stripe_client, StripeClient};
@@ -34,3 +44,5 @@ pub use self::subscription::{
     AutomaticTax, CancellationDetails, CollectionMethod, Subscription, SubscriptionId,
     SubscriptionItem, SubscriptionItemId, SubscriptionStatus,
 };
+pub use self::test_clock::{TestClock, TestClockId, TestClockStatus};
+pub use self::usage_record::{UsageAction, UsageRecord, UsageRecordId};
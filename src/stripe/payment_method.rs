@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::{BillingDetails, ChargeCard, CustomerId, StripeClient};
+use super::{BillingDetails, ChargeCard, Customer, CustomerId, StripeClient};
 use crate::common::Error;
 use crate::impl_wrapper_str;
 use crate::serde_utils::is_default;
@@ -10,6 +10,7 @@ use core::fmt::Debug;
 use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
 
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 /// Payment Source ID.
 pub struct PaymentMethodId(pub String);
@@ -77,30 +78,47 @@ impl StripeClient {
             id: payment_method_id,
             ..
         } = self.post("payment_methods", &cc_form_data).await?;
-        let customer_form_data = [("customer", format!("{customer_id}"))];
-        let payment_method: PaymentMethod = self
-            .post(
-                &format!("payment_methods/{payment_method_id}/attach"),
-                &customer_form_data,
-            )
+        let payment_method = self
+            .attach_payment_method(&payment_method_id, customer_id)
             .await?;
         // The new payment method becomes the default for the customer.
-        let form_data = [(
-            "invoice_settings[default_payment_method]",
-            payment_method.id.to_string(),
-        )];
-        self.update_customer(customer_id, &form_data).await?;
+        self.set_default_payment_method(customer_id, &payment_method.id)
+            .await?;
         Ok(payment_method)
     }
 
+    /// Attach an existing payment method to the specified customer.
+    pub async fn attach_payment_method(
+        &self,
+        payment_method_id: &PaymentMethodId,
+        customer_id: &CustomerId,
+    ) -> Result<PaymentMethod, Error> {
+        let form_data = [("customer", format!("{customer_id}"))];
+        self.post(
+            &format!("payment_methods/{payment_method_id}/attach"),
+            &form_data,
+        )
+        .await
+    }
+
     /// Delete (detach) an existing payment method.
     pub async fn delete_payment_method(
         &self,
         payment_method_id: &PaymentMethodId,
     ) -> Result<(), Error> {
+        self.detach_payment_method(payment_method_id).await?;
+        Ok(())
+    }
+
+    /// Detach a payment method from its customer.  Once detached, it can't be used
+    /// or attached again.
+    pub async fn detach_payment_method(
+        &self,
+        payment_method_id: &PaymentMethodId,
+    ) -> Result<PaymentMethod, Error> {
         let form_data: &[(&str, &str)] = &[];
         self.post(
-            &format!("payment_methods/{payment_method_id}/detatch"),
+            &format!("payment_methods/{payment_method_id}/detach"),
             &form_data,
         )
         .await
@@ -136,6 +154,20 @@ impl StripeClient {
         Ok(list.data)
     }
 
+    /// Make an attached payment method the customer's default, by updating their
+    /// `invoice_settings.default_payment_method`.
+    pub async fn set_default_payment_method(
+        &self,
+        customer_id: &CustomerId,
+        payment_method_id: &PaymentMethodId,
+    ) -> Result<Customer, Error> {
+        let form_data = [(
+            "invoice_settings[default_payment_method]",
+            payment_method_id.to_string(),
+        )];
+        self.update_customer(customer_id, &form_data).await
+    }
+
     /// Update credit or debit card payment method.  It is only possible to
     /// update the expiration date, not the card[number] or card[cvc].
     pub async fn update_card_payment_method(
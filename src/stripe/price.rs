@@ -9,6 +9,7 @@ use crate::time_id::NonZeroUnixSeconds;
 use core::fmt::Debug;
 use serde::{Deserialize, Serialize};
 
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 /// Price ID.
 pub struct PriceId(pub String);
@@ -26,11 +27,53 @@ pub enum Currency {
     #[serde(rename = "gbp")]
     /// Great Britain Pound
     GBP,
+    #[serde(rename = "jpy")]
+    /// Japanese Yen
+    JPY,
     #[serde(rename = "usd")]
     /// United States Dollar
     USD,
 }
 
+impl Currency {
+    /// Number of decimal places in which Stripe expresses amounts, e.g. 2 for USD
+    /// (cents) but 0 for JPY.
+    pub fn minor_units(&self) -> u32 {
+        match self {
+            Self::JPY => 0,
+            Self::CAD | Self::EUR | Self::GBP | Self::USD => 2,
+        }
+    }
+
+    /// Currency symbol, e.g. "$" for USD.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::CAD => "CA$",
+            Self::EUR => "€",
+            Self::GBP => "£",
+            Self::JPY => "¥",
+            Self::USD => "$",
+        }
+    }
+
+    /// Formats an amount expressed in minor units, e.g. 1050 is "$10.50" for USD
+    /// but "¥1050" for JPY.
+    pub fn format_amount(&self, minor: u64) -> String {
+        let minor_units = self.minor_units();
+        let symbol = self.symbol();
+        if minor_units == 0 {
+            format!("{symbol}{minor}")
+        } else {
+            let scale = 10u64.pow(minor_units);
+            let (major, minor) = (minor / scale, minor % scale);
+            format!(
+                "{symbol}{major}.{minor:0width$}",
+                width = minor_units as usize
+            )
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 /// Whether the payment is one time or recurring.
@@ -41,6 +84,40 @@ pub enum PriceType {
     Recurring,
 }
 
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// How the tiers of a tiered price apply to a quantity.
+pub enum TiersMode {
+    /// Each tier's price applies to the portion of the quantity within that tier.
+    Graduated,
+    /// The price of the tier containing the total quantity applies to all of it.
+    Volume,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+/// One tier of a tiered price.
+pub struct PriceTier {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Flat price for this tier, expressed in cents.
+    pub flat_amount: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Flat price for this tier as a decimal string, expressed in cents.
+    pub flat_amount_decimal: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Price per unit within this tier, expressed in cents.
+    pub unit_amount: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Price per unit within this tier as a decimal string, expressed in cents.
+    pub unit_amount_decimal: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Upper bound (inclusive) of this tier, or `None` for the last (unbounded) tier.
+    pub up_to: Option<u64>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 /// Price.
 pub struct Price {
@@ -76,8 +153,16 @@ pub struct Price {
     /// The product to which this price applies.
     pub product: Option<ProductId>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Tiers of a tiered price, in ascending order of `up_to`.
+    pub tiers: Vec<PriceTier>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How `tiers` apply, e.g. TiersMode::Graduated, if the price is tiered.
+    pub tiers_mode: Option<TiersMode>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
-    /// Price expressed in cents.
+    /// Price expressed in cents (`None` if the price is tiered).
     pub unit_amount: Option<i64>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -86,13 +171,13 @@ pub struct Price {
 }
 
 impl StripeClient {
-    /// List up to 100 prices.
+    /// List up to 100 prices, including the tiers of tiered prices.
     pub async fn list_prices(&self) -> Result<Vec<Price>, Error> {
         #[derive(Debug, Deserialize)]
         struct PriceList {
             data: Vec<Price>,
         }
-        let mut list: PriceList = self.get("prices?limit=100").await?;
+        let mut list: PriceList = self.get("prices?limit=100&expand[]=data.tiers").await?;
         list.data.retain(|p| p.active || !p.deleted);
         Ok(list.data)
     }
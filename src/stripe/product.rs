@@ -1,6 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+use super::resource_list::StripeResource;
 use super::{PriceId, StripeClient};
 use crate::common::Error;
 use crate::impl_wrapper_str;
@@ -10,6 +11,7 @@ use core::fmt::Debug;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 /// Product ID.
 pub struct ProductId(pub String);
@@ -70,16 +72,31 @@ pub struct Product {
     pub updated: Option<NonZeroUnixSeconds>,
 }
 
+impl StripeResource for Product {
+    fn resource_id(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
 impl StripeClient {
-    /// List up to 100 products.
+    /// List all products.
     pub async fn list_products(&self) -> Result<Vec<Product>, Error> {
-        #[derive(Debug, Deserialize)]
-        struct ProductList {
-            data: Vec<Product>,
-        }
-        let mut list: ProductList = self.get("products?limit=100").await?;
-        list.data.retain(|p| p.active || !p.deleted);
-        Ok(list.data)
+        self.list_products_filtered(None).await
+    }
+
+    /// List all products, optionally only those which are active (`Some(true)`) or
+    /// archived (`Some(false)`).
+    pub async fn list_products_filtered(
+        &self,
+        active: Option<bool>,
+    ) -> Result<Vec<Product>, Error> {
+        let path = match active {
+            Some(active) => format!("products?active={active}"),
+            None => "products".to_string(),
+        };
+        let mut products: Vec<Product> = self.list_all(&path).await?;
+        products.retain(|p| p.active || !p.deleted);
+        Ok(products)
     }
 
     /// Load an existing Product.
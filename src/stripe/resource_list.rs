@@ -3,6 +3,12 @@
 
 use serde::{Deserialize, Serialize};
 
+/// A Stripe object which can be paged through by its ID.
+pub(crate) trait StripeResource {
+    /// Unique identifier, used as the `starting_after` cursor.
+    fn resource_id(&self) -> &str;
+}
+
 #[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
 /// Stripe resource list.
 pub struct StripeResourceList<T> {
@@ -37,3 +43,43 @@ impl<T: Clone> Clone for StripeResourceList<T> {
         }
     }
 }
+
+impl<T> StripeResourceList<T> {
+    /// Iterates the list content.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Number of items in this page of the list (not necessarily the total count).
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if this page of the list has no items.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// First item in the list, if any.
+    pub fn first(&self) -> Option<&T> {
+        self.data.first()
+    }
+}
+
+impl<T> IntoIterator for StripeResourceList<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a StripeResourceList<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
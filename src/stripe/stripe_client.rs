@@ -1,6 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+use super::resource_list::{StripeResource, StripeResourceList};
 use crate::common::{CubConfig, Error};
 use core::fmt::Debug;
 use hyper::header::{HeaderMap, HeaderValue};
@@ -13,13 +14,18 @@ use std::time::Duration;
 const DEBUG_REQUEST: bool = false;
 const DEBUG_RESPONSE: bool = false;
 
-/// Stripe HTTP Client.
+/// Stripe HTTP Client.  Endpoints which this crate doesn't wrap (e.g. `payouts`) can be
+/// called with `get`, `post`, and `delete`, which take a path relative to
+/// `https://api.stripe.com/v1/`.
 pub struct StripeClient {
     client: reqwest::Client,
+    /// Connected account to act on behalf of, sent as the `Stripe-Account` header.
+    stripe_account: Option<String>,
 }
 
 impl StripeClient {
-    /// Create Stripe HTTP Client.
+    /// Create Stripe HTTP Client with the `[stripe] secret_key` from the config.  Panics
+    /// if the secret key is invalid.
     pub fn new(cub_config: &CubConfig) -> Self {
         #[derive(Deserialize)]
         struct StripeConfig {
@@ -32,12 +38,25 @@ impl StripeClient {
         let ConfigToml {
             stripe: StripeConfig { secret_key },
         } = cub_config.get().expect("stripe.toml");
+        Self::with_secret_key(&secret_key).unwrap_or_else(|e| panic!("stripe.toml: {e}"))
+    }
+
+    /// Create Stripe HTTP Client with the specified secret key, e.g. when rotating keys.
+    /// Surrounding whitespace (e.g. a trailing newline from a secret store) is ignored.
+    /// Errors never contain the key.
+    pub fn with_secret_key(secret_key: &str) -> Result<Self, Error> {
+        let secret_key = secret_key.trim();
         if secret_key.len() < 16 {
-            panic!("{secret_key}: not a valid secret key for stripe");
+            return Err(Error::String(
+                "not a valid secret key for stripe (too short)".to_string(),
+            ));
         }
 
         let mut default_headers = HeaderMap::new();
-        let mut auth_header = HeaderValue::from_str(&format!("Bearer {}", secret_key)).unwrap();
+        let mut auth_header =
+            HeaderValue::from_str(&format!("Bearer {}", secret_key)).map_err(|_| {
+                Error::String("not a valid secret key for stripe (invalid characters)".to_string())
+            })?;
         auth_header.set_sensitive(true);
         default_headers.insert(reqwest::header::AUTHORIZATION, auth_header);
 
@@ -45,17 +64,43 @@ impl StripeClient {
             .timeout(Duration::from_secs(20))
             .default_headers(default_headers)
             .build()
-            .unwrap();
-        Self { client }
+            .map_err(|e| Error::String(format!("cannot build stripe client: {e}")))?;
+        Ok(Self {
+            client,
+            stripe_account: None,
+        })
+    }
+
+    /// Returns a client (sharing this one's connection pool and secret key) which acts
+    /// on behalf of the specified Stripe Connect account, e.g. `acct_1234`.
+    pub fn for_account(&self, account_id: &str) -> Self {
+        Self {
+            client: self.client.clone(),
+            stripe_account: Some(account_id.to_string()),
+        }
     }
 
-    /// Delete the object with the specified path from Stripe.
-    pub(crate) async fn delete(&self, path: &str) -> Result<(), Error> {
+    /// Stripe Connect account this client acts on behalf of, if any.
+    pub fn account_id(&self) -> Option<&str> {
+        self.stripe_account.as_deref()
+    }
+
+    /// Begin a request to the specified path, on behalf of the connected account (if any).
+    fn request(&self, method: Method, request_path: String) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, request_path);
+        match &self.stripe_account {
+            Some(account_id) => request.header("Stripe-Account", account_id),
+            None => request,
+        }
+    }
+
+    /// Delete the object with the specified path (e.g. `customers/cus_1234`) from Stripe.
+    pub async fn delete(&self, path: &str) -> Result<(), Error> {
         let request_path = format!("https://api.stripe.com/v1/{path}");
         if DEBUG_REQUEST {
             println!(">> DELETE {request_path}");
         }
-        let request = self.client.request(Method::DELETE, request_path);
+        let request = self.request(Method::DELETE, request_path);
         match request.send().await {
             Ok(r) => {
                 let status = r.status();
@@ -75,13 +120,14 @@ impl StripeClient {
         }
     }
 
-    /// Get the object with the specified path from Stripe.
-    pub(crate) async fn get<T: Debug + DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+    /// Get the object with the specified path (which may include query parameters, e.g.
+    /// `payouts?limit=10`) from Stripe.
+    pub async fn get<T: Debug + DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
         let request_path = format!("https://api.stripe.com/v1/{path}");
         if DEBUG_REQUEST {
             println!(">> GET {request_path}");
         }
-        let request = self.client.request(Method::GET, request_path);
+        let request = self.request(Method::GET, request_path);
         match request.send().await {
             Ok(r) => {
                 let status = r.status();
@@ -109,8 +155,52 @@ impl StripeClient {
         }
     }
 
-    /// Post URL encoded form to Stripe via Stripe client.
-    pub(crate) async fn post<F: Debug + Serialize, T: Debug + DeserializeOwned>(
+    /// Get the object with the specified path (which may include query parameters)
+    /// from Stripe, asking Stripe to inline the related objects named by `expand`, e.g.
+    /// `customer` or `data.customer` for a list.
+    pub async fn get_expanded<T: Debug + DeserializeOwned>(
+        &self,
+        path: &str,
+        expand: &[&str],
+    ) -> Result<T, Error> {
+        let mut path = path.to_string();
+        for (i, e) in expand.iter().enumerate() {
+            let separator = if i == 0 && !path.contains('?') {
+                '?'
+            } else {
+                '&'
+            };
+            path.push_str(&format!("{separator}expand[]={e}"));
+        }
+        self.get(&path).await
+    }
+
+    /// Get all objects of the list with the specified path (which may include query
+    /// parameters) from Stripe, following `has_more` one page at a time.
+    pub(crate) async fn list_all<T: Debug + DeserializeOwned + StripeResource>(
+        &self,
+        path: &str,
+    ) -> Result<Vec<T>, Error> {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let mut all = Vec::new();
+        loop {
+            let starting_after = all
+                .last()
+                .map(|r: &T| format!("&starting_after={}", r.resource_id()))
+                .unwrap_or_default();
+            let page: StripeResourceList<T> = self
+                .get(&format!("{path}{separator}limit=100{starting_after}"))
+                .await?;
+            let has_more = page.has_more && !page.data.is_empty();
+            all.extend(page.data);
+            if !has_more {
+                return Ok(all);
+            }
+        }
+    }
+
+    /// Post URL encoded form to the specified path (e.g. `payouts`) via Stripe client.
+    pub async fn post<F: Debug + Serialize, T: Debug + DeserializeOwned>(
         &self,
         path: &str,
         payload: &F,
@@ -119,10 +209,7 @@ impl StripeClient {
         if DEBUG_REQUEST {
             println!(">> POST {request_path}\n{:?}", payload);
         }
-        let request = self
-            .client
-            .request(Method::POST, request_path)
-            .form(payload);
+        let request = self.request(Method::POST, request_path).form(payload);
         match request.send().await {
             Ok(r) => {
                 let status = r.status();
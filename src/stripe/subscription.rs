@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 use super::{
-    Currency, CustomerId, PaymentMethodId, Price, PriceId, StripeClient, StripeResourceList,
+    Currency, Customer, CustomerId, Expandable, PaymentMethod, PaymentMethodId, Price, PriceId,
+    StripeClient, StripeResourceList,
 };
 use crate::common::Error;
 use crate::impl_wrapper_str;
@@ -12,11 +13,13 @@ use core::fmt::Debug;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 /// Subscription ID.
 pub struct SubscriptionId(pub String);
 impl_wrapper_str!(SubscriptionId);
 
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 /// Subscription item ID.
 pub struct SubscriptionItemId(pub String);
@@ -101,12 +104,12 @@ pub struct Subscription {
     pub current_period_start: Option<NonZeroUnixSeconds>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    /// Customer paying for this subscription.
-    pub customer: Option<CustomerId>,
+    /// Customer paying for this subscription (an object if expanded).
+    pub customer: Option<Expandable<CustomerId, Customer>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    /// Default payment method.
-    pub default_payment_method: Option<PaymentMethodId>,
+    /// Default payment method (an object if expanded).
+    pub default_payment_method: Option<Expandable<PaymentMethodId, PaymentMethod>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Subscription description.
@@ -137,6 +140,24 @@ pub struct Subscription {
     pub status: Option<SubscriptionStatus>,
 }
 
+impl Subscription {
+    /// ID of the customer paying for this subscription, whether or not it was expanded.
+    pub fn customer_id(&self) -> Option<&CustomerId> {
+        self.customer.as_ref().map(|c| match c {
+            Expandable::Id(id) => id,
+            Expandable::Object(customer) => &customer.id,
+        })
+    }
+
+    /// ID of the default payment method, whether or not it was expanded.
+    pub fn default_payment_method_id(&self) -> Option<&PaymentMethodId> {
+        self.default_payment_method.as_ref().map(|pm| match pm {
+            Expandable::Id(id) => id,
+            Expandable::Object(payment_method) => &payment_method.id,
+        })
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 /// Subscription item.
 pub struct SubscriptionItem {
@@ -178,6 +199,9 @@ pub enum SubscriptionStatus {
     Trialing,
     /// Unpaid.
     Unpaid,
+    /// A status this crate doesn't know about (yet), e.g. `paused`.
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 impl StripeClient {
@@ -214,6 +238,17 @@ impl StripeClient {
             .await
     }
 
+    /// Load an existing Subscription, inlining the related objects named by `expand`,
+    /// e.g. `&["customer", "default_payment_method"]`.
+    pub async fn get_subscription(
+        &self,
+        subscription_id: &SubscriptionId,
+        expand: &[&str],
+    ) -> Result<Subscription, Error> {
+        self.get_expanded(&format!("subscriptions/{subscription_id}"), expand)
+            .await
+    }
+
     /// List up to 10 subscriptions for the specified customer.
     pub async fn list_subscriptions(
         &self,
@@ -0,0 +1,110 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{Customer, StripeClient};
+use crate::common::Error;
+use crate::impl_wrapper_str;
+use crate::serde_utils::is_default;
+use crate::time_id::NonZeroUnixSeconds;
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+/// Test clock ID.
+pub struct TestClockId(pub String);
+impl_wrapper_str!(TestClockId);
+
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+/// Test clock, which simulates the passage of time for the customers (and their
+/// subscriptions) bound to it.  Only available in test mode.
+pub struct TestClock {
+    /// Unique identifier for the test clock.
+    pub id: TestClockId,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Date/Time record was created.
+    pub created: Option<NonZeroUnixSeconds>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Date/Time the test clock (and its customers) will be deleted.
+    pub deletes_after: Option<NonZeroUnixSeconds>,
+
+    /// Current (simulated) time.
+    pub frozen_time: NonZeroUnixSeconds,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Live mode vs test mode.
+    pub livemode: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Test clock name.
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Test clock status.
+    pub status: Option<TestClockStatus>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Test clock status, e.g. `ready` or `advancing`.
+pub enum TestClockStatus {
+    /// Advancing to a new `frozen_time`.
+    Advancing,
+    /// Failed to advance.
+    InternalFailure,
+    /// Ready to be advanced.
+    Ready,
+    /// A status this crate doesn't know about (yet).
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl StripeClient {
+    /// Advance an existing test clock to the specified (later) time.  Advancing is
+    /// asynchronous, so poll `load_test_clock` until its status is `ready`.
+    pub async fn advance_test_clock(
+        &self,
+        test_clock_id: &TestClockId,
+        to: NonZeroUnixSeconds,
+    ) -> Result<TestClock, Error> {
+        let form_data = [("frozen_time", to.to_string())];
+        self.post(
+            &format!("test_helpers/test_clocks/{test_clock_id}/advance"),
+            &form_data,
+        )
+        .await
+    }
+
+    /// Create a Customer bound to the specified test clock, with the specified form data.
+    pub async fn create_customer_with_test_clock(
+        &self,
+        test_clock_id: &TestClockId,
+        form_data: &[(&str, &str)],
+    ) -> Result<Customer, Error> {
+        let mut form_data = form_data.to_vec();
+        form_data.push(("test_clock", test_clock_id.as_str()));
+        self.create_customer(&form_data).await
+    }
+
+    /// Create a test clock, starting at the specified time.
+    pub async fn create_test_clock(
+        &self,
+        frozen_time: NonZeroUnixSeconds,
+    ) -> Result<TestClock, Error> {
+        let form_data = [("frozen_time", frozen_time.to_string())];
+        self.post("test_helpers/test_clocks", &form_data).await
+    }
+
+    /// Delete an existing test clock, along with the customers bound to it.
+    pub async fn delete_test_clock(&self, test_clock_id: &TestClockId) -> Result<(), Error> {
+        self.delete(&format!("test_helpers/test_clocks/{test_clock_id}"))
+            .await
+    }
+
+    /// Load an existing test clock.
+    pub async fn load_test_clock(&self, test_clock_id: &TestClockId) -> Result<TestClock, Error> {
+        self.get(&format!("test_helpers/test_clocks/{test_clock_id}"))
+            .await
+    }
+}
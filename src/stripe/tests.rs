@@ -3,8 +3,12 @@
 
 #[cfg(test)]
 mod stripe_tests {
-    use crate::common::CubConfig;
-    use crate::stripe::{PriceId, StripeClient};
+    use crate::common::{CubConfig, Error};
+    use crate::stripe::{
+        CardRisk, ChargeCard, CheckResult, Checks, Currency, Invoice, InvoiceStatus, Price,
+        PriceId, StripeClient, StripeResourceList, Subscription, SubscriptionStatus, TestClock,
+        TestClockStatus, TiersMode, UsageAction, UsageRecord,
+    };
 
     fn test_config() -> CubConfig {
         CubConfig::builder()
@@ -19,6 +23,177 @@ mod stripe_tests {
             .expect("stripe_tests.toml")
     }
 
+    #[test]
+    fn connect_account_tests() {
+        let stripe = StripeClient::with_secret_key("sk_test_1234567890\n").expect("valid key");
+        assert_eq!(stripe.account_id(), None);
+        let connected = stripe.for_account("acct_1234");
+        assert_eq!(connected.account_id(), Some("acct_1234"));
+    }
+
+    #[test]
+    fn invalid_secret_key_tests() {
+        for secret_key in ["sk_test_12", "sk_test_1234\u{7f}567890"] {
+            match StripeClient::with_secret_key(secret_key) {
+                Err(Error::String(message)) => assert!(!message.contains("sk_test")),
+                _ => panic!("expected an error"),
+            }
+        }
+    }
+
+    #[test]
+    fn subscription_status_tests() {
+        let status: SubscriptionStatus = serde_json::from_str(r#""past_due""#).unwrap();
+        assert_eq!(status, SubscriptionStatus::PastDue);
+        let status: SubscriptionStatus = serde_json::from_str(r#""paused""#).unwrap();
+        assert_eq!(status, SubscriptionStatus::Unknown("paused".to_string()));
+        assert_eq!(serde_json::to_string(&status).unwrap(), r#""paused""#);
+    }
+
+    #[test]
+    fn currency_tests() {
+        assert_eq!(Currency::USD.minor_units(), 2);
+        assert_eq!(Currency::JPY.minor_units(), 0);
+        assert_eq!(Currency::USD.format_amount(1050), "$10.50");
+        assert_eq!(Currency::USD.format_amount(5), "$0.05");
+        assert_eq!(Currency::EUR.format_amount(100000), "€1000.00");
+        assert_eq!(Currency::JPY.format_amount(1050), "¥1050");
+    }
+
+    #[test]
+    fn card_risk_tests() {
+        let mut card = ChargeCard::default();
+        assert_eq!(card.risk_summary(), CardRisk::Unknown);
+        card.checks = Some(Checks {
+            address_line1_check: Some(CheckResult::Unavailable),
+            address_postal_code_check: Some(CheckResult::Pass),
+            cvc_check: Some(CheckResult::Pass),
+        });
+        assert!(card.checks.as_ref().unwrap().is_cvc_verified());
+        assert!(!card.checks.as_ref().unwrap().is_address_line1_verified());
+        assert_eq!(card.risk_summary(), CardRisk::Low);
+        card.checks.as_mut().unwrap().cvc_check = Some(CheckResult::Unchecked);
+        assert_eq!(card.risk_summary(), CardRisk::Unknown);
+        card.checks.as_mut().unwrap().address_postal_code_check = Some(CheckResult::Failed);
+        assert_eq!(card.risk_summary(), CardRisk::High);
+    }
+
+    #[test]
+    fn expandable_tests() {
+        let subscription: Subscription = serde_json::from_str(
+            r#"{"id": "sub_1", "customer": "cus_1", "default_payment_method": "pm_1"}"#,
+        )
+        .expect("unexpanded subscription");
+        assert!(!subscription.customer.as_ref().unwrap().is_object());
+        assert_eq!(subscription.customer_id().unwrap().as_str(), "cus_1");
+        assert_eq!(
+            subscription.default_payment_method_id().unwrap().as_str(),
+            "pm_1"
+        );
+
+        let subscription: Subscription = serde_json::from_str(
+            r#"{"id": "sub_1", "customer": {"id": "cus_1", "name": "Mr. Ed"}}"#,
+        )
+        .expect("expanded subscription");
+        let customer = subscription.customer.as_ref().unwrap().as_object().unwrap();
+        assert_eq!(customer.name.as_deref(), Some("Mr. Ed"));
+        assert_eq!(subscription.customer_id().unwrap().as_str(), "cus_1");
+        assert_eq!(subscription.default_payment_method_id(), None);
+    }
+
+    #[test]
+    fn test_clock_tests() {
+        let json = r#"{
+          "id": "clock_1MsKWkLkdIwHu7ix3GqbGQ1t",
+          "object": "test_helpers.test_clock",
+          "created": 1680428434,
+          "deletes_after": 1681033234,
+          "frozen_time": 1680428434,
+          "livemode": false,
+          "name": null,
+          "status": "ready"
+        }"#;
+        let clock: TestClock = serde_json::from_str(json).expect("test clock");
+        assert_eq!(clock.frozen_time.0.get(), 1680428434);
+        assert_eq!(clock.status, Some(TestClockStatus::Ready));
+        assert_eq!(clock.name, None);
+    }
+
+    #[test]
+    fn resource_list_tests() {
+        let empty = StripeResourceList::<u32>::default();
+        assert!(empty.is_empty());
+        assert_eq!(empty.len(), 0);
+        assert_eq!(empty.first(), None);
+
+        let list = StripeResourceList {
+            data: vec![1u32, 2, 3],
+            has_more: false,
+            total_count: None,
+            url: "/v1/things".to_string(),
+        };
+        assert!(!list.is_empty());
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.first(), Some(&1));
+        assert_eq!(list.iter().sum::<u32>(), 6);
+        let mut borrowed = 0;
+        for n in &list {
+            borrowed += n;
+        }
+        assert_eq!(borrowed, 6);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn usage_record_tests() {
+        assert_eq!(UsageAction::default(), UsageAction::Increment);
+        assert_eq!(UsageAction::Set.as_str(), "set");
+        let json = r#"{
+          "id": "mbur_1MoC0hLkdIwHu7ixCGcNZ2LF",
+          "object": "usage_record",
+          "livemode": false,
+          "quantity": 100,
+          "subscription_item": "si_NZKfpSgIgxfq1q",
+          "timestamp": 1679431331
+        }"#;
+        let record: UsageRecord = serde_json::from_str(json).expect("usage record");
+        assert_eq!(record.quantity, 100);
+        assert_eq!(record.subscription_item.as_str(), "si_NZKfpSgIgxfq1q");
+        assert_eq!(record.timestamp.map(|t| t.0.get()), Some(1679431331));
+    }
+
+    #[test]
+    fn invoice_tests() {
+        let json = r#"{
+          "id": "in_1MtHbELkdIwHu7ixl4OzzPMv",
+          "object": "invoice",
+          "amount_due": 999,
+          "amount_paid": 999,
+          "amount_remaining": 0,
+          "created": 1680644467,
+          "currency": "usd",
+          "customer": "cus_NeZwdNtLEOXuvB",
+          "due_date": null,
+          "hosted_invoice_url": "https://invoice.stripe.com/i/acct_1234",
+          "invoice_pdf": "https://pay.stripe.com/invoice/acct_1234/pdf",
+          "livemode": false,
+          "number": "ABCD-0001",
+          "period_end": 1680644467,
+          "period_start": 1680644467,
+          "status": "paid",
+          "subscription": null,
+          "total": 999
+        }"#;
+        let invoice: Invoice = serde_json::from_str(json).expect("invoice");
+        assert_eq!(invoice.amount_paid, 999);
+        assert_eq!(invoice.currency, Some(Currency::USD));
+        assert_eq!(invoice.status, Some(InvoiceStatus::Paid));
+        assert_eq!(invoice.due_date, None);
+        assert!(invoice.invoice_pdf.is_some());
+        let status: InvoiceStatus = serde_json::from_str(r#""deleted""#).unwrap();
+        assert_eq!(status, InvoiceStatus::Unknown("deleted".to_string()));
+    }
+
     #[tokio::test]
     #[should_panic]
     async fn customer_tests() {
@@ -141,4 +316,78 @@ mod stripe_tests {
         };
         println!("Stripe product tests completed");
     }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn active_product_tests() {
+        println!("Stripe active product tests starting");
+        let stripe = StripeClient::new(&test_config());
+        println!("List active products");
+        match stripe.list_products_filtered(Some(true)).await {
+            Ok(products) => {
+                println!("list succeeded: {products:?}");
+                assert!(products.iter().all(|p| p.active));
+            }
+            Err(e) => panic!("Error: {e:?}"),
+        };
+        println!("Stripe active product tests completed");
+    }
+
+    #[test]
+    fn price_tier_tests() {
+        // Graduated, metered price: first 100 free, then $0.01 each.
+        let json = r#"{
+          "id": "price_1MoBy5LkdIwHu7ixZhnattbh",
+          "object": "price",
+          "active": true,
+          "billing_scheme": "tiered",
+          "created": 1679431181,
+          "currency": "usd",
+          "custom_unit_amount": null,
+          "livemode": false,
+          "lookup_key": null,
+          "metadata": {},
+          "nickname": null,
+          "product": "prod_NZKdYqrwEYx6iK",
+          "recurring": {
+            "aggregate_usage": "sum",
+            "interval": "month",
+            "interval_count": 1,
+            "trial_period_days": null,
+            "usage_type": "metered"
+          },
+          "tax_behavior": "unspecified",
+          "tiers": [
+            {
+              "flat_amount": null,
+              "flat_amount_decimal": null,
+              "unit_amount": 0,
+              "unit_amount_decimal": "0",
+              "up_to": 100
+            },
+            {
+              "flat_amount": null,
+              "flat_amount_decimal": null,
+              "unit_amount": 1,
+              "unit_amount_decimal": "1",
+              "up_to": null
+            }
+          ],
+          "tiers_mode": "graduated",
+          "transform_quantity": null,
+          "type": "recurring",
+          "unit_amount": null,
+          "unit_amount_decimal": null
+        }"#;
+        let price: Price = serde_json::from_str(json).expect("tiered price");
+        println!("tiered price: {price:?}");
+        assert_eq!(price.currency, Some(Currency::USD));
+        assert_eq!(price.tiers_mode, Some(TiersMode::Graduated));
+        assert_eq!(price.unit_amount, None);
+        assert_eq!(price.tiers.len(), 2);
+        assert_eq!(price.tiers[0].up_to, Some(100));
+        assert_eq!(price.tiers[0].unit_amount, Some(0));
+        assert_eq!(price.tiers[1].up_to, None);
+        assert_eq!(price.tiers[1].unit_amount, Some(1));
+    }
 }
@@ -0,0 +1,82 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{StripeClient, SubscriptionItemId};
+use crate::common::Error;
+use crate::impl_wrapper_str;
+use crate::serde_utils::is_default;
+use crate::time_id::NonZeroUnixSeconds;
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "bitcode", derive(bitcode::Encode, bitcode::Decode))]
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+/// Usage record ID.
+pub struct UsageRecordId(pub String);
+impl_wrapper_str!(UsageRecordId);
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// How a usage record's quantity is combined with prior usage in the same period.
+pub enum UsageAction {
+    /// Add the quantity to the existing usage.
+    #[default]
+    Increment,
+    /// Replace the existing usage with the quantity.
+    Set,
+}
+
+impl UsageAction {
+    /// Stripe form value, e.g. `increment`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Increment => "increment",
+            Self::Set => "set",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+/// Usage record, reporting quantity for a metered subscription item.
+pub struct UsageRecord {
+    /// Unique identifier for the usage record.
+    pub id: UsageRecordId,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Live mode vs test mode.
+    pub livemode: bool,
+
+    /// Usage quantity.
+    pub quantity: u64,
+
+    /// Subscription item this usage is billed to.
+    pub subscription_item: SubscriptionItemId,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Date/Time the usage occurred.
+    pub timestamp: Option<NonZeroUnixSeconds>,
+}
+
+impl StripeClient {
+    /// Report usage for a subscription item with a metered price.  If `timestamp` is
+    /// `None`, Stripe uses the current time.
+    pub async fn create_usage_record(
+        &self,
+        subscription_item_id: &SubscriptionItemId,
+        quantity: u64,
+        timestamp: Option<NonZeroUnixSeconds>,
+        action: UsageAction,
+    ) -> Result<UsageRecord, Error> {
+        let mut form_data: Vec<(&str, String)> = vec![
+            ("quantity", quantity.to_string()),
+            ("action", action.as_str().to_string()),
+        ];
+        if let Some(timestamp) = timestamp {
+            form_data.push(("timestamp", timestamp.to_string()));
+        }
+        self.post(
+            &format!("subscription_items/{subscription_item_id}/usage_records"),
+            &form_data,
+        )
+        .await
+    }
+}
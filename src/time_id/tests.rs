@@ -3,10 +3,9 @@
 
 #[cfg(test)]
 mod time_tests {
-    use crate::time_id::{UnixMillis, UnixTime};
+    use crate::time_id::{NonZeroUnixMillis, NonZeroUnixSeconds, UnixMillis, UnixTime};
 
-    // (oauth2 already has a Chrono dependency.)
-    #[cfg(feature = "oauth")]
+    #[cfg(feature = "chrono")]
     #[test]
     fn chrono_tests() {
         println!("Testing chrono");
@@ -19,8 +18,54 @@ mod time_tests {
         }
     }
 
-    #[tokio::test]
-    async fn time_casts_01() {
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn to_relative() {
+        let now = UnixMillis::from_seconds(1_700_000_000);
+        let relative = |millis: i64| now.add_signed_millis(millis).to_relative(now);
+        assert_eq!(relative(0), "just now");
+        assert_eq!(relative(-999), "just now");
+        assert_eq!(relative(-1_000), "1 second ago");
+        assert_eq!(relative(-45 * 60_000), "45 minutes ago");
+        assert_eq!(relative(-90 * 60_000), "2 hours ago");
+        assert_eq!(relative(-89 * 60_000), "1 hour ago");
+        assert_eq!(relative(3 * 3_600_000), "in 3 hours");
+        assert_eq!(relative(2 * 86_400_000), "in 2 days");
+        assert_eq!(relative(-36 * 3_600_000), "2 days ago");
+        // Rounding up to the next unit uses that unit.
+        assert_eq!(relative(-59_500), "1 minute ago");
+        assert_eq!(relative(-(59 * 60_000 + 30_000)), "1 hour ago");
+        assert_eq!(relative(-(59 * 60_000 + 29_999)), "59 minutes ago");
+        assert_eq!(relative(23 * 3_600_000 + 1_800_000), "in 1 day");
+        assert_eq!(relative(23 * 3_600_000 + 1_799_999), "in 23 hours");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn add_business_days() {
+        // Thursday, 2024-01-04 12:00 UTC.
+        let thursday = UnixMillis::from_ymdhms(2024, 1, 4, 12, 0, 0).unwrap();
+        let ymd = |t: UnixMillis| (t.year(), t.month(), t.day());
+        assert_eq!(thursday.add_business_days(0), thursday);
+        assert_eq!(ymd(thursday.add_business_days(1)), (2024, 1, 5));
+        assert_eq!(ymd(thursday.add_business_days(2)), (2024, 1, 8));
+        assert_eq!(ymd(thursday.add_business_days(5)), (2024, 1, 11));
+        assert_eq!(thursday.add_business_days(5).hours_since(thursday), 7 * 24);
+
+        // Saturday to Monday.
+        let saturday = thursday.add_days(2);
+        assert_eq!(ymd(saturday.add_business_days(1)), (2024, 1, 8));
+
+        // Friday is a holiday.
+        let holidays = [thursday.add_days(1).floor_days()];
+        assert_eq!(
+            ymd(thursday.add_business_days_excluding(1, &holidays)),
+            (2024, 1, 8)
+        );
+    }
+
+    #[test]
+    fn time_casts_01() {
         println!("Testing time casts (for 0, 1).");
         let a1 = 1i64;
         let b1 = -1i64;
@@ -68,4 +113,62 @@ mod time_tests {
 
         println!("Time test completed");
     }
+
+    #[test]
+    fn from_seconds_and_millis() {
+        let exp = 1_700_000_000i64;
+        assert_eq!(UnixMillis::from_seconds(exp), UnixMillis(exp * 1000));
+        assert_eq!(UnixMillis::from_millis(exp), UnixMillis(exp));
+        assert_eq!(NonZeroUnixSeconds::from_seconds(exp).0.get(), exp as u64);
+        assert_eq!(
+            NonZeroUnixSeconds::from_millis(exp * 1000 + 999).0.get(),
+            exp as u64
+        );
+        assert_eq!(UnixMillis::from_seconds(i64::MAX), UnixMillis::MAX);
+        assert_eq!(UnixMillis::from_seconds(i64::MIN), UnixMillis::MIN);
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let t = UnixMillis(1_000);
+        assert_eq!(t.checked_add_millis(500), Some(UnixMillis(1_500)));
+        assert_eq!(t.checked_sub_millis(500), Some(UnixMillis(500)));
+        assert_eq!(t.checked_add_signed_millis(-1_500), Some(UnixMillis(-500)));
+        assert_eq!(t.checked_add_millis(u64::MAX), None);
+        assert_eq!(UnixMillis::MAX.checked_add_millis(1), None);
+        assert_eq!(UnixMillis::MIN.checked_sub_millis(1), None);
+        assert_eq!(UnixMillis::MAX.add_millis(1), UnixMillis::MAX);
+
+        // Non-zero times cannot be at or before the epoch.
+        let nz = NonZeroUnixMillis::from_i64(1_000);
+        assert_eq!(nz.checked_sub_millis(999), Some(NonZeroUnixMillis::MIN));
+        assert_eq!(nz.checked_sub_millis(1_000), None);
+        assert_eq!(nz.checked_add_signed_millis(-2_000), None);
+        assert_eq!(NonZeroUnixMillis::checked_from_i64(0), None);
+        assert_eq!(NonZeroUnixSeconds::checked_from_i64(999), None);
+        assert_eq!(
+            NonZeroUnixSeconds::checked_from_i64(1_000),
+            Some(NonZeroUnixSeconds::MIN)
+        );
+    }
+
+    #[test]
+    fn floor_to_interval() {
+        let quarter_hour = UnixMillis::MILLIS_PER_MINUTE * 15;
+        let t = UnixMillis::from_seconds(1_700_000_000); // 22:13:20 UTC
+        assert_eq!(
+            t.floor_to_interval(quarter_hour),
+            UnixMillis::from_seconds(1_699_999_200)
+        );
+        assert_eq!(
+            t.floor_to_interval(UnixMillis::MILLIS_PER_MINUTE * 5),
+            UnixMillis::from_seconds(1_699_999_800)
+        );
+        assert_eq!(
+            t.floor_to_interval(UnixMillis::MILLIS_PER_HOUR),
+            t.floor_hours()
+        );
+        assert_eq!(t.floor_to_interval(0), t);
+        assert_eq!(UnixMillis(-1).floor_to_interval(1_000), UnixMillis(-1_000));
+    }
 }
@@ -7,7 +7,7 @@ use crate::{impl_wrapper_from_str, impl_wrapper_int, impl_wrapper_nz};
 #[cfg(feature = "chrono")]
 use chrono::offset::LocalResult;
 #[cfg(feature = "chrono")]
-use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Timelike, Utc};
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Timelike, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use std::num::NonZeroU64;
@@ -185,7 +185,9 @@ impl From<NonZeroUnixSeconds> for UnixMillis {
     }
 }
 
-/// Convenient time arithmetic.
+/// Convenient time arithmetic.  The arithmetic has no dependency on `chrono`, but the
+/// methods which deal in calendar dates, and the alternate (`{:#}`) `Display` format,
+/// require the `chrono` feature.
 pub trait UnixTime: Sized + Clone {
     /// Maximum time supported by notation.
     const MAX: Self;
@@ -216,6 +218,36 @@ pub trait UnixTime: Sized + Clone {
         )
     }
 
+    /// Adds business days to a Unix date/time, skipping Saturdays and Sundays (UTC),
+    /// and returns the result.  The time of day is preserved.  Requires the `chrono`
+    /// feature.
+    #[cfg(feature = "chrono")]
+    fn add_business_days(&self, d: u64) -> Self {
+        self.add_business_days_excluding(d, &[])
+    }
+
+    /// Like `add_business_days`, but also skips the UTC days of the specified `holidays`.
+    /// Requires the `chrono` feature.
+    #[cfg(feature = "chrono")]
+    fn add_business_days_excluding(&self, d: u64, holidays: &[Self]) -> Self {
+        let mut result = self.clone();
+        let mut remaining = d;
+        while remaining > 0 && result.to_i64() < Self::MAX.to_i64() {
+            result = result.add_days(1);
+            let weekend = matches!(
+                result.to_date_time_utc().weekday(),
+                Weekday::Sat | Weekday::Sun
+            );
+            let holiday = holidays
+                .iter()
+                .any(|h| h.days_since_epoch() == result.days_since_epoch());
+            if !weekend && !holiday {
+                remaining -= 1;
+            }
+        }
+        result
+    }
+
     /// Adds days to a Unix date/time and returns the result.
     fn add_days(&self, d: u64) -> Self {
         self.add_millis(d * Self::MILLIS_PER_DAY)
@@ -273,7 +305,40 @@ pub trait UnixTime: Sized + Clone {
         self.add_millis(w * Self::MILLIS_PER_WEEK)
     }
 
-    /// Day number from 1 to 31.
+    /// Adds milliseconds to a Unix date/time and returns the result, or `None` if the
+    /// result is out of range (instead of saturating like `add_millis`).
+    fn checked_add_millis(&self, m: u64) -> Option<Self> {
+        i64::try_from(m)
+            .ok()
+            .and_then(|m| self.checked_add_signed_millis(m))
+    }
+
+    /// Adds (or subtracts) millis to (or from) a Unix date/time and returns the result,
+    /// or `None` if the result is out of range.
+    fn checked_add_signed_millis(&self, m: i64) -> Option<Self> {
+        self.to_i64()
+            .checked_add(m)
+            .and_then(|value| Self::checked_from_i64(value))
+    }
+
+    /// Returns time corresponding to i64, or `None` if it is out of range (instead of
+    /// clamping like `from_i64`).
+    fn checked_from_i64(value: i64) -> Option<Self> {
+        (Self::MIN.to_i64()..=Self::MAX.to_i64())
+            .contains(&value)
+            .then(|| Self::from_i64(value))
+    }
+
+    /// Subtracts milliseconds from a Unix date/time and returns the result, or `None`
+    /// if the result is out of range (instead of saturating like `sub_millis`).
+    fn checked_sub_millis(&self, m: u64) -> Option<Self> {
+        i64::try_from(m)
+            .ok()
+            .and_then(|m| self.to_i64().checked_sub(m))
+            .and_then(|value| Self::checked_from_i64(value))
+    }
+
+    /// Day number from 1 to 31.  Requires the `chrono` feature.
     #[cfg(feature = "chrono")]
     fn day(&self) -> u32 {
         self.to_date_time_utc().day0() + 1
@@ -294,41 +359,57 @@ pub trait UnixTime: Sized + Clone {
     /// Returns the date/time rounded down to days.  (That is, date/time of the
     /// midnight which precedes the specified time.)
     fn floor_days(&self) -> Self {
-        // This can be accomplished via simple math because UTC has no TZ.
-        Self::from_i64((self.to_i64() / Self::MILLIS_PER_DAY as i64) * Self::MILLIS_PER_DAY as i64)
+        self.floor_to_interval(Self::MILLIS_PER_DAY)
     }
 
     /// Returns the date/time rounded down to hours.
     fn floor_hours(&self) -> Self {
-        Self::from_i64(
-            (self.to_i64() / Self::MILLIS_PER_HOUR as i64) * Self::MILLIS_PER_HOUR as i64,
-        )
+        self.floor_to_interval(Self::MILLIS_PER_HOUR)
     }
 
     /// Returns the date/time rounded down to minutes.
     fn floor_minutes(&self) -> Self {
-        Self::from_i64(
-            (self.to_i64() / Self::MILLIS_PER_MINUTE as i64) * Self::MILLIS_PER_MINUTE as i64,
-        )
+        self.floor_to_interval(Self::MILLIS_PER_MINUTE)
     }
 
     /// Returns the date/time rounded down to seconds.
     fn floor_seconds(&self) -> Self {
-        Self::from_i64(
-            (self.to_i64() / Self::MILLIS_PER_SECOND as i64) * Self::MILLIS_PER_SECOND as i64,
-        )
+        self.floor_to_interval(Self::MILLIS_PER_SECOND)
+    }
+
+    /// Returns the date/time rounded down to a multiple of `interval_millis` since the
+    /// epoch, e.g. `15 * MILLIS_PER_MINUTE` for quarter hours.  This can be accomplished
+    /// via simple math because UTC has no TZ.  An interval of zero is ignored.
+    fn floor_to_interval(&self, interval_millis: u64) -> Self {
+        match i64::try_from(interval_millis) {
+            Ok(interval) if interval > 0 => {
+                let value = self.to_i64();
+                Self::from_i64(value - value.rem_euclid(interval))
+            }
+            _ => self.clone(),
+        }
     }
 
     /// Returns time corresponding to i64.
     fn from_i64(value: i64) -> Self;
 
-    /// Format `UnixMillis` as string.
+    /// Creates a Unix date/time from milliseconds since 1970, e.g. a JavaScript `Date.now()`.
+    fn from_millis(millis: i64) -> Self {
+        Self::from_i64(millis)
+    }
+
+    /// Creates a Unix date/time from seconds since 1970, e.g. a JWT `exp` claim.
+    fn from_seconds(seconds: i64) -> Self {
+        Self::from_i64(seconds.saturating_mul(Self::MILLIS_PER_SECOND as i64))
+    }
+
+    /// Format `UnixMillis` as string.  Requires the `chrono` feature.
     #[cfg(feature = "chrono")]
     fn format(&self, fmt: &str) -> String {
         self.to_date_time_utc().format(fmt).to_string()
     }
 
-    /// Create a `UnixMillis` from YMD HMS.
+    /// Create a `UnixMillis` from YMD HMS.  Requires the `chrono` feature.
     #[cfg(feature = "chrono")]
     fn from_ymdhms(
         year: u32,
@@ -371,7 +452,7 @@ pub trait UnixTime: Sized + Clone {
         self.millis_since(unix_time) / Self::MILLIS_PER_MINUTE
     }
 
-    /// Month number from 1 (Jan) to 12 (Dec).
+    /// Month number from 1 (Jan) to 12 (Dec).  Requires the `chrono` feature.
     #[cfg(feature = "chrono")]
     fn month(&self) -> u32 {
         self.to_date_time_utc().month0() + 1
@@ -409,7 +490,41 @@ pub trait UnixTime: Sized + Clone {
         self.sub_millis(minutes * Self::MILLIS_PER_WEEK)
     }
 
-    /// Returns i64 corresponding to time.
+    /// Returns a humanized description of the time relative to `now`, e.g. "3 hours ago"
+    /// or "in 2 days".  The largest unit (day, hour, minute, or second) of which there is
+    /// at least one is used, and the count is rounded to the nearest whole unit, with
+    /// halves rounded up (e.g. 90 minutes ago is "2 hours ago").  If that rounds up to the
+    /// next unit, the next unit is used (e.g. 59.5 minutes ago is "1 hour ago").  Differences
+    /// of under a second are "just now".  Requires the `chrono` feature.
+    #[cfg(feature = "chrono")]
+    fn to_relative(&self, now: impl UnixTime) -> String {
+        let millis = self.to_i64().saturating_sub(now.to_i64());
+        let abs = millis.unsigned_abs();
+        let units = [
+            (Self::MILLIS_PER_DAY, "day"),
+            (Self::MILLIS_PER_HOUR, "hour"),
+            (Self::MILLIS_PER_MINUTE, "minute"),
+            (Self::MILLIS_PER_SECOND, "second"),
+        ];
+        let Some(mut i) = units.iter().position(|(unit, _)| abs >= *unit) else {
+            return "just now".to_string();
+        };
+        let round = |unit: u64| abs / unit + u64::from(abs % unit >= unit / 2);
+        // Rounding may reach the next unit, e.g. 59.5 minutes is "1 hour", not "60 minutes".
+        if i > 0 && round(units[i].0) * units[i].0 >= units[i - 1].0 {
+            i -= 1;
+        }
+        let (unit, name) = units[i];
+        let count = round(unit);
+        let plural = if count == 1 { "" } else { "s" };
+        if millis < 0 {
+            format!("{count} {name}{plural} ago")
+        } else {
+            format!("in {count} {name}{plural}")
+        }
+    }
+
+    /// Returns the `chrono` UTC date/time corresponding to time.  Requires the `chrono` feature.
     #[cfg(feature = "chrono")]
     fn to_date_time_utc(&self) -> DateTime<Utc> {
         match Utc.timestamp_millis_opt(self.to_i64()) {
@@ -419,7 +534,7 @@ pub trait UnixTime: Sized + Clone {
         }
     }
 
-    /// Returns a reasonable string representation of the time.
+    /// Returns a reasonable string representation of the time.  Requires the `chrono` feature.
     #[cfg(feature = "chrono")]
     fn to_default_format(&self) -> String {
         self.format("%Y-%m-%d %H:%M")
@@ -433,14 +548,14 @@ pub trait UnixTime: Sized + Clone {
         self.millis_since(unix_time) / Self::MILLIS_PER_WEEK
     }
 
-    /// Year, e.g. 2024.
+    /// Year, e.g. 2024.  Requires the `chrono` feature.
     #[cfg(feature = "chrono")]
     fn year(&self) -> u32 {
         let (_ad, year) = self.to_date_time_utc().year_ce();
         year
     }
 
-    /// Convert a `UnixMillis` into YMD HMS.
+    /// Convert a `UnixMillis` into YMD HMS.  Requires the `chrono` feature.
     #[cfg(feature = "chrono")]
     fn ymdhms(&self) -> (u32, u32, u32, u32, u32, u32) {
         let dt = self.to_date_time_utc();
@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use crate::common::Error;
+use crate::common::{strip_provider_prefix, Error};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
@@ -10,6 +10,13 @@ use serde::{Deserialize, Serialize};
 pub struct VideoResourceId(pub String);
 crate::impl_wrapper_str!(VideoResourceId);
 
+impl VideoResourceId {
+    /// Returns the ID without the expected provider prefix, e.g. "abc" for "youtube/abc".
+    pub fn strip_provider_prefix(&self, provider: &str) -> Result<&str, Error> {
+        strip_provider_prefix(&self.0, provider)
+    }
+}
+
 /// Video cloud.
 #[async_trait]
 pub trait CloudVideos {
@@ -6,7 +6,8 @@ use crate::common::{CubConfig, Error};
 use crate::log::StringLogger;
 use async_trait::async_trait;
 use hyper::StatusCode;
-use reqwest::Client;
+use reqwest::{Client, Response};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::time::Duration;
@@ -54,57 +55,34 @@ impl YoutubeVideos {
         Error::Http(StatusCode::FAILED_DEPENDENCY, format!("{}", e))
     }
 
-    fn parse_resource_id(resource_id: &VideoResourceId) -> Result<String, Error> {
-        let mut split = resource_id.0.splitn(2, '/');
-        if split
-            .next()
-            .map(|s| s != YOUTUBE_RESOURCE_PREFIX)
-            .unwrap_or(true)
-        {
-            Err(Error::Http(
-                StatusCode::NOT_ACCEPTABLE,
-                format!(
-                    "{}: expected '{YOUTUBE_RESOURCE_PREFIX}' prefix in resource ID",
-                    resource_id.0
-                ),
-            ))
-        } else if let Some(id) = split.next() {
-            Ok(id.to_string())
-        } else {
-            Err(Error::Http(
-                StatusCode::NOT_ACCEPTABLE,
-                format!("{}: invalid video resource ID", resource_id.0),
-            ))
-        }
-    }
-
-    fn parse_result<'a, T: Deserialize<'a>>(text: &'a String) -> Result<T, Error> {
-        match serde_json::from_str(&text) {
-            Ok(response) => Ok(response),
-            Err(_) => {
-                #[derive(Deserialize)]
-                struct YoutubeReason {
-                    // code: usize,
-                    message: String,
-                }
-                #[derive(Deserialize)]
-                struct YoutubeError {
-                    error: YoutubeReason,
-                }
-                match serde_json::from_str(&text) {
-                    Ok(YoutubeError {
-                        error: YoutubeReason { message },
-                    }) => Err(Error::Http(
-                        StatusCode::NOT_FOUND,
-                        format!("youtube error: {message}"),
-                    )),
-                    Err(_) => Err(Error::Http(
-                        StatusCode::FAILED_DEPENDENCY,
-                        format!("cannot parse youtube error: {text}"),
-                    )),
-                }
+    /// Parses a Youtube response, inspecting its status before its body.
+    async fn parse_response<T: DeserializeOwned>(response: Response) -> Result<T, Error> {
+        let status = response.status();
+        let text = response.text().await.map_err(Self::map_error)?;
+        if !status.is_success() {
+            #[derive(Deserialize)]
+            struct YoutubeReason {
+                // code: usize,
+                message: String,
+            }
+            #[derive(Deserialize)]
+            struct YoutubeError {
+                error: YoutubeReason,
             }
+            let message = match serde_json::from_str(&text) {
+                Ok(YoutubeError {
+                    error: YoutubeReason { message },
+                }) => format!("youtube error: {message}"),
+                Err(_) => format!("cannot parse youtube error: {text}"),
+            };
+            return Err(Error::from_provider_status(status, message));
         }
+        serde_json::from_str(&text).map_err(|e| {
+            Error::Http(
+                StatusCode::FAILED_DEPENDENCY,
+                format!("cannot parse youtube response: {e}"),
+            )
+        })
     }
 }
 
@@ -146,11 +124,11 @@ impl CloudVideos for YoutubeVideos {
         id: &VideoResourceId,
     ) -> Result<Vec<(VideoResourceId, VideoRecord)>, Error> {
         let _logger = StringLogger::new(self.debug);
-        let playlist_id = Self::parse_resource_id(id)?;
+        let playlist_id = id.strip_provider_prefix(YOUTUBE_RESOURCE_PREFIX)?;
         let parameters: Vec<_> = vec![
             ("part", "snippet"),
             ("key", &self.api_key),
-            ("playlistId", &playlist_id),
+            ("playlistId", playlist_id),
         ]
         .into_iter()
         .map(|(name, value)| format!("{name}={value}"))
@@ -167,9 +145,7 @@ impl CloudVideos for YoutubeVideos {
             .execute(request)
             .await
             .map_err(Self::map_error)?;
-        let result = response.text().await.map_err(Self::map_error)?;
-
-        let response: YoutubeResponse = Self::parse_result(&result)?;
+        let response: YoutubeResponse = Self::parse_response(response).await?;
         Ok(response
             .items
             .into_iter()
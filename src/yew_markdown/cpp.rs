@@ -4,55 +4,99 @@
 use super::MarkdownOptions;
 
 /// Parses string and applies simple cpp rules.
+///
+/// Directives must be on lines of their own, starting in the first column (so that e.g.
+/// an indented `#if` in a code block is text):
+/// - `#ifdef NAME` includes what follows if `options.components` renders `NAME`.
+/// - `#ifndef NAME` includes what follows if `options.components` doesn't render `NAME`.
+/// - `#if NAME` includes what follows if `options.defines` has `NAME` with a value
+///   other than `""`, `"0"` or `"false"`.
+/// - `#if NAME == VALUE` (or `#if NAME != VALUE`) compares the value of `NAME`.
+/// - `#else` includes what follows if the preceding condition was false.
+/// - `#endif` ends the block.
+///
+/// Blocks may be nested.  An `#endif` without a matching `#if` is removed, whereas an
+/// `#else` without one is left as text, as is an `#if` (and everything after it) which is
+/// never ended.
 pub(crate) fn cpp(input: &str, options: &MarkdownOptions) -> String {
-    let mut line: Vec<char> = Vec::new();
-    let mut output: Vec<char> = Vec::new();
-    let mut undef = false;
-
-    for ch in input.chars() {
-        match ch {
-            '\r' => {}
-            '\n' => {
-                line.push(ch);
-                process_line(&mut line, &mut output, &mut undef, options);
-            }
-            _ => line.push(ch),
-        }
-    } // for ch
-    process_line(&mut line, &mut output, &mut undef, options);
+    let mut preprocessor = Preprocessor::default();
+    for line in input.split_inclusive('\n') {
+        preprocessor.process_line(&line.replace('\r', ""), options);
+    }
+    preprocessor.finish()
+}
 
-    output.iter().collect()
+/// A conditional block.
+struct Block {
+    active: bool,
+    has_else: bool,
 }
 
-fn process_line(
-    line: &mut Vec<char>,
-    output: &mut Vec<char>,
-    undef: &mut bool,
-    options: &MarkdownOptions,
-) {
-    if !line.is_empty() {
-        if line[0] == '#' {
-            let text: String = line.iter().collect();
-            if text.starts_with("#ifdef ") {
-                let var: String = line.drain(7..).collect();
-                let var = var.trim();
-                *undef = (options.components)(&var, &var).is_none();
-            } else if text.starts_with("#ifndef ") {
-                let var: String = line.drain(8..).collect();
-                let var = var.trim();
-                *undef = (options.components)(&var, &var).is_some();
-            } else if text.starts_with("#endif") {
-                *undef = false;
-            } else if !*undef {
-                for ch in line.drain(..) {
-                    output.push(ch);
-                }
-            }
-        } else if !*undef {
-            for ch in line.drain(..) {
-                output.push(ch);
+#[derive(Default)]
+struct Preprocessor {
+    /// Open blocks, innermost last.
+    blocks: Vec<Block>,
+    output: String,
+    /// Unprocessed text of the outermost open block, in case it's never ended.
+    pending: String,
+    /// Length of `output` when the outermost open block began.
+    pending_start: usize,
+}
+
+impl Preprocessor {
+    fn evaluate(expression: &str, options: &MarkdownOptions) -> bool {
+        let value_of = |name: &str| options.defines.get(name.trim()).map(|v| v.trim());
+        if let Some((name, value)) = expression.split_once("!=") {
+            value_of(name) != Some(value.trim())
+        } else if let Some((name, value)) = expression.split_once("==") {
+            value_of(name) == Some(value.trim())
+        } else {
+            !matches!(value_of(expression), None | Some("" | "0" | "false"))
+        }
+    }
+
+    fn finish(mut self) -> String {
+        if !self.blocks.is_empty() {
+            // Leave text of unterminated block as-is.
+            self.output.truncate(self.pending_start);
+            self.output.push_str(&self.pending);
+        }
+        self.output
+    }
+
+    fn open(&mut self, active: bool) {
+        if self.blocks.is_empty() {
+            self.pending_start = self.output.len();
+            self.pending.clear();
+        }
+        self.blocks.push(Block {
+            active,
+            has_else: false,
+        });
+    }
+
+    fn process_line(&mut self, line: &str, options: &MarkdownOptions) {
+        let directive = line.trim_end();
+        if let Some(var) = directive.strip_prefix("#ifdef ") {
+            let var = var.trim();
+            self.open((options.components)(var, var).is_some());
+        } else if let Some(var) = directive.strip_prefix("#ifndef ") {
+            let var = var.trim();
+            self.open((options.components)(var, var).is_none());
+        } else if let Some(expression) = directive.strip_prefix("#if ") {
+            self.open(Self::evaluate(expression, options));
+        } else if directive == "#else" && self.blocks.last().map(|b| !b.has_else).unwrap_or(false) {
+            if let Some(block) = self.blocks.last_mut() {
+                block.active = !block.active;
+                block.has_else = true;
             }
+        } else if directive.starts_with("#endif") {
+            self.blocks.pop();
+        } else if self.blocks.iter().all(|b| b.active) {
+            self.output.push_str(line);
+        }
+        if !self.blocks.is_empty() {
+            self.pending.push_str(line);
         }
-        line.clear();
     }
 }
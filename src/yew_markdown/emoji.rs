@@ -0,0 +1,120 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::MarkdownTag;
+use std::collections::HashMap;
+
+/// Built-in shortcodes, a small subset of those supported by GitHub.
+const DEFAULT_EMOJI: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("angry", "😠"),
+    ("blush", "😊"),
+    ("broken_heart", "💔"),
+    ("bulb", "💡"),
+    ("clap", "👏"),
+    ("crown", "👑"),
+    ("cry", "😢"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("ghost", "👻"),
+    ("grin", "😁"),
+    ("heart", "❤️"),
+    ("heart_eyes", "😍"),
+    ("joy", "😂"),
+    ("laughing", "😆"),
+    ("ok_hand", "👌"),
+    ("pray", "🙏"),
+    ("rocket", "🚀"),
+    ("skull", "💀"),
+    ("smile", "😄"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("sunglasses", "😎"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("trophy", "🏆"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("x", "❌"),
+    ("zap", "⚡"),
+];
+
+/// Returns the built-in shortcode table, e.g. `"smile"` to `"😄"`.
+pub(crate) fn default_emoji() -> HashMap<String, String> {
+    DEFAULT_EMOJI
+        .iter()
+        .map(|(name, emoji)| (name.to_string(), emoji.to_string()))
+        .collect()
+}
+
+/// Replaces `:name:` shortcodes in the text of `tokens` with emoji from the table.
+/// Unknown shortcodes, and colons in e.g. `a:b` or `10:30`, are left as-is.
+pub(crate) fn resolve_emoji(tokens: &mut [MarkdownTag], emoji: &HashMap<String, String>) {
+    if emoji.is_empty() {
+        return;
+    }
+    for token in tokens {
+        match token {
+            MarkdownTag::B(text) | MarkdownTag::Em(text) | MarkdownTag::Span(text) => {
+                if text.contains(':') {
+                    *text = replace_shortcodes(text, emoji);
+                }
+            }
+            MarkdownTag::H(_, content)
+            | MarkdownTag::Li(content)
+            | MarkdownTag::Ol(content)
+            | MarkdownTag::P(content)
+            | MarkdownTag::Ul(content) => resolve_emoji(content, emoji),
+            MarkdownTag::Footnotes(notes) => {
+                for note in notes {
+                    resolve_emoji(note, emoji);
+                }
+            }
+            MarkdownTag::Table(titles, _, body) => {
+                for title in titles.iter_mut().filter(|title| title.contains(':')) {
+                    *title = replace_shortcodes(title, emoji);
+                }
+                for cell in body.iter_mut().flatten() {
+                    resolve_emoji(cell, emoji);
+                }
+            }
+            MarkdownTag::A(_, _)
+            | MarkdownTag::Br
+            | MarkdownTag::FootnoteRef(_, _)
+            | MarkdownTag::Img(_, _) => {}
+        }
+    }
+}
+
+fn replace_shortcodes(text: &str, emoji: &HashMap<String, String>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let replacement = rest[1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-')))
+            .filter(|end| rest[1 + end..].starts_with(':'))
+            .and_then(|end| emoji.get(&rest[1..=end]).map(|e| (end, e)));
+        match replacement {
+            Some((end, emoji)) => {
+                output.push_str(emoji);
+                rest = &rest[end + 2..];
+            }
+            None => {
+                // The colon may end this shortcode yet start the next, e.g. "a:smile:".
+                output.push(':');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
@@ -0,0 +1,91 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::parser::tokenize;
+use super::{MarkdownOptions, MarkdownTag};
+
+/// Footnote definitions, in the order they were defined.
+#[derive(Default)]
+pub(crate) struct Footnotes(Vec<(String, String)>);
+
+impl Footnotes {
+    /// Removes definitions, which must be on lines of their own, e.g. `[^1]: A note.`,
+    /// from `input`.  The first definition of an id wins.
+    pub(crate) fn extract(input: &str) -> (String, Self) {
+        let mut footnotes = Self::default();
+        if !input.contains("[^") {
+            return (input.to_owned(), footnotes);
+        }
+        let mut text = String::with_capacity(input.len());
+        for line in input.split_inclusive('\n') {
+            let definition = line
+                .trim_start()
+                .strip_prefix("[^")
+                .and_then(|rest| rest.split_once("]:"))
+                .filter(|(id, _)| !id.is_empty() && !id.contains(['[', ']']));
+            match definition {
+                Some((id, note)) => {
+                    if footnotes.number(id).is_none() {
+                        footnotes.0.push((id.to_owned(), note.trim().to_owned()));
+                    }
+                }
+                None => text.push_str(line),
+            }
+        }
+        (text, footnotes)
+    }
+
+    /// Returns the number (starting at 1) of the footnote with the specified id, if any.
+    fn number(&self, id: &str) -> Option<usize> {
+        self.0.iter().position(|(i, _)| i == id).map(|i| i + 1)
+    }
+
+    /// Replaces references (including those within notes), which are tokenized as links to
+    /// e.g. `^1`, with `FootnoteRef` (or literal text, if undefined) and appends the
+    /// footnotes to `tokens`.
+    pub(crate) fn resolve(self, tokens: &mut Vec<MarkdownTag>, options: &MarkdownOptions) {
+        let mut counts = vec![0; self.0.len()];
+        self.resolve_references(tokens, &mut counts);
+        if !self.0.is_empty() {
+            // Notes may refer to other notes, e.g. `[^1]: See [^2].`
+            let notes = self
+                .0
+                .iter()
+                .map(|(_, note)| {
+                    let mut note = tokenize(note, options);
+                    self.resolve_references(&mut note, &mut counts);
+                    note
+                })
+                .collect();
+            tokens.push(MarkdownTag::Footnotes(notes));
+        }
+    }
+
+    /// `counts` holds how many references to each footnote were resolved so far.
+    fn resolve_references(&self, tokens: &mut [MarkdownTag], counts: &mut [usize]) {
+        for token in tokens {
+            match token {
+                MarkdownTag::A(href, text) if href == text && text.starts_with('^') => {
+                    *token = match self.number(&text[1..]) {
+                        Some(n) => {
+                            counts[n - 1] += 1;
+                            MarkdownTag::FootnoteRef(n, counts[n - 1])
+                        }
+                        None => MarkdownTag::Span(format!("[{text}]")),
+                    };
+                }
+                MarkdownTag::H(_, content)
+                | MarkdownTag::Li(content)
+                | MarkdownTag::Ol(content)
+                | MarkdownTag::P(content)
+                | MarkdownTag::Ul(content) => self.resolve_references(content, counts),
+                MarkdownTag::Table(_, _, body) => {
+                    for cell in body.iter_mut().flatten() {
+                        self.resolve_references(cell, counts);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use std::collections::HashMap;
+
+/// Resolves reference-style links into inline links.
+///
+/// Definitions must be on lines of their own, e.g. `[id]: https://example.com "Title"`,
+/// and are removed from the output.  Ids are case-insensitive and the first definition
+/// of an id wins.  The title, if any, is ignored.
+/// - `[text][id]` becomes `[text](url)`, or literal text if `id` is undefined.
+/// - `[text][]` and `[text]` become `[text](url)` if `text` is a defined id.  Otherwise,
+///   `[text]` is left as-is.
+pub(crate) fn resolve_links(input: &str) -> String {
+    if !input.contains("]:") {
+        return resolve_references(input, &HashMap::new());
+    }
+    let mut definitions: HashMap<String, String> = HashMap::new();
+    let mut text = String::with_capacity(input.len());
+    for line in input.split_inclusive('\n') {
+        if let Some((id, url)) = parse_definition(line) {
+            definitions.entry(id).or_insert(url);
+        } else {
+            text.push_str(line);
+        }
+    }
+    resolve_references(&text, &definitions)
+}
+
+/// Parses a definition such as `[id]: <url> "title"`, returning the lowercase id and url.
+fn parse_definition(line: &str) -> Option<(String, String)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        // Indented too much to be a definition.
+        return None;
+    }
+    let (id, rest) = trimmed.strip_prefix('[')?.split_once("]:")?;
+    if id.trim().is_empty() || id.contains(['[', ']']) {
+        return None;
+    }
+    let rest = rest.trim();
+    let (url, title) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let url = url.strip_prefix('<').unwrap_or(url);
+    let url = url.strip_suffix('>').unwrap_or(url);
+    let title = title.trim();
+    let is_title = title.is_empty()
+        || [('"', '"'), ('\'', '\''), ('(', ')')]
+            .iter()
+            .any(|(open, close)| {
+                title.len() > 1 && title.starts_with(*open) && title.ends_with(*close)
+            });
+    (!url.is_empty() && is_title).then(|| (id.trim().to_lowercase(), url.to_owned()))
+}
+
+/// Rewrites `[text][id]`, `[text][]` and `[text]` references using `definitions`.
+fn resolve_references(text: &str, definitions: &HashMap<String, String>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(['[', '\\']) {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with('\\') {
+            // Copy escaped character as-is.
+            let len = rest[1..].chars().next().map(char::len_utf8).unwrap_or(0) + 1;
+            output.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+        let Some(end) = rest[1..].find([']', '[']).map(|i| i + 1) else {
+            break;
+        };
+        if !rest[end..].starts_with(']') {
+            // Not a reference, e.g. "[a [b]".
+            output.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        let label = &rest[1..end];
+        let after = &rest[end + 1..];
+        let lookup = |id: &str| definitions.get(&id.trim().to_lowercase());
+        if after.starts_with('(') {
+            // Already an inline link.
+            output.push_str(&rest[..=end]);
+            rest = after;
+        } else if let Some(id_end) = after
+            .strip_prefix('[')
+            .and_then(|a| a.find([']', '[']).filter(|i| a[*i..].starts_with(']')))
+        {
+            let id = &after[1..=id_end];
+            let id = if id.is_empty() { label } else { id };
+            match lookup(id) {
+                Some(url) => output.push_str(&format!("[{label}]({url})")),
+                None => output.push_str(&format!("\\[{label}\\]\\[{}\\]", &after[1..=id_end])),
+            }
+            rest = &after[id_end + 2..];
+        } else {
+            match lookup(label) {
+                Some(url) => output.push_str(&format!("[{label}]({url})")),
+                None => output.push_str(&rest[..=end]),
+            }
+            rest = after;
+        }
+    }
+    output.push_str(rest);
+    output
+}
@@ -0,0 +1,67 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::text::push_text;
+use super::{parse, MarkdownOptions, MarkdownTag};
+
+/// Metadata of a markdown document, e.g. for link previews.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MarkdownMeta {
+    /// Text of the first heading.
+    pub title: Option<String>,
+    /// URL of the first image.
+    pub first_image: Option<String>,
+    /// Number of words of text.
+    pub word_count: usize,
+}
+
+/// Parse markdown `input` and return its `MarkdownMeta`.
+pub fn markdown_metadata(input: &str, options: &MarkdownOptions) -> MarkdownMeta {
+    let tokens = parse(input, options);
+    let mut meta = MarkdownMeta::default();
+    find_metadata(&tokens, &mut meta);
+    let mut text = String::new();
+    push_text(&tokens, &mut text);
+    meta.word_count = text.split_whitespace().count();
+    meta
+}
+
+fn find_metadata(tokens: &[MarkdownTag], meta: &mut MarkdownMeta) {
+    for token in tokens {
+        match token {
+            MarkdownTag::H(_, content) => {
+                if meta.title.is_none() {
+                    let mut title = String::new();
+                    push_text(content, &mut title);
+                    meta.title = Some(title.split_whitespace().collect::<Vec<_>>().join(" "));
+                }
+                find_metadata(content, meta);
+            }
+            MarkdownTag::Img(src, _) => {
+                if meta.first_image.is_none() {
+                    meta.first_image = Some(src.to_string());
+                }
+            }
+            MarkdownTag::Li(content)
+            | MarkdownTag::Ol(content)
+            | MarkdownTag::P(content)
+            | MarkdownTag::Ul(content) => find_metadata(content, meta),
+            MarkdownTag::Table(_, _, rows) => {
+                for cell in rows.iter().flatten() {
+                    find_metadata(cell, meta);
+                }
+            }
+            MarkdownTag::Footnotes(notes) => {
+                for note in notes {
+                    find_metadata(note, meta);
+                }
+            }
+            MarkdownTag::A(_, _)
+            | MarkdownTag::B(_)
+            | MarkdownTag::Br
+            | MarkdownTag::Em(_)
+            | MarkdownTag::FootnoteRef(_, _)
+            | MarkdownTag::Span(_) => {}
+        }
+    }
+}
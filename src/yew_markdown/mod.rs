@@ -2,30 +2,67 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 mod cpp;
+mod emoji;
+mod footnotes;
+mod links;
+mod metadata;
 mod parser;
 mod renderer;
+mod text;
 
 use self::cpp::cpp;
+use self::footnotes::Footnotes;
+use self::links::resolve_links;
 use self::parser::tokenize;
 use self::renderer::yew_html;
 use yew::Html;
 
-pub use self::renderer::MarkdownOptions;
-pub(crate) use self::renderer::MarkdownTag;
+pub use self::metadata::{markdown_metadata, MarkdownMeta};
+pub use self::renderer::{MarkdownAlign, MarkdownOptions, MarkdownOptionsBuilder, MarkdownTag};
+pub use self::text::markdown_to_text;
 
 /// Parse markdown `input` and emit Yew `Html`.
 pub fn markdown(input: &str, options: &MarkdownOptions) -> Html {
-    let preprocessed = cpp(input, options);
-    let tokens = tokenize(&preprocessed);
-    yew_html(tokens, options)
+    yew_html(parse(input, options), options)
+}
+
+/// Parse markdown `input` and return tokens, e.g. for rendering to something other than Yew.
+pub fn parse(input: &str, options: &MarkdownOptions) -> Vec<MarkdownTag> {
+    let (text, footnotes) = Footnotes::extract(&cpp(input, options));
+    let mut tokens = tokenize(&resolve_links(&text), options);
+    footnotes.resolve(&mut tokens, options);
+    tokens
 }
 
 // cargo test --package engine_client --lib -- yew::markdown::tests --nocapture
 #[cfg(test)]
 mod yew_markdown_tests {
-    use crate::yew_markdown::{markdown, MarkdownOptions};
+    use crate::yew_markdown::cpp::cpp;
+    use crate::yew_markdown::parser::tokenize;
+    use crate::yew_markdown::{
+        markdown, markdown_metadata, markdown_to_text, parse, MarkdownAlign, MarkdownOptions,
+        MarkdownTag,
+    };
     use yew::{function_component, Html, Properties, ServerRenderer};
 
+    #[derive(PartialEq, Properties)]
+    struct RawHtmlProps {
+        html: Html,
+    }
+
+    #[function_component(RawHtml)]
+    fn raw_html(props: &RawHtmlProps) -> Html {
+        props.html.clone()
+    }
+
+    async fn render_markdown(input: &'static str) -> String {
+        ServerRenderer::<RawHtml>::with_props(move || RawHtmlProps {
+            html: markdown(input, &MarkdownOptions::default()),
+        })
+        .render()
+        .await
+    }
+
     #[tokio::test]
     async fn markdown_tests() {
         let input = r#"
@@ -81,24 +118,503 @@ Different and last paragraph
 
 "#;
 
-        #[derive(PartialEq, Properties)]
-        struct RawHtmlProps {
-            html: Html,
+        let output = render_markdown(input).await;
+
+        println!("{output}");
+    }
+
+    #[tokio::test]
+    async fn markdown_escape_tests() {
+        let input = "Hi <img src=x onerror=alert(1)> & [bye](<script>)";
+        let output = render_markdown(input).await;
+        println!("{output}");
+        assert!(!output.contains("<img"));
+        assert!(!output.contains("<script"));
+        assert!(output.contains("&lt;img src=x onerror=alert(1)&gt; &amp; "));
+    }
+
+    #[tokio::test]
+    async fn empty_input_tests() {
+        let span = |s: &str| vec![MarkdownTag::P(vec![MarkdownTag::Span(s.to_string())])];
+        let options = MarkdownOptions::default();
+        for input in ["", " ", "\t", "\n", "\n\n\n", " \n \n\t\n", "\r\n\r\n"] {
+            assert_eq!(tokenize(input, &options), vec![], "{input:?}");
+        }
+        for input in ["", "\n\n"] {
+            let output = render_markdown(input).await;
+            assert!(!output.contains("<p"), "{output}");
         }
 
-        #[function_component(RawHtml)]
-        fn raw_html(props: &RawHtmlProps) -> Html {
-            props.html.clone()
+        // Unterminated constructs are emitted verbatim.
+        for input in ["**", "[", "|", "*", "_", "#", "1.", "hi **", "| a | b"] {
+            assert_eq!(tokenize(input, &options), span(input), "{input:?}");
         }
+        assert_eq!(
+            tokenize("hi\n\n**", &options),
+            vec![span("hi").remove(0), span("**").remove(0)]
+        );
+        assert_eq!(
+            tokenize("[link]", &options),
+            vec![MarkdownTag::P(vec![MarkdownTag::A(
+                "link".to_string(),
+                "link".to_string()
+            )])]
+        );
+        assert!(matches!(
+            tokenize("| a |\n|---|\n| 1 |", &options).as_slice(),
+            [MarkdownTag::Table(_, _, body)] if body.len() == 1
+        ));
+        assert!(render_markdown("**").await.contains("**"));
+    }
 
-        let html = ServerRenderer::<RawHtml>::with_props(move || RawHtmlProps {
-            html: markdown(input, &MarkdownOptions::default()),
-        })
-        .render()
-        .await;
+    #[test]
+    fn allowed_link_schemes_tests() {
+        let a = |href: &str, text: &str| MarkdownTag::A(href.to_string(), text.to_string());
+        let span = |s: &str| MarkdownTag::Span(s.to_string());
+        let mut options = MarkdownOptions::default();
+        let input =
+            "[a](https://x.com) [b](javascript:alert`1`) [c](JavaScript:x) [d](java\tscript:x) \
+            ![e](data:image/png;base64,x) [f](mailto:me@x.com) [g](/path:x) [h](page#a:b)";
+        let tokens = tokenize(input, &options);
+        println!("{tokens:?}");
+        assert_eq!(
+            tokens,
+            vec![MarkdownTag::P(vec![
+                a("https://x.com", "a"),
+                span(" "),
+                span("b"),
+                span(" "),
+                span("c"),
+                span(" "),
+                span("d"),
+                span(" "),
+                span("e"),
+                span(" "),
+                a("mailto:me@x.com", "f"),
+                span(" "),
+                a("/path:x", "g"),
+                span(" "),
+                a("page#a:b", "h"),
+            ])]
+        );
+
+        options.allowed_link_schemes.push("data".to_string());
+        assert_eq!(
+            tokenize("![e](data:x)", &options),
+            vec![MarkdownTag::P(vec![MarkdownTag::Img(
+                "data:x".to_string(),
+                "e".to_string()
+            )])]
+        );
+    }
+
+    #[test]
+    fn escape_tests() {
+        let span = |s: &str| vec![MarkdownTag::P(vec![MarkdownTag::Span(s.to_string())])];
+        let options = MarkdownOptions::default();
+        for (input, expected) in [
+            (r"\*not bold\*", "*not bold*"),
+            (r"a \\ b", r"a \ b"),
+            (r"\_not italic\_", "_not italic_"),
+            (r"\[not a link\]", "[not a link]"),
+            (r"\| not | a table |", "| not | a table |"),
+            (r"\# not a heading", "# not a heading"),
+            (r"\- not a bullet", "- not a bullet"),
+            (r"1\. not a bullet", "1. not a bullet"),
+            (r"trailing \", r"trailing \"),
+        ] {
+            assert_eq!(tokenize(input, &options), span(expected), "{input:?}");
+        }
+        assert_eq!(
+            tokenize(r"**a\*b** _c\_d_ [e\]f](g)", &options),
+            vec![MarkdownTag::P(vec![
+                MarkdownTag::B("a*b".to_string()),
+                MarkdownTag::Span(" ".to_string()),
+                MarkdownTag::Em("c_d".to_string()),
+                MarkdownTag::Span(" ".to_string()),
+                MarkdownTag::A("g".to_string(), "e]f".to_string()),
+            ])]
+        );
+    }
+
+    #[test]
+    fn markdown_options_builder_tests() {
+        let options = MarkdownOptions::builder()
+            .allowed_link_schemes(&["https"])
+            .components(|href, _| (href == "widget").then(Html::default))
+            .define("MOBILE", "1")
+            .h_level(1)
+            .hard_breaks(true)
+            .build();
+        assert_eq!(options.allowed_link_schemes, vec!["https".to_string()]);
+        assert!((options.components)("widget", "").is_some());
+        assert!((options.components)("other", "").is_none());
+        assert_eq!(options.defines.get("MOBILE").map(String::as_str), Some("1"));
+        assert_eq!(options.h_level, 1);
+        assert!(options.hard_breaks);
+
+        let defaults = MarkdownOptions::builder().build();
+        assert_eq!(defaults.h_level, MarkdownOptions::default().h_level);
+        assert_eq!(
+            defaults.allowed_link_schemes,
+            MarkdownOptions::default().allowed_link_schemes
+        );
+    }
+
+    #[test]
+    fn emoji_tests() {
+        let span = |s: &str| vec![MarkdownTag::P(vec![MarkdownTag::Span(s.to_string())])];
+        let options = MarkdownOptions::default();
+        for (input, expected) in [
+            (":smile:", "😄"),
+            ("Hi :wave::smile: there :+1:", "Hi 👋😄 there 👍"),
+            (":not_an_emoji: and :smile", ":not_an_emoji: and :smile"),
+            ("a:b at 10:30:45 a:smile:", "a:b at 10:30:45 a😄"),
+            ("::smile:::", ":😄::"),
+        ] {
+            assert_eq!(tokenize(input, &options), span(expected), "{input:?}");
+        }
+        assert_eq!(
+            tokenize("**:fire:** :x:", &options),
+            vec![MarkdownTag::P(vec![
+                MarkdownTag::B("🔥".to_string()),
+                MarkdownTag::Span(" ❌".to_string()),
+            ])]
+        );
+
+        let tokens = tokenize("| :star: | a:b |\n|--|--|\n| :x: | c:d |", &options);
+        println!("{tokens:?}");
+        match tokens.as_slice() {
+            [MarkdownTag::Table(titles, _, body)] => {
+                assert_eq!(titles.len(), 2);
+                assert_eq!(titles[0].trim(), "⭐");
+                assert_eq!(titles[1].trim(), "a:b");
+                assert_eq!(body[0][0], span("❌ "));
+                assert_eq!(body[0][1], span("c:d "));
+            }
+            _ => panic!("expected table"),
+        }
+
+        let options = MarkdownOptions::builder()
+            .emoji([("cub".to_string(), "🐻".to_string())].into())
+            .build();
+        assert_eq!(tokenize(":cub: :smile:", &options), span("🐻 :smile:"));
+    }
+
+    #[tokio::test]
+    async fn footnote_tests() {
+        let span = |s: &str| MarkdownTag::Span(s.to_string());
+        let p = |s: &str| vec![MarkdownTag::P(vec![span(s)])];
+        let options = MarkdownOptions::default();
+        let input = "Cubs[^cub] and bears[^bear], not [^wolf]. Cubs[^cub]!\n\n\
+            [^bear]: Big **and** furry.\n\
+            [^cub]: Small.\n\
+            [^bear]: Ignored.\n";
+        let tokens = parse(input, &options);
+        println!("{tokens:?}");
+        assert_eq!(
+            tokens,
+            vec![
+                MarkdownTag::P(vec![
+                    span("Cubs"),
+                    MarkdownTag::FootnoteRef(2, 1),
+                    span(" and bears"),
+                    MarkdownTag::FootnoteRef(1, 1),
+                    span(", not "),
+                    span("[^wolf]"),
+                    span(". Cubs"),
+                    MarkdownTag::FootnoteRef(2, 2),
+                    span("!"),
+                ]),
+                MarkdownTag::Footnotes(vec![
+                    vec![MarkdownTag::P(vec![
+                        span("Big "),
+                        MarkdownTag::B("and".to_string()),
+                        span(" furry."),
+                    ])],
+                    p("Small."),
+                ]),
+            ]
+        );
+        // A note may refer to another note.
+        let tokens = parse("A[^1]\n\n[^1]: See [^2].\n[^2]: Nested.\n", &options);
+        assert_eq!(
+            tokens,
+            vec![
+                MarkdownTag::P(vec![span("A"), MarkdownTag::FootnoteRef(1, 1)]),
+                MarkdownTag::Footnotes(vec![
+                    vec![MarkdownTag::P(vec![
+                        span("See "),
+                        MarkdownTag::FootnoteRef(2, 1),
+                        span("."),
+                    ])],
+                    p("Nested."),
+                ]),
+            ]
+        );
+        assert_eq!(
+            markdown_to_text("No [^notes] here.", &options, None),
+            "No [^notes] here."
+        );
+
+        let output = render_markdown("A[^1]\n\n[^1]: Note.").await;
+        println!("{output}");
+        assert!(output.contains(r##"<sup><a href="#fn1" id="fnref1">1</a></sup>"##));
+        assert!(output.contains(r#"<li id="fn1"><p>Note.</p></li>"#));
+        assert_eq!(
+            markdown_to_text("A[^1]\n\n[^1]: Note.", &options, None),
+            "A Note."
+        );
+    }
+
+    #[test]
+    fn cpp_tests() {
+        let mut options = MarkdownOptions::default();
+        options
+            .defines
+            .insert("MOBILE".to_string(), "1".to_string());
+        options
+            .defines
+            .insert("PLATFORM".to_string(), "ios".to_string());
+
+        let input =
+            "a\n#if MOBILE\nb\n#if PLATFORM == android\nc\n#else\nd\n#endif\n#else\ne\n#endif\nf\n";
+        assert_eq!(cpp(input, &options), "a\nb\nd\nf\n");
+
+        let input = "#if DESKTOP\na\n#else\nb\n#endif\n#if PLATFORM != ios\nc\n#endif\n";
+        assert_eq!(cpp(input, &options), "b\n");
+
+        // An unmatched #endif is removed, other unmatched directives are left as-is.
+        let input = "#else\na\n#if DESKTOP\nb\n#if MOBILE\nc\n#endif\n";
+        assert_eq!(cpp(&format!("#endif\n{input}"), &options), input);
+
+        // Indented directives, e.g. in a code block, are text.
+        let input = "    #if DESKTOP\n    a\n    #endif\n";
+        assert_eq!(cpp(input, &options), input);
+    }
+
+    #[test]
+    fn reference_link_tests() {
+        let a = |href: &str, text: &str| MarkdownTag::A(href.to_string(), text.to_string());
+        let span = |s: &str| MarkdownTag::Span(s.to_string());
+        let options = MarkdownOptions::default();
+        let input = "See [the docs][Docs], [docs][] and [docs], not [this][nope].\n\n\
+            [docs]: https://example.com/docs \"Docs\"\n\
+            [docs]: https://example.com/ignored\n\
+            ![logo][img]\n\n\
+            [img]: <logo.png>\n";
+        let tokens = parse(input, &options);
+        println!("{tokens:?}");
+        let docs = "https://example.com/docs";
+        assert_eq!(
+            tokens,
+            vec![
+                MarkdownTag::P(vec![
+                    span("See "),
+                    a(docs, "the docs"),
+                    span(", "),
+                    a(docs, "docs"),
+                    span(" and "),
+                    a(docs, "docs"),
+                    span(", not [this][nope]."),
+                ]),
+                MarkdownTag::P(vec![MarkdownTag::Img(
+                    "logo.png".to_string(),
+                    "logo".to_string()
+                )]),
+            ]
+        );
+
+        // Undefined shortcuts are still links to themselves, and inline links are unchanged.
+        assert_eq!(
+            parse("[queen] [king](k)", &options),
+            vec![MarkdownTag::P(vec![
+                a("queen", "queen"),
+                span(" "),
+                a("k", "king")
+            ])]
+        );
+    }
+
+    #[test]
+    fn markdown_metadata_tests() {
+        let input =
+            "Intro with ![a cat](cat.png) and ![a dog](dog.png) too.\n## The **Title**\n# Another\n";
+        let meta = markdown_metadata(input, &MarkdownOptions::default());
+        println!("{meta:?}");
+        assert_eq!(meta.title.as_deref(), Some("The Title"));
+        assert_eq!(meta.first_image.as_deref(), Some("cat.png"));
+        assert_eq!(meta.word_count, 7);
+    }
+
+    #[test]
+    fn markdown_to_text_tests() {
+        let input =
+            "# Title\nSome **bold** and _italic_ [link](https://example.com).\n\n* one\n* two\n";
+        let options = MarkdownOptions::default();
+        let text = markdown_to_text(input, &options, None);
+        assert_eq!(text, "Title Some bold and italic link. one two");
+        assert_eq!(
+            markdown_to_text(input, &options, Some(20)),
+            "Title Some bold and…"
+        );
+        assert_eq!(
+            markdown_to_text(input, &options, Some(18)),
+            "Title Some bold…"
+        );
+        assert_eq!(markdown_to_text(input, &options, Some(100)), text);
+    }
 
-        let output = format!("{html:?}");
+    #[test]
+    fn nested_list_tests() {
+        let span = |s: &str| MarkdownTag::Span(s.to_string());
+        let tokens = tokenize("- a\n  - b\n  1. c\n- d", &MarkdownOptions::default());
+        println!("{tokens:?}");
+        assert_eq!(
+            tokens,
+            vec![MarkdownTag::Ul(vec![
+                MarkdownTag::Li(vec![
+                    span("a"),
+                    MarkdownTag::Ul(vec![MarkdownTag::Li(vec![span("b")])]),
+                    MarkdownTag::Ol(vec![MarkdownTag::Li(vec![span("c")])]),
+                ]),
+                MarkdownTag::Li(vec![span("d")]),
+            ])]
+        );
 
+        let tokens = tokenize("- a\n- b\n1. c", &MarkdownOptions::default());
+        println!("{tokens:?}");
+        assert_eq!(
+            tokens,
+            vec![
+                MarkdownTag::Ul(vec![
+                    MarkdownTag::Li(vec![span("a")]),
+                    MarkdownTag::Li(vec![span("b")]),
+                ]),
+                MarkdownTag::Ol(vec![MarkdownTag::Li(vec![span("c")])]),
+            ]
+        );
+    }
+
+    #[test]
+    fn line_break_tests() {
+        let span = |s: &str| MarkdownTag::Span(s.to_string());
+        let soft = tokenize("one\ntwo  \nthree", &MarkdownOptions::default());
+        println!("{soft:?}");
+        assert_eq!(
+            soft,
+            vec![MarkdownTag::P(vec![
+                span("one"),
+                span(" two"),
+                MarkdownTag::Br,
+                span("three"),
+            ])]
+        );
+
+        let separator = tokenize("one\u{2028}two", &MarkdownOptions::default());
+        assert_eq!(separator, vec![MarkdownTag::P(vec![span("one two")])]);
+
+        let options = MarkdownOptions {
+            hard_breaks: true,
+            ..Default::default()
+        };
+        let hard = tokenize("one\ntwo\n\n- three\n- four", &options);
+        println!("{hard:?}");
+        assert_eq!(
+            hard,
+            vec![
+                MarkdownTag::P(vec![span("one"), MarkdownTag::Br, span("two")]),
+                MarkdownTag::Ul(vec![
+                    MarkdownTag::Li(vec![span("three")]),
+                    MarkdownTag::Li(vec![span("four")]),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_tests() {
+        let tokens = parse(
+            "#ifdef X\n# Hidden\n#endif\n**Shown**",
+            &MarkdownOptions::default(),
+        );
+        println!("{tokens:?}");
+        assert_eq!(
+            tokens,
+            vec![MarkdownTag::P(vec![MarkdownTag::B("Shown".to_string())])]
+        );
+    }
+
+    #[test]
+    fn table_alignment_tests() {
+        let input = "| a | b | c | d |\n|:--|:-:|--:|---|\n| 1 | 2 | 3 | 4 |\n\nEnd";
+        let tokens = tokenize(input, &MarkdownOptions::default());
+        println!("{tokens:?}");
+        match tokens.as_slice() {
+            [MarkdownTag::Table(_, alignments, body), _] => {
+                assert_eq!(
+                    alignments,
+                    &vec![
+                        MarkdownAlign::Left,
+                        MarkdownAlign::Center,
+                        MarkdownAlign::Right,
+                        MarkdownAlign::Left
+                    ]
+                );
+                assert_eq!(body.len(), 1);
+            }
+            _ => panic!("expected table"),
+        }
+    }
+
+    #[tokio::test]
+    async fn ragged_table_tests() {
+        let output = render_markdown("| a | b |\n|---|--:|\n| 1 | 2 | 3 |").await;
         println!("{output}");
+        // The extra cell is kept, and aligned left.
+        assert!(output.contains(
+            r#"<td style="text-align:right">2 </td><td style="text-align:left">3 </td>"#
+        ));
+    }
+
+    // cargo test --release --features yew_markdown tokenize_benchmark -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn tokenize_benchmark() {
+        let section = r#"
+## Section
+Some **bold** text, some _italic_ text and a [link](https://example.com).
+The paragraph continues on a second line.
+
+* one
+* two **bold**
+  - nested _italic_
+3. three
+
+| Name | Value |
+|:-----|------:|
+| a | 1 |
+| b | 2 |
+
+"#;
+        let input = section.repeat(128);
+        let options = MarkdownOptions::default();
+        let iterations = 20;
+        // The fastest of several runs is the least affected by noise.
+        let elapsed = (0..7)
+            .map(|_| {
+                let start = std::time::Instant::now();
+                for _ in 0..iterations {
+                    assert!(!tokenize(&input, &options).is_empty());
+                }
+                start.elapsed()
+            })
+            .min()
+            .unwrap();
+        let bytes = input.len() * iterations;
+        println!(
+            "tokenize: {bytes} bytes in {elapsed:?} ({:.1} MB/s)",
+            bytes as f64 / elapsed.as_secs_f64() / 1_000_000.0
+        );
     }
 }
@@ -1,23 +1,74 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::MarkdownTag;
+use super::emoji::resolve_emoji;
+use super::{MarkdownAlign, MarkdownOptions, MarkdownTag};
 
 const DEBUG: bool = false;
 
+/// Stands in for a newline which is a line break rather than a space.  Occurrences in the
+/// input are treated as spaces, so only the tokenizer can produce a `MarkdownTag::Br`.
+const LINE_BREAK: char = '\u{2028}';
+
+fn list_tag(ordered: bool, bullets: Vec<MarkdownTag>) -> MarkdownTag {
+    if ordered {
+        MarkdownTag::Ol(bullets)
+    } else {
+        MarkdownTag::Ul(bullets)
+    }
+}
+
+fn close_list(
+    output: &mut Vec<MarkdownTag>,
+    line_type: LineType,
+    line_content: &mut Vec<MarkdownTag>,
+    lists: &mut Vec<ListLevel>,
+) {
+    if let Some(ListLevel {
+        mut bullets,
+        ordered,
+        parent_content,
+        ..
+    }) = lists.pop()
+    {
+        emit_markdown(&mut bullets, line_type, line_content);
+        if lists.is_empty() {
+            if !bullets.is_empty() {
+                output.push(list_tag(ordered, bullets));
+            }
+        } else {
+            // A nested list becomes part of the bullet that contains it.
+            if DEBUG {
+                println!("End nested list");
+            }
+            *line_content = parent_content;
+            if !bullets.is_empty() {
+                line_content.push(list_tag(ordered, bullets));
+            }
+        }
+    }
+}
+
 fn emit_anchor(
     line_content: &mut Vec<MarkdownTag>,
     span_content: &mut Vec<char>,
     start_index: usize,
+    options: &MarkdownOptions,
 ) {
-    if start_index != 0 {
-        line_content.push(MarkdownTag::Span(
-            span_content[..start_index].iter().collect(),
-        ));
-        *span_content = span_content[start_index..].into();
-    }
-    if let Some(bracket_index) = span_content.iter().position(|c| *c == ']') {
-        let text: String = span_content[1..bracket_index].iter().collect();
+    // An image is an anchor preceded by '!'.
+    let image = start_index != 0 && span_content[start_index - 1] == '!';
+    let text_index = if image { start_index - 1 } else { start_index };
+    push_span(line_content, span_content, Some(text_index));
+    // The text may contain an escaped ']', so search for the closing bracket from the end.
+    let bracket_index = if span_content.last() == Some(&')') {
+        span_content.windows(2).rposition(|w| w == [']', '('])
+    } else {
+        span_content.iter().rposition(|c| *c == ']')
+    };
+    if let Some(bracket_index) = bracket_index {
+        let text: String = span_content[(start_index - text_index + 1)..bracket_index]
+            .iter()
+            .collect();
         let href_index = bracket_index + 2;
         let href = if href_index < span_content.len() {
             span_content[href_index..span_content.len() - 1]
@@ -26,7 +77,14 @@ fn emit_anchor(
         } else {
             text.clone()
         };
-        line_content.push(MarkdownTag::A(href, text));
+        line_content.push(if !is_allowed_link(&href, options) {
+            // Neutralize e.g. "javascript:" links by rendering them as plain text.
+            MarkdownTag::Span(text)
+        } else if image {
+            MarkdownTag::Img(href, text)
+        } else {
+            MarkdownTag::A(href, text)
+        });
         span_content.clear();
         if DEBUG {
             println!("Anchor done, line content is: {line_content:?}");
@@ -40,20 +98,13 @@ fn emit_markdown(
     line_content: &mut Vec<MarkdownTag>,
 ) {
     if !line_content.is_empty() {
+        // Copy the content, rather than taking it, so `line_content` keeps its capacity.
+        let content: Vec<_> = line_content.drain(..).collect();
         match line_type {
-            LineType::Bullet(_) => output.push(MarkdownTag::Li(line_content.drain(..).collect())),
-            LineType::Heading(n) => {
-                output.push(MarkdownTag::H(n, line_content.drain(..).collect()))
-            }
-            LineType::List(ol) => {
-                if ol {
-                    output.push(MarkdownTag::Ol(line_content.drain(..).collect()));
-                } else {
-                    output.push(MarkdownTag::Ul(line_content.drain(..).collect()));
-                }
-            }
-            LineType::Paragraph => output.push(MarkdownTag::P(line_content.drain(..).collect())),
-            LineType::Table => output.extend(line_content.drain(..).collect::<Vec<_>>()),
+            LineType::Bullet(_) => output.push(MarkdownTag::Li(content)),
+            LineType::Heading(n) => output.push(MarkdownTag::H(n, content)),
+            LineType::Paragraph => output.push(MarkdownTag::P(content)),
+            LineType::Table => output.extend(content),
             LineType::None => {}
         }
     }
@@ -66,24 +117,26 @@ fn emit_pending(
     output: &mut Vec<MarkdownTag>,
     line_type: LineType,
     line_content: &mut Vec<MarkdownTag>,
-    list: &mut Option<bool>,
-    bullets: &mut Vec<MarkdownTag>,
+    lists: &mut Vec<ListLevel>,
 ) {
-    if let Some(ordered) = list {
+    if lists.is_empty() {
+        emit_markdown(output, line_type, line_content);
+    } else {
         if DEBUG {
             println!("End list");
         }
-        emit_markdown(bullets, line_type, line_content);
-        emit_markdown(output, LineType::List(*ordered), bullets);
+        let mut line_type = line_type;
+        while !lists.is_empty() {
+            close_list(output, line_type, line_content, lists);
+            // The content of the parent bullet is restored to `line_content`.
+            line_type = LineType::Bullet('*');
+        }
         if !line_content.is_empty() {
             if DEBUG {
                 println!("WARNING: line content is not empty: {line_content:?}");
             }
             line_content.clear(); // Should be empty anyway.
         }
-        *list = None;
-    } else {
-        emit_markdown(output, line_type, line_content);
     }
 }
 
@@ -92,6 +145,7 @@ fn emit_table(
     line_content: &mut Vec<MarkdownTag>,
     span_content: &mut Vec<char>,
     titles: Vec<String>,
+    alignments: Vec<MarkdownAlign>,
     body: Vec<Vec<Vec<MarkdownTag>>>,
 ) {
     if body.is_empty() {
@@ -100,31 +154,78 @@ fn emit_table(
             println!("Rollback empty table");
         }
         let text = format!("{} {}", titles.join("|"), take_span(span_content, None));
-        span_content.extend(text.chars().collect::<Vec<_>>());
+        span_content.extend(text.chars());
     } else {
         if DEBUG {
             println!("Complete table");
         }
         line_content.clear(); // Should be empty anyway.
-        line_content.push(MarkdownTag::Table(titles, body));
+        line_content.push(MarkdownTag::Table(titles, alignments, body));
         emit_markdown(output, LineType::Table, line_content);
     }
 }
 
+/// Returns true if `href` is relative or its scheme is in `options.allowed_link_schemes`.
+fn is_allowed_link(href: &str, options: &MarkdownOptions) -> bool {
+    // Browsers ignore whitespace and control characters, e.g. "java\tscript:".
+    let chars = href
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control());
+    let scheme_len = chars
+        .clone()
+        .position(|c| matches!(c, ':' | '/' | '?' | '#'));
+    match scheme_len {
+        Some(len) if chars.clone().nth(len) == Some(':') => {
+            options.allowed_link_schemes.iter().any(|scheme| {
+                scheme.chars().count() == len
+                    && scheme
+                        .chars()
+                        .zip(chars.clone())
+                        .all(|(a, b)| a.eq_ignore_ascii_case(&b))
+            })
+        }
+        _ => true,
+    }
+}
+
+/// Parses a table underline such as `|:---|:---:|---:|` into column alignments.
+fn parse_alignments(underline: &str, columns: usize) -> Vec<MarkdownAlign> {
+    let mut alignments: Vec<MarkdownAlign> = underline
+        .split('|')
+        .map(str::trim)
+        .filter(|cell| !cell.is_empty())
+        .map(|cell| match (cell.starts_with(':'), cell.ends_with(':')) {
+            (true, true) if cell.len() > 1 => MarkdownAlign::Center,
+            (false, true) => MarkdownAlign::Right,
+            _ => MarkdownAlign::Left,
+        })
+        .collect();
+    alignments.resize(columns, MarkdownAlign::Left);
+    alignments
+}
+
 fn push_span(
     line_content: &mut Vec<MarkdownTag>,
     span_content: &mut Vec<char>,
     end_index: Option<usize>,
 ) {
-    let text = take_span(span_content, end_index);
-    // Ignore leading spaces.
-    let trimmed_text = text.trim_start().to_string();
-    if !(line_content.is_empty() && trimmed_text.is_empty()) {
-        line_content.push(MarkdownTag::Span(if line_content.is_empty() {
-            trimmed_text
-        } else {
-            text
-        }));
+    let mut text = take_span(span_content, end_index);
+    if line_content.is_empty() {
+        // Ignore leading spaces.
+        let leading = text.len() - text.trim_start().len();
+        text.drain(..leading);
+    }
+    if text.contains(LINE_BREAK) {
+        for (i, line) in text.split(LINE_BREAK).enumerate() {
+            if i != 0 {
+                line_content.push(MarkdownTag::Br);
+            }
+            if !line.is_empty() {
+                line_content.push(MarkdownTag::Span(line.to_string()));
+            }
+        }
+    } else if !text.is_empty() {
+        line_content.push(MarkdownTag::Span(text));
     }
 }
 
@@ -139,26 +240,40 @@ fn take_span(span_content: &mut Vec<char>, end_index: Option<usize>) -> String {
 }
 
 /// Parses markdown and returns a list of tokens that maps directly to HTML.
-pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
-    let mut bullets: Vec<MarkdownTag> = Vec::new();
+pub(crate) fn tokenize(input: &str, options: &MarkdownOptions) -> Vec<MarkdownTag> {
+    let mut indent = 0;
+    let mut newline = ' ';
     let mut line_content: Vec<MarkdownTag> = Vec::new();
     let mut tokenizer_state = Tokenizer::Start;
-    let mut list: Option<bool> = None; // false = <UL>, true = <OL>
+    let mut lists: Vec<ListLevel> = Vec::new(); // Innermost list is last.
     let mut output: Vec<MarkdownTag> = Vec::new();
-    let mut span_content: Vec<char> = Vec::new();
+    // Spans rarely exceed a few lines, so reserving this much avoids most reallocation.
+    let mut span_content: Vec<char> = Vec::with_capacity(input.len().min(1024));
     let mut quoted = false;
 
     if DEBUG {
         println!("DEBUG is on");
     }
 
-    for ch in input.chars() {
+    for ch in input
+        .chars()
+        .map(|ch| if ch == LINE_BREAK { ' ' } else { ch })
+    {
         if DEBUG {
             println!(
                 "{tokenizer_state:?}: char is '{ch}', span content is '{}'",
                 span_content.iter().collect::<String>()
             );
         }
+        // Fast path for the common case of an ordinary character within a span, which
+        // the general case below would also just push.
+        if !quoted
+            && matches!(tokenizer_state, Tokenizer::Found(_))
+            && !matches!(ch, '\\' | '\r' | '\n' | '_' | '[' | '*')
+        {
+            span_content.push(ch);
+            continue;
+        }
         let matched = if quoted {
             quoted = false;
             false
@@ -170,44 +285,48 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                 }
                 '\r' => true,
                 '\n' => {
+                    indent = 0;
+                    newline = ' ';
                     match tokenizer_state {
                         Tokenizer::Found(line_type) => {
-                            // Single newline
+                            // Single newline, which is a break if there are two trailing spaces.
+                            let trailing =
+                                span_content.iter().rev().take_while(|c| **c == ' ').count();
+                            if options.hard_breaks || trailing >= 2 {
+                                span_content.truncate(span_content.len() - trailing);
+                                newline = LINE_BREAK;
+                            }
                             push_span(&mut line_content, &mut span_content, None);
                             tokenizer_state = Tokenizer::Newline(line_type);
                         }
                         Tokenizer::Newline(line_type) => {
                             // Double newline
-                            emit_pending(
-                                &mut output,
-                                line_type,
-                                &mut line_content,
-                                &mut list,
-                                &mut bullets,
-                            );
+                            emit_pending(&mut output, line_type, &mut line_content, &mut lists);
                         }
                         Tokenizer::PreA(line_type, ']', start_index) => {
-                            emit_anchor(&mut line_content, &mut span_content, start_index);
+                            emit_anchor(&mut line_content, &mut span_content, start_index, options);
                             span_content.push(ch);
                             tokenizer_state = Tokenizer::Newline(line_type);
                         }
-                        Tokenizer::Table(false, titles, body, _) => {
+                        Tokenizer::Table(false, titles, alignments, body, _) => {
                             emit_table(
                                 &mut output,
                                 &mut line_content,
                                 &mut span_content,
                                 titles,
+                                alignments,
                                 body,
                             );
                             tokenizer_state = Tokenizer::Newline(LineType::Paragraph);
                             span_content.push(ch);
                         }
-                        Tokenizer::Table(true, titles, mut body, last_row) => {
+                        Tokenizer::Table(true, titles, alignments, mut body, last_row) => {
                             if DEBUG {
                                 println!("End table row: {last_row:?}");
                             }
                             body.push(last_row);
-                            tokenizer_state = Tokenizer::Table(false, titles, body, vec![]);
+                            tokenizer_state =
+                                Tokenizer::Table(false, titles, alignments, body, vec![]);
                         }
                         Tokenizer::Titles(line_type, titles) => {
                             if DEBUG {
@@ -216,15 +335,11 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                             tokenizer_state = Tokenizer::Underline(line_type, false, titles, 0);
                         }
                         Tokenizer::Underline(line_type, true, titles, _count) => {
-                            span_content.clear(); // Don't need table underline.
-                            emit_pending(
-                                &mut output,
-                                line_type,
-                                &mut line_content,
-                                &mut list,
-                                &mut bullets,
-                            );
-                            tokenizer_state = Tokenizer::Table(false, titles, vec![], vec![]);
+                            let alignments =
+                                parse_alignments(&take_span(&mut span_content, None), titles.len());
+                            emit_pending(&mut output, line_type, &mut line_content, &mut lists);
+                            tokenizer_state =
+                                Tokenizer::Table(false, titles, alignments, vec![], vec![]);
                         }
                         _ => tokenizer_state = Tokenizer::Newline(LineType::None),
                     };
@@ -233,10 +348,15 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                 ' ' | '\t' => {
                     match tokenizer_state {
                         Tokenizer::Newline(line_type) => {
+                            indent += 1;
                             tokenizer_state = Tokenizer::Indent(line_type)
                         }
+                        Tokenizer::Indent(_) | Tokenizer::Start => {
+                            indent += 1;
+                            span_content.push(ch);
+                        }
                         Tokenizer::PreA(line_type, ']', start_index) => {
-                            emit_anchor(&mut line_content, &mut span_content, start_index);
+                            emit_anchor(&mut line_content, &mut span_content, start_index, options);
                             span_content.push(ch);
                             tokenizer_state = Tokenizer::Found(line_type);
                         }
@@ -246,13 +366,7 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                             tokenizer_state = Tokenizer::Found(line_type);
                         }
                         Tokenizer::PreH(line_type, n) => {
-                            emit_pending(
-                                &mut output,
-                                line_type,
-                                &mut line_content,
-                                &mut list,
-                                &mut bullets,
-                            );
+                            emit_pending(&mut output, line_type, &mut line_content, &mut lists);
                             for _ in 0..n {
                                 span_content.pop();
                             }
@@ -264,19 +378,78 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                             } else {
                                 '*'
                             };
-                            if list.is_some() {
-                                if DEBUG {
-                                    println!("Continue list");
+                            let ordered = n != '*' && n != '-';
+                            span_content.clear(); // Ignore bullet.
+
+                            // Close any lists which are indented more deeply than this bullet.
+                            let mut line_type = line_type;
+                            while lists.len() > 1
+                                && lists.last().map(|l| indent < l.indent).unwrap_or(false)
+                            {
+                                close_list(&mut output, line_type, &mut line_content, &mut lists);
+                                line_type = LineType::Bullet(n);
+                            }
+                            match lists.last().map(|l| indent > l.indent) {
+                                Some(true) => {
+                                    if DEBUG {
+                                        println!("Start nested list");
+                                    }
+                                    // The pending bullet is the parent of the nested list.
+                                    lists.push(ListLevel {
+                                        bullets: Vec::new(),
+                                        indent,
+                                        ordered,
+                                        parent_content: std::mem::take(&mut line_content),
+                                    });
                                 }
-                                span_content.clear(); // Ignore bullet.
-                                emit_markdown(&mut bullets, line_type, &mut line_content);
-                            } else {
-                                if DEBUG {
-                                    println!("Start list");
+                                Some(false)
+                                    if lists
+                                        .last()
+                                        .map(|l| l.ordered != ordered)
+                                        .unwrap_or(false) =>
+                                {
+                                    if DEBUG {
+                                        println!("Start sibling list");
+                                    }
+                                    // A different kind of bullet at the same indent ends the list and
+                                    // starts a new one, which shares the same parent bullet (if any).
+                                    close_list(
+                                        &mut output,
+                                        line_type,
+                                        &mut line_content,
+                                        &mut lists,
+                                    );
+                                    lists.push(ListLevel {
+                                        bullets: Vec::new(),
+                                        indent,
+                                        ordered,
+                                        parent_content: std::mem::take(&mut line_content),
+                                    });
+                                }
+                                Some(false) => {
+                                    if DEBUG {
+                                        println!("Continue list");
+                                    }
+                                    if let Some(list) = lists.last_mut() {
+                                        emit_markdown(
+                                            &mut list.bullets,
+                                            line_type,
+                                            &mut line_content,
+                                        );
+                                    }
+                                }
+                                None => {
+                                    if DEBUG {
+                                        println!("Start list");
+                                    }
+                                    emit_markdown(&mut output, line_type, &mut line_content);
+                                    lists.push(ListLevel {
+                                        bullets: Vec::new(),
+                                        indent,
+                                        ordered,
+                                        parent_content: Vec::new(),
+                                    });
                                 }
-                                span_content.clear(); // Ignore bullet.
-                                emit_markdown(&mut output, line_type, &mut line_content);
-                                list = Some(n != '*' && n != '-');
                             }
                             tokenizer_state = Tokenizer::Found(LineType::Bullet(n));
                         }
@@ -296,7 +469,7 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                         true
                     }
                     Tokenizer::Newline(line_type) if ch != '0' => {
-                        span_content.push(' '); // Newline counts as space.
+                        span_content.push(newline); // Newline counts as space (or break).
                         span_content.push(ch); // In case it's not a bullet.
                         tokenizer_state = Tokenizer::PreLi(line_type, '1');
                         true
@@ -334,7 +507,7 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                 },
                 '#' => match tokenizer_state {
                     Tokenizer::Newline(line_type) => {
-                        span_content.push(' '); // Newline counts as space.
+                        span_content.push(newline); // Newline counts as space (or break).
                         span_content.push(ch); // In case it's not a heading.
                         tokenizer_state = Tokenizer::PreH(line_type, 1);
                         true
@@ -366,16 +539,18 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                         tokenizer_state = Tokenizer::Titles(LineType::Paragraph, vec![]);
                         true
                     }
-                    Tokenizer::Table(false, titles, body, last_row) => {
-                        tokenizer_state = Tokenizer::Table(true, titles, body, last_row);
+                    Tokenizer::Table(false, titles, alignments, body, last_row) => {
+                        tokenizer_state =
+                            Tokenizer::Table(true, titles, alignments, body, last_row);
                         true
                     }
-                    Tokenizer::Table(true, titles, body, mut last_row) => {
+                    Tokenizer::Table(true, titles, alignments, body, mut last_row) => {
                         let mut column: Vec<MarkdownTag> = vec![];
                         push_span(&mut line_content, &mut span_content, None);
                         emit_markdown(&mut column, LineType::Paragraph, &mut line_content);
                         last_row.push(column);
-                        tokenizer_state = Tokenizer::Table(true, titles, body, last_row);
+                        tokenizer_state =
+                            Tokenizer::Table(true, titles, alignments, body, last_row);
                         true
                     }
                     Tokenizer::Titles(line_type, mut titles) => {
@@ -399,8 +574,8 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                             span_content.clear();
                         } else {
                             // Trim _ from the front and back of span_content.
-                            span_content = span_content[1..n].into();
-                            line_content.push(MarkdownTag::Em(span_content.drain(..).collect()));
+                            line_content.push(MarkdownTag::Em(span_content[1..n].iter().collect()));
+                            span_content.clear();
                             if DEBUG {
                                 println!("Italic done, line content is: {line_content:?}");
                             }
@@ -412,14 +587,15 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                         let start_index = span_content.len();
                         if start_index == 0 || span_content[start_index - 1].is_whitespace() {
                             tokenizer_state = Tokenizer::Italic(line_type, start_index);
-                        } else {
-                            println!("@@@ {start_index} {span_content:?}");
+                        } else if DEBUG {
+                            // For example, "snake_case".
+                            println!("Not italic, '_' is within a word");
                         }
                         span_content.push(ch);
                         true
                     }
                     Tokenizer::PreA(line_type, ']', start_index) => {
-                        emit_anchor(&mut line_content, &mut span_content, start_index);
+                        emit_anchor(&mut line_content, &mut span_content, start_index, options);
                         tokenizer_state = Tokenizer::Italic(line_type, span_content.len());
                         span_content.push(ch);
                         true
@@ -430,7 +606,9 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                         true
                     }
                     _ => {
-                        println!("@@@#2 {tokenizer_state:?}");
+                        if DEBUG {
+                            println!("Not italic in state {tokenizer_state:?}");
+                        }
                         false
                     }
                 },
@@ -441,13 +619,7 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                         true
                     }
                     Tokenizer::Newline(line_type) => {
-                        emit_pending(
-                            &mut output,
-                            line_type,
-                            &mut line_content,
-                            &mut list,
-                            &mut bullets,
-                        );
+                        emit_pending(&mut output, line_type, &mut line_content, &mut lists);
                         tokenizer_state =
                             Tokenizer::PreA(LineType::Paragraph, '[', span_content.len());
                         span_content.push(ch);
@@ -479,7 +651,7 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                 ')' => match tokenizer_state {
                     Tokenizer::PreA(line_type, '(', start_index) => {
                         span_content.push(ch);
-                        emit_anchor(&mut line_content, &mut span_content, start_index);
+                        emit_anchor(&mut line_content, &mut span_content, start_index, options);
                         tokenizer_state = Tokenizer::Found(line_type);
                         true
                     }
@@ -492,20 +664,14 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                         true
                     }
                     Tokenizer::Newline(line_type) => {
-                        span_content.push(' '); // Newline counts as space.
+                        span_content.push(newline); // Newline counts as space (or break).
                         span_content.push(ch); // In case it's not a bullet.
                         tokenizer_state = Tokenizer::PreLi(line_type, ch);
                         true
                     }
                     Tokenizer::PreLi(line_type, '*') => {
                         // It's not what it seemed to be. For example, "**This is not a bullet**".
-                        emit_pending(
-                            &mut output,
-                            line_type,
-                            &mut line_content,
-                            &mut list,
-                            &mut bullets,
-                        );
+                        emit_pending(&mut output, line_type, &mut line_content, &mut lists);
                         tokenizer_state =
                             Tokenizer::PreB(LineType::Paragraph, '2', span_content.len() - 1);
                         span_content.push(ch); // In case it's not a bold.
@@ -532,9 +698,10 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                                     span_content.clear();
                                 } else {
                                     // Trim ** from the front and back of span_content.
-                                    span_content = span_content[2..(n - 1)].into();
-                                    line_content
-                                        .push(MarkdownTag::B(span_content.drain(..).collect()));
+                                    line_content.push(MarkdownTag::B(
+                                        span_content[2..(n - 1)].iter().collect(),
+                                    ));
+                                    span_content.clear();
                                     if DEBUG {
                                         println!("Bold done, line content is: {line_content:?}");
                                     }
@@ -543,7 +710,12 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                                 true
                             }
                             Tokenizer::PreA(line_type, ']', start_index) => {
-                                emit_anchor(&mut line_content, &mut span_content, start_index);
+                                emit_anchor(
+                                    &mut line_content,
+                                    &mut span_content,
+                                    start_index,
+                                    options,
+                                );
                                 tokenizer_state =
                                     Tokenizer::PreB(line_type, '1', span_content.len());
                                 span_content.push(ch);
@@ -587,7 +759,7 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                 Tokenizer::Bold(_, _)
                 | Tokenizer::Found(_)
                 | Tokenizer::Italic(_, _)
-                | Tokenizer::Table(true, _, _, _)
+                | Tokenizer::Table(true, _, _, _, _)
                 | Tokenizer::Titles(_, _)
                 | Tokenizer::Underline(_, true, _, _) => {
                     // i.e. Bold, Found (Header, List, Paragraph), or Italic.
@@ -595,7 +767,7 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                 }
                 Tokenizer::PreA(line_type, bracket, start_index) => {
                     if bracket == ']' {
-                        emit_anchor(&mut line_content, &mut span_content, start_index);
+                        emit_anchor(&mut line_content, &mut span_content, start_index, options);
                         tokenizer_state = Tokenizer::Found(line_type);
                     }
                     span_content.push(ch);
@@ -611,7 +783,7 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                     tokenizer_state = Tokenizer::Found(line_type);
                 }
                 Tokenizer::Newline(LineType::Paragraph) => {
-                    span_content.push(' '); // Newline counts as space.
+                    span_content.push(newline); // Newline counts as space (or break).
                     span_content.push(ch);
                     tokenizer_state = Tokenizer::Found(LineType::Paragraph);
                 }
@@ -620,13 +792,7 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                 | Tokenizer::PreH(line_type, _)
                 | Tokenizer::PreLi(line_type, _) => {
                     // It's not what it seemed to be. For example, "*a This is not a bullet".
-                    emit_pending(
-                        &mut output,
-                        line_type,
-                        &mut line_content,
-                        &mut list,
-                        &mut bullets,
-                    );
+                    emit_pending(&mut output, line_type, &mut line_content, &mut lists);
                     tokenizer_state = Tokenizer::Found(LineType::Paragraph);
                     span_content.push(ch);
                 }
@@ -635,12 +801,13 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                     tokenizer_state = Tokenizer::Found(LineType::Paragraph);
                     span_content.push(ch);
                 }
-                Tokenizer::Table(false, titles, body, _last_row) => {
+                Tokenizer::Table(false, titles, alignments, body, _last_row) => {
                     emit_table(
                         &mut output,
                         &mut line_content,
                         &mut span_content,
                         titles,
+                        alignments,
                         body,
                     );
                     tokenizer_state = Tokenizer::Found(LineType::Paragraph);
@@ -652,24 +819,66 @@ pub(crate) fn tokenize(input: &str) -> Vec<MarkdownTag> {
                         println!("Rollback table");
                     }
                     let text = titles.join("|");
-                    span_content.extend(text.chars().collect::<Vec<_>>());
+                    span_content.extend(text.chars());
                     span_content.push(ch);
                     tokenizer_state = Tokenizer::Found(line_type);
                 }
             }
         }
     } // for ch
-    push_span(&mut line_content, &mut span_content, None);
-    match tokenizer_state {
-        Tokenizer::Found(line_type) | Tokenizer::Newline(line_type) => emit_pending(
-            &mut output,
-            line_type,
-            &mut line_content,
-            &mut list,
-            &mut bullets,
-        ),
-        _ => {}
+    if quoted {
+        // A trailing backslash has nothing to escape.
+        span_content.push('\\');
     }
+
+    // Input may end in the middle of a construct, e.g. "**" or "[", in which case
+    // whatever is pending is emitted as it stands.
+    let line_type = match tokenizer_state {
+        Tokenizer::Start => LineType::Paragraph,
+        Tokenizer::Bold(line_type, _)
+        | Tokenizer::Found(line_type)
+        | Tokenizer::Indent(line_type)
+        | Tokenizer::Italic(line_type, _)
+        | Tokenizer::Newline(line_type)
+        | Tokenizer::PostB(line_type, _)
+        | Tokenizer::PreB(line_type, _, _)
+        | Tokenizer::PreH(line_type, _)
+        | Tokenizer::PreLi(line_type, _) => line_type,
+        Tokenizer::PreA(line_type, bracket, start_index) => {
+            if bracket == ']' {
+                emit_anchor(&mut line_content, &mut span_content, start_index, options);
+            }
+            line_type
+        }
+        Tokenizer::Titles(line_type, titles) => {
+            // It wasn't a table after all.  Newline counts as space.
+            let text: String = titles.iter().map(|title| format!("{title}|")).collect();
+            span_content.splice(0..0, format!(" |{text}").chars());
+            line_type
+        }
+        Tokenizer::Underline(line_type, _, titles, _) => {
+            let text: String = titles.iter().map(|title| format!("{title}|")).collect();
+            span_content.splice(0..0, format!(" |{text} ").chars());
+            line_type
+        }
+        Tokenizer::Table(_, titles, alignments, mut body, last_row) => {
+            if !last_row.is_empty() {
+                body.push(last_row);
+            }
+            emit_table(
+                &mut output,
+                &mut line_content,
+                &mut span_content,
+                titles,
+                alignments,
+                body,
+            );
+            LineType::Paragraph
+        }
+    };
+    push_span(&mut line_content, &mut span_content, None);
+    emit_pending(&mut output, line_type, &mut line_content, &mut lists);
+    resolve_emoji(&mut output, &options.emoji);
     output
 }
 
@@ -679,11 +888,19 @@ pub(crate) enum LineType {
     None,
     Bullet(char),
     Heading(usize),
-    List(bool),
     Paragraph,
     Table,
 }
 
+/// A list which is still open.  Nested lists are pushed on top of their parents.
+#[derive(Debug)]
+struct ListLevel {
+    bullets: Vec<MarkdownTag>,
+    indent: usize,
+    ordered: bool,
+    parent_content: Vec<MarkdownTag>,
+}
+
 /// Tokenizer states
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub(crate) enum Tokenizer {
@@ -701,6 +918,7 @@ pub(crate) enum Tokenizer {
     Table(
         bool,
         Vec<String>,
+        Vec<MarkdownAlign>,
         Vec<Vec<Vec<MarkdownTag>>>,
         Vec<Vec<MarkdownTag>>,
     ),
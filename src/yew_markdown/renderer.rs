@@ -1,13 +1,25 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+use super::emoji::default_emoji;
+use std::collections::HashMap;
 use yew::{html, Html};
 
 /// Markdown renderer options.
 pub struct MarkdownOptions {
+    /// Schemes of links and images that are allowed, e.g. `"https"`.  Others, such as
+    /// `javascript:`, are rendered as plain text.  Relative URLs are always allowed.
+    pub allowed_link_schemes: Vec<String>,
     /// fn(href, content) -> Html
     #[allow(clippy::type_complexity)]
     pub components: Box<dyn Fn(&str, &str) -> Option<Html>>,
+    /// Values of variables tested by `#if` preprocessor directives.
+    pub defines: HashMap<String, String>,
+    /// Emoji for `:name:` shortcodes, e.g. `"smile"` to `"😄"`.  Empty to disable.
+    pub emoji: HashMap<String, String>,
+    /// Treat every newline within a paragraph as `<br/>` instead of a space.
+    /// (Otherwise, only a line that ends with two spaces is followed by `<br/>`.)
+    pub hard_breaks: bool,
     /// Start headings with specified level instead of `<h1>`.
     pub h_level: usize,
 }
@@ -15,28 +27,139 @@ pub struct MarkdownOptions {
 impl Default for MarkdownOptions {
     fn default() -> Self {
         Self {
+            allowed_link_schemes: ["http", "https", "mailto"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
             components: Box::new(|_, _| None),
+            defines: HashMap::new(),
+            emoji: default_emoji(),
+            hard_breaks: false,
             h_level: 3,
         }
     }
 }
 
+impl MarkdownOptions {
+    /// Create a markdown options builder, starting from the defaults.
+    pub fn builder() -> MarkdownOptionsBuilder {
+        MarkdownOptionsBuilder::default()
+    }
+}
+
+/// Markdown options builder.
+#[derive(Default)]
+pub struct MarkdownOptionsBuilder {
+    options: MarkdownOptions,
+}
+
+impl MarkdownOptionsBuilder {
+    /// Sets the schemes of links and images that are allowed, e.g. `["https"]`.
+    pub fn allowed_link_schemes(mut self, schemes: &[&str]) -> Self {
+        self.options.allowed_link_schemes = schemes.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Returns the markdown options.
+    pub fn build(self) -> MarkdownOptions {
+        self.options
+    }
+
+    /// Sets the fn(href, content) which may render a link as a component.
+    pub fn components(mut self, components: impl Fn(&str, &str) -> Option<Html> + 'static) -> Self {
+        self.options.components = Box::new(components);
+        self
+    }
+
+    /// Defines a variable tested by `#if` preprocessor directives.
+    pub fn define(mut self, name: &str, value: &str) -> Self {
+        self.options
+            .defines
+            .insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Sets the emoji for `:name:` shortcodes, replacing the built-in ones.
+    pub fn emoji(mut self, emoji: HashMap<String, String>) -> Self {
+        self.options.emoji = emoji;
+        self
+    }
+
+    /// Sets the level of the first heading, e.g. `1` for `<h1>`.
+    pub fn h_level(mut self, h_level: usize) -> Self {
+        self.options.h_level = h_level;
+        self
+    }
+
+    /// Treat every newline within a paragraph as `<br/>` instead of a space.
+    pub fn hard_breaks(mut self, hard_breaks: bool) -> Self {
+        self.options.hard_breaks = hard_breaks;
+        self
+    }
+}
+
+/// Alignment of a table column.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MarkdownAlign {
+    /// Left aligned (the default).
+    #[default]
+    Left,
+    /// Centered.
+    Center,
+    /// Right aligned.
+    Right,
+}
+
+impl MarkdownAlign {
+    fn as_style(&self) -> &'static str {
+        match self {
+            Self::Left => "text-align:left",
+            Self::Center => "text-align:center",
+            Self::Right => "text-align:right",
+        }
+    }
+}
+
 /// HTML tags that are created from markdown.
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub(crate) enum MarkdownTag {
+pub enum MarkdownTag {
+    /// Anchor with `href` and text.
     A(String, String),
+    /// Bold text.
     B(String),
+    /// Line break.
+    Br,
+    /// Emphasized (italic) text.
     Em(String),
+    /// Reference to the footnote with the specified number (starting at 1), and which
+    /// reference to that footnote it is (also starting at 1).
+    FootnoteRef(usize, usize),
+    /// Footnotes in order, each with content, which follow the rest of the output.
+    ///
+    /// Footnotes and their references are rendered with `id`s like `fn1` and `fnref1`,
+    /// which are page-global, so render at most one document with footnotes per page.
+    Footnotes(Vec<Vec<MarkdownTag>>),
+    /// Heading with level (starting at 1) and content.
     H(usize, Vec<MarkdownTag>),
+    /// Image with `src` and alt text.
+    Img(String, String),
+    /// List item.
     Li(Vec<MarkdownTag>),
+    /// Ordered list of `Li`.
     Ol(Vec<MarkdownTag>),
+    /// Paragraph.
     P(Vec<MarkdownTag>),
+    /// Plain text.
     Span(String),
-    Table(Vec<String>, Vec<Vec<Vec<MarkdownTag>>>),
+    /// Table with column titles, column alignments and rows of cells.
+    Table(Vec<String>, Vec<MarkdownAlign>, Vec<Vec<Vec<MarkdownTag>>>),
+    /// Unordered list of `Li`.
     Ul(Vec<MarkdownTag>),
 }
 
 /// Creates Yew object hierarchy by recursively walking markdown tokens.
+/// Text is always emitted as Yew text nodes (or attributes), never as raw HTML,
+/// so Yew takes care of escaping it.
 pub(crate) fn yew_html(tokens: Vec<MarkdownTag>, options: &MarkdownOptions) -> Html {
     tokens
         .into_iter()
@@ -51,9 +174,31 @@ pub(crate) fn yew_html(tokens: Vec<MarkdownTag>, options: &MarkdownOptions) -> H
             MarkdownTag::B(text) => html! {
                 <b>{text}</b>
             },
+            MarkdownTag::Br => html! {
+                <br/>
+            },
             MarkdownTag::Em(text) => html! {
                 <em>{text}</em>
             },
+            MarkdownTag::FootnoteRef(n, k) => {
+                let id = if k == 1 {
+                    format!("fnref{n}")
+                } else {
+                    format!("fnref{n}-{k}")
+                };
+                html! {
+                    <sup><a href={format!("#fn{n}")} {id}>{n}</a></sup>
+                }
+            }
+            MarkdownTag::Footnotes(notes) => html! {
+                <ol class="footnotes">
+                    {
+                        notes.into_iter().enumerate().map(|(i, note)| html! {
+                            <li id={format!("fn{}", i + 1)}>{yew_html(note, options)}</li>
+                        }).collect::<Html>()
+                    }
+                </ol>
+            },
             MarkdownTag::H(n, content) => {
                 let k = options.h_level + n - 1;
                 match k {
@@ -65,6 +210,9 @@ pub(crate) fn yew_html(tokens: Vec<MarkdownTag>, options: &MarkdownOptions) -> H
                     _ => html! {<h6>{yew_html(content, options)}</h6>},
                 }
             }
+            MarkdownTag::Img(src, alt) => html! {
+                <img {src} {alt}/>
+            },
             MarkdownTag::Li(content) => html! {
                 <li>{yew_html(content, options)}</li>
             },
@@ -77,11 +225,15 @@ pub(crate) fn yew_html(tokens: Vec<MarkdownTag>, options: &MarkdownOptions) -> H
             MarkdownTag::Span(text) => html! {
                 {text}
             },
-            MarkdownTag::Table(titles, body) => html! {
+            MarkdownTag::Table(titles, alignments, body) => html! {
                 <table>
                     <thead>
                         <tr>
-                            { titles.iter().map(|t| html!{ <th>{t}</th> }).collect::<Html>() }
+                            {
+                                titles.iter().zip(alignments.iter()).map(|(t, a)| html!{
+                                    <th style={a.as_style()}>{t}</th>
+                                }).collect::<Html>()
+                            }
                         </tr>
                     </thead>
                     <tbody>
@@ -89,8 +241,9 @@ pub(crate) fn yew_html(tokens: Vec<MarkdownTag>, options: &MarkdownOptions) -> H
                             body.into_iter().map(|row| html! {
                                 <tr>
                                 {
-                                    row.into_iter().map(|col| html! {
-                                        <td>
+                                    // A row may have more cells than there are titles.
+                                    row.into_iter().enumerate().map(|(i, col)| html! {
+                                        <td style={alignments.get(i).copied().unwrap_or_default().as_style()}>
                                             {
                                                 if col.len() == 1 {
                                                     match &col[0] {
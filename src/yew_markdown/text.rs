@@ -0,0 +1,72 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{parse, MarkdownOptions, MarkdownTag};
+
+/// Parse markdown `input` and return its plain text, e.g. for search indexing or meta
+/// descriptions.  If `max_chars` is specified, longer text is truncated at a word boundary
+/// and ends with an ellipsis.
+pub fn markdown_to_text(
+    input: &str,
+    options: &MarkdownOptions,
+    max_chars: Option<usize>,
+) -> String {
+    let mut text = String::new();
+    push_text(&parse(input, options), &mut text);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match max_chars {
+        Some(n) if text.chars().count() > n => {
+            let mut excerpt: String = text.chars().take(n.saturating_sub(1)).collect();
+            let at_boundary = text[excerpt.len()..].starts_with(' ');
+            if !at_boundary {
+                if let Some(i) = excerpt.rfind(' ') {
+                    excerpt.truncate(i);
+                }
+            }
+            excerpt.push('…');
+            excerpt
+        }
+        _ => text,
+    }
+}
+
+/// Appends the text of `tokens`, dropping formatting.  Blocks are separated by spaces.
+pub(crate) fn push_text(tokens: &[MarkdownTag], text: &mut String) {
+    for token in tokens {
+        match token {
+            MarkdownTag::A(_, s)
+            | MarkdownTag::B(s)
+            | MarkdownTag::Em(s)
+            | MarkdownTag::Span(s) => text.push_str(s),
+            MarkdownTag::Br => text.push(' '),
+            MarkdownTag::FootnoteRef(_, _) | MarkdownTag::Img(_, _) => {}
+            MarkdownTag::H(_, content)
+            | MarkdownTag::Li(content)
+            | MarkdownTag::Ol(content)
+            | MarkdownTag::P(content)
+            | MarkdownTag::Ul(content) => {
+                text.push(' ');
+                push_text(content, text);
+                text.push(' ');
+            }
+            MarkdownTag::Footnotes(notes) => {
+                for note in notes {
+                    text.push(' ');
+                    push_text(note, text);
+                }
+                text.push(' ');
+            }
+            MarkdownTag::Table(titles, _, rows) => {
+                for title in titles {
+                    text.push(' ');
+                    text.push_str(title);
+                }
+                for cell in rows.iter().flatten() {
+                    text.push(' ');
+                    push_text(cell, text);
+                }
+                text.push(' ');
+            }
+        }
+    }
+}